@@ -55,6 +55,10 @@ pub async fn run_api_server(
             .configure(controller::init_plan_logs_controller)
             .configure(controller::init_metrics_receiver_controller)
             .configure(controller::init_definition_controller)
+            .configure(controller::init_expression_controller)
+            .configure(controller::init_pending_action_controller)
+            .configure(controller::init_action_controller)
+            .configure(controller::init_maintenance_window_controller)
     })
     .workers(1)
     .bind((host.clone(), port));
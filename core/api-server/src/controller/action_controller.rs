@@ -0,0 +1,58 @@
+use crate::app_state::AppState;
+use actix_web::{get, web, HttpResponse, Responder};
+use tracing::{debug, error};
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_action_by_id);
+}
+
+// Get the status and the result of a manual run (POST /api/run-plan)
+#[get("/api/actions/{id}")]
+async fn get_action_by_id(
+    path: web::Path<String>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    debug!("Getting action: {}", id);
+    let action = app_state.data_layer.get_action(&id).await;
+    match action {
+        Ok(Some(action)) => HttpResponse::Ok().json(action),
+        Ok(None) => HttpResponse::NotFound().body("Action not found"),
+        Err(error) => {
+            error!("Failed to get action: {:?}", error);
+            HttpResponse::InternalServerError().body(format!("{:?}", error))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::init;
+    use crate::utils::test_utils::get_app_state_for_test;
+    use actix_web::{http::StatusCode, test, App};
+    use data_layer::types::action::ActionDefinition;
+
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_get_action_by_id() {
+        let app_state = get_app_state_for_test().await;
+        let action = app_state
+            .data_layer
+            .add_action("plan_id", "plan_item_id", &Default::default())
+            .await
+            .unwrap();
+        let app = test::init_service(App::new().app_data(app_state).configure(init)).await;
+
+        let req = test::TestRequest::get()
+            .uri(format!("/api/actions/{}", action.id).as_str())
+            .to_request();
+        let resp: ActionDefinition = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp, action);
+
+        let req = test::TestRequest::get()
+            .uri("/api/actions/unknown")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
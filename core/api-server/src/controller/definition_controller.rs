@@ -1,6 +1,8 @@
 use crate::app_state::AppState;
 use actix_web::{post, web, HttpResponse, Responder};
+use data_layer::reader::definition_validator::DefinitionValidationErrors;
 use serde::Deserialize;
+use serde_json::json;
 use tracing::{debug, error};
 use validator::Validate;
 
@@ -23,6 +25,13 @@ async fn post_defintions(
         .data_layer
         .add_definitions(request.yaml.as_str())
         .await;
+    if let Err(error) = &result {
+        // The definitions are invalid (e.g. an expression that cannot be compiled)
+        if let Some(validation_errors) = error.downcast_ref::<DefinitionValidationErrors>() {
+            error!("Invalid definitions: {}", validation_errors);
+            return HttpResponse::BadRequest().json(json!({ "errors": validation_errors.0 }));
+        }
+    }
     if result.is_err() {
         error!("Failed to add plans: {:?}", result);
         return HttpResponse::InternalServerError().body(format!("{:?}", result));
@@ -131,4 +140,41 @@ plans:
         let response = test::call_service(&app, req).await;
         assert!(!response.status().is_success());
     }
+
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_post_definitions_with_invalid_expression() {
+        let app_state = get_app_state_for_test().await;
+        let app = test::init_service(App::new().app_data(app_state).configure(init)).await;
+
+        let request = json!({
+            "yaml":
+r#"
+---
+kind: ScalingPlan
+id: scaling_plan_id
+metadata: {}
+plans:
+  - id: plan_id
+    expression: "get({ metric_id: 'unknown_metric_id', stats: 'avg' }) >= (30"
+    priority: 1
+    scaling_components: []
+"#
+        });
+        let req = test::TestRequest::post()
+            .uri("/api/definitions")
+            .set_json(&request)
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(response).await;
+        let errors = body["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0]["document_index"], 0);
+        assert_eq!(errors[0]["plan_item_id"], "plan_id");
+        assert_eq!(
+            errors[1]["message"],
+            "Unknown metric_id in get(): unknown_metric_id"
+        );
+    }
 }
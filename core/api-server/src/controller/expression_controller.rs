@@ -0,0 +1,88 @@
+use crate::app_state::AppState;
+use actix_web::{post, web, HttpResponse, Responder};
+use data_layer::types::planner_request::{
+    ExpressionEvaluateRequest, PlannerRequest, PlannerUnavailableError,
+};
+use tracing::{debug, error};
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(post_evaluate_expression);
+}
+
+// Evaluate an expression with the current metrics data and get the result, the values of get() and the error
+#[post("/api/expressions/evaluate")]
+async fn post_evaluate_expression(
+    request: web::Json<ExpressionEvaluateRequest>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    debug!("Evaluating expression: {}", request.expression);
+    let result = app_state
+        .data_layer
+        .send_planner_request(PlannerRequest::EvaluateExpression(request.into_inner()))
+        .await;
+    if let Err(error) = result.as_ref() {
+        // The scaling planners run only in the leader
+        if error.is::<PlannerUnavailableError>() {
+            return HttpResponse::ServiceUnavailable().body(error.to_string());
+        }
+    }
+    if result.is_err() {
+        error!("Failed to evaluate expression: {:?}", result);
+        return HttpResponse::InternalServerError().body(format!("{:?}", result));
+    }
+    HttpResponse::Ok().json(result.unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::init;
+    use crate::utils::test_utils::get_app_state_for_test;
+    use actix_web::{test, App};
+    use data_layer::types::planner_request::PlannerRequest;
+    use serde_json::json;
+
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_post_evaluate_expression() {
+        let app_state = get_app_state_for_test().await;
+        let app = test::init_service(App::new().app_data(app_state.clone()).configure(init)).await;
+        let body = json!({
+            "expression": "get({ metric_id: 'metric_id' }) > $threshold",
+            "variables": { "threshold": 10 }
+        });
+
+        // No handler for the planner requests
+        let req = test::TestRequest::post()
+            .uri("/api/expressions/evaluate")
+            .set_json(body.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_server_error());
+
+        // Fake handler that returns the expression and the variables
+        let mut receiver = app_state
+            .data_layer
+            .take_planner_request_receiver()
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            while let Some((request, responder)) = receiver.recv().await {
+                let PlannerRequest::EvaluateExpression(request) = request else {
+                    continue;
+                };
+                let _ = responder.send(Ok(json!({
+                    "result": true,
+                    "expression_values": [{ "$threshold": request.variables["threshold"] }],
+                    "error": null,
+                })));
+            }
+        });
+        let req = test::TestRequest::post()
+            .uri("/api/expressions/evaluate")
+            .set_json(body)
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["result"], true);
+        assert_eq!(resp["expression_values"][0]["$threshold"], 10);
+    }
+}
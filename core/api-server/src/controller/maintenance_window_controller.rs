@@ -0,0 +1,139 @@
+use crate::app_state::AppState;
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, error};
+use validator::Validate;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_maintenance_windows)
+        .service(post_maintenance_window_yaml)
+        .service(delete_maintenance_window_by_id);
+}
+
+// The maintenance windows with "active_until" (RFC 3339) if the window is open now
+#[get("/api/maintenance-windows")]
+async fn get_maintenance_windows(app_state: web::Data<AppState>) -> impl Responder {
+    let maintenance_windows = app_state.data_layer.get_all_maintenance_windows().await;
+    if maintenance_windows.is_err() {
+        error!(
+            "Failed to get maintenance windows: {:?}",
+            maintenance_windows
+        );
+        return HttpResponse::InternalServerError().body(format!("{:?}", maintenance_windows));
+    }
+    let now = Utc::now();
+    let maintenance_windows = maintenance_windows
+        .unwrap()
+        .into_iter()
+        .map(|maintenance_window| {
+            let active_until = maintenance_window
+                .get_active_until(now)
+                .ok()
+                .flatten()
+                .filter(|_| maintenance_window.enabled)
+                .map(|active_until| active_until.to_rfc3339());
+            let mut maintenance_window = json!(maintenance_window);
+            maintenance_window["active_until"] = json!(active_until);
+            maintenance_window
+        })
+        .collect::<Vec<serde_json::Value>>();
+    HttpResponse::Ok().json(maintenance_windows)
+}
+
+#[derive(Deserialize, Validate)]
+struct PostMaintenanceWindowYamlRequest {
+    yaml: String,
+}
+
+#[post("/api/maintenance-windows/yaml")]
+async fn post_maintenance_window_yaml(
+    request: web::Json<PostMaintenanceWindowYamlRequest>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    debug!("Adding maintenance windows: {:?}", request.yaml);
+    let result = app_state
+        .data_layer
+        .add_maintenance_window_yaml(request.yaml.as_str())
+        .await;
+    if result.is_err() {
+        error!("Failed to add maintenance windows: {:?}", result);
+        return HttpResponse::BadRequest().body(format!("{:?}", result));
+    }
+    HttpResponse::Ok().body("ok")
+}
+
+#[delete("/api/maintenance-windows/{db_id}")]
+async fn delete_maintenance_window_by_id(
+    db_id: web::Path<String>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    debug!("Deleting maintenance window by id: {}", db_id);
+    let result = app_state
+        .data_layer
+        .delete_maintenance_window(db_id.into_inner())
+        .await;
+    if result.is_err() {
+        error!("Failed to delete maintenance window: {:?}", result);
+        return HttpResponse::InternalServerError().body(format!("{:?}", result));
+    }
+    HttpResponse::Ok().body("ok")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::init;
+    use crate::utils::test_utils::get_app_state_for_test;
+    use actix_web::{http::StatusCode, test, App};
+    use serde_json::json;
+
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_maintenance_windows() {
+        let app_state = get_app_state_for_test().await;
+        let app = test::init_service(App::new().app_data(app_state).configure(init)).await;
+
+        let yaml = r#"
+kind: MaintenanceWindow
+id: always
+cron_expression: "* * * * *"
+duration: 2m
+---
+kind: MaintenanceWindow
+id: past
+start_time: "2024-03-25T01:00:00Z"
+end_time: "2024-03-25T03:00:00Z"
+"#;
+        let req = test::TestRequest::post()
+            .uri("/api/maintenance-windows/yaml")
+            .set_json(json!({ "yaml": yaml }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let req = test::TestRequest::get()
+            .uri("/api/maintenance-windows")
+            .to_request();
+        let resp: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+        let always = resp.iter().find(|window| window["id"] == "always").unwrap();
+        assert!(always["active_until"].is_string());
+        let past = resp.iter().find(|window| window["id"] == "past").unwrap();
+        assert!(past["active_until"].is_null());
+
+        // An invalid schedule
+        let req = test::TestRequest::post()
+            .uri("/api/maintenance-windows/yaml")
+            .set_json(json!({ "yaml": "kind: MaintenanceWindow\nid: invalid\nduration: 2h\n" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let db_id = past["db_id"].as_str().unwrap();
+        let req = test::TestRequest::delete()
+            .uri(format!("/api/maintenance-windows/{}", db_id).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+    }
+}
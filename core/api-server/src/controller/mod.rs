@@ -1,13 +1,21 @@
+pub mod action_controller;
 pub mod definition_controller;
+pub mod expression_controller;
+pub mod maintenance_window_controller;
 pub mod metric_controller;
 pub mod metrics_data_controller;
+pub mod pending_action_controller;
 pub mod plan_controller;
 pub mod plan_logs_controller;
 pub mod scaling_component_controller;
 
+pub use action_controller::init as init_action_controller;
 pub use definition_controller::init as init_definition_controller;
+pub use expression_controller::init as init_expression_controller;
+pub use maintenance_window_controller::init as init_maintenance_window_controller;
 pub use metric_controller::init as init_metric_controller;
 pub use metrics_data_controller::init as init_metrics_receiver_controller;
+pub use pending_action_controller::init as init_pending_action_controller;
 pub use plan_controller::init as init_plan_controller;
 pub use plan_logs_controller::init as init_plan_logs_controller;
 pub use scaling_component_controller::init as init_scaling_component_controller;
@@ -0,0 +1,235 @@
+use crate::app_state::AppState;
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::Utc;
+use data_layer::types::{
+    pending_action::{PendingActionDefinition, PendingActionStatus},
+    planner_request::{PlannerRequest, PlannerUnavailableError},
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, error};
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_pending_actions)
+        .service(post_approve_pending_action)
+        .service(post_reject_pending_action);
+}
+
+#[derive(Debug, Deserialize)]
+struct GetPendingActionsRequest {
+    // PENDING, APPROVED, REJECTED or EXPIRED (all statuses if it is not set)
+    status: Option<PendingActionStatus>,
+}
+
+// Get the pending action that can still be approved or rejected. Otherwise, the response to return.
+async fn get_decidable_pending_action(
+    app_state: &AppState,
+    id: &str,
+) -> Result<PendingActionDefinition, HttpResponse> {
+    // The actions that passed expires_at are expired before they are decided
+    if let Err(error) = app_state
+        .data_layer
+        .expire_pending_actions(Utc::now())
+        .await
+    {
+        error!("Failed to expire pending actions: {:?}", error);
+        return Err(HttpResponse::InternalServerError().body(format!("{:?}", error)));
+    }
+    let pending_action = app_state.data_layer.get_pending_action(id).await;
+    let pending_action = match pending_action {
+        Ok(Some(pending_action)) => pending_action,
+        Ok(None) => return Err(HttpResponse::NotFound().body("Pending action not found")),
+        Err(error) => {
+            error!("Failed to get pending action: {:?}", error);
+            return Err(HttpResponse::InternalServerError().body(format!("{:?}", error)));
+        }
+    };
+    if pending_action.status != PendingActionStatus::Pending.to_string() {
+        return Err(HttpResponse::Conflict().body(format!(
+            "The pending action is already {}",
+            pending_action.status
+        )));
+    }
+    Ok(pending_action)
+}
+
+#[get("/api/pending-actions")]
+async fn get_pending_actions(
+    query: web::Query<GetPendingActionsRequest>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    debug!("Getting pending actions: {:?}", query);
+    if let Err(error) = app_state
+        .data_layer
+        .expire_pending_actions(Utc::now())
+        .await
+    {
+        error!("Failed to expire pending actions: {:?}", error);
+        return HttpResponse::InternalServerError().body(format!("{:?}", error));
+    }
+    let pending_actions = app_state.data_layer.get_pending_actions(query.status).await;
+    if pending_actions.is_err() {
+        error!("Failed to get pending actions: {:?}", pending_actions);
+        return HttpResponse::InternalServerError().body(format!("{:?}", pending_actions));
+    }
+    HttpResponse::Ok().json(pending_actions.unwrap())
+}
+
+// Approve a pending action. The main application applies it to the scaling components and returns the results.
+#[post("/api/pending-actions/{id}/approve")]
+async fn post_approve_pending_action(
+    path: web::Path<String>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    debug!("Approving pending action: {}", id);
+    if let Err(response) = get_decidable_pending_action(&app_state, &id).await {
+        return response;
+    }
+    let result = app_state
+        .data_layer
+        .send_planner_request(PlannerRequest::ApprovePendingAction(id))
+        .await;
+    if let Err(error) = result.as_ref() {
+        // The scaling planners run only in the leader
+        if error.is::<PlannerUnavailableError>() {
+            return HttpResponse::ServiceUnavailable().body(error.to_string());
+        }
+    }
+    if result.is_err() {
+        error!("Failed to approve pending action: {:?}", result);
+        return HttpResponse::InternalServerError().body(format!("{:?}", result));
+    }
+    HttpResponse::Ok().json(json!({ "results": result.unwrap() }))
+}
+
+#[post("/api/pending-actions/{id}/reject")]
+async fn post_reject_pending_action(
+    path: web::Path<String>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    debug!("Rejecting pending action: {}", id);
+    if let Err(response) = get_decidable_pending_action(&app_state, &id).await {
+        return response;
+    }
+    let result = app_state
+        .data_layer
+        .update_pending_action_status(
+            &id,
+            PendingActionStatus::Pending,
+            PendingActionStatus::Rejected,
+        )
+        .await;
+    match result {
+        Ok(true) => HttpResponse::Ok().json(json!({ "message": "Pending action rejected" })),
+        // Approved or expired in the meantime
+        Ok(false) => HttpResponse::Conflict().body("The pending action is not pending"),
+        Err(error) => {
+            error!("Failed to reject pending action: {:?}", error);
+            HttpResponse::InternalServerError().body(format!("{:?}", error))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::init;
+    use crate::utils::test_utils::get_app_state_for_test;
+    use actix_web::{http::StatusCode, test, App};
+    use chrono::{Duration, Utc};
+    use data_layer::types::{
+        pending_action::PendingActionDefinition, plan_item_definition::PlanItemDefinition,
+        planner_request::PlannerRequest,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn get_plan_item() -> PlanItemDefinition {
+        serde_json::from_value(json!({
+            "id": "plan_item_id",
+            "expression": "true",
+            "scaling_components": [{ "component_id": "component_id", "replicas": 3 }],
+            "ui": null,
+            "require_approval": true,
+        }))
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_pending_actions() {
+        let app_state = get_app_state_for_test().await;
+        let app = test::init_service(App::new().app_data(app_state.clone()).configure(init)).await;
+        let scaling_components = vec![HashMap::from([
+            ("component_id".to_string(), json!("component_id")),
+            ("replicas".to_string(), json!(3)),
+        ])];
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let pending_action = app_state
+                .data_layer
+                .add_pending_action(
+                    "plan_db_id",
+                    "plan_id",
+                    &get_plan_item(),
+                    &scaling_components,
+                    Utc::now() + Duration::hours(1),
+                )
+                .await
+                .unwrap();
+            ids.push(pending_action.id);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/pending-actions?status=PENDING")
+            .to_request();
+        let resp: Vec<PendingActionDefinition> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 2);
+
+        // Reject
+        let req = test::TestRequest::post()
+            .uri(format!("/api/pending-actions/{}/reject", ids[0]).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let req = test::TestRequest::post()
+            .uri(format!("/api/pending-actions/{}/approve", ids[0]).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        // Approve with a fake handler of the main application
+        let mut receiver = app_state
+            .data_layer
+            .take_planner_request_receiver()
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            while let Some((request, responder)) = receiver.recv().await {
+                let PlannerRequest::ApprovePendingAction(id) = request else {
+                    continue;
+                };
+                let _ = responder.send(Ok(json!([{ "id": id, "status": "SUCCESS" }])));
+            }
+        });
+        let req = test::TestRequest::post()
+            .uri(format!("/api/pending-actions/{}/approve", ids[1]).as_str())
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["results"][0]["id"], ids[1]);
+
+        let req = test::TestRequest::post()
+            .uri("/api/pending-actions/unknown/approve")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/api/pending-actions?status=REJECTED")
+            .to_request();
+        let resp: Vec<PendingActionDefinition> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 1);
+        assert_eq!(resp[0].id, ids[0]);
+    }
+}
@@ -1,16 +1,30 @@
 use crate::app_state::AppState;
 use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use chrono::Utc;
+use data_layer::types::{
+    action::ActionOverrides,
+    cron_schedule::CronSchedule,
+    plan_pause::PlanPauseDefinition,
+    planner_request::{BacktestRequest, PlannerRequest},
+};
 use serde::Deserialize;
+use serde_json::json;
 use tracing::{debug, error};
 use validator::Validate;
 
+// The number of the next fire times of a cron expression in the plan responses
+const NEXT_FIRE_TIMES_COUNT: usize = 5;
+
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(get_plans)
         .service(get_plan_by_id)
         .service(post_plan_yaml)
         // .service(put_plan_by_id)
         .service(delete_plan_by_id)
-        .service(run_plan);
+        .service(run_plan)
+        .service(pause_plan)
+        .service(resume_plan)
+        .service(post_backtest);
 }
 
 #[get("/api/plans")]
@@ -20,7 +34,18 @@ async fn get_plans(app_state: web::Data<AppState>) -> impl Responder {
         error!("Failed to get plans: {:?}", plans);
         return HttpResponse::InternalServerError().body(format!("{:?}", plans));
     }
-    HttpResponse::Ok().json(plans.unwrap())
+    let plan_pauses = app_state.data_layer.get_plan_pauses().await;
+    if plan_pauses.is_err() {
+        error!("Failed to get plan pauses: {:?}", plan_pauses);
+        return HttpResponse::InternalServerError().body(format!("{:?}", plan_pauses));
+    }
+    let plan_pauses = plan_pauses.unwrap();
+    let mut plans = plans.unwrap();
+    for plan in plans.iter_mut() {
+        add_next_fire_times(plan);
+        add_pause(plan, &plan_pauses);
+    }
+    HttpResponse::Ok().json(plans)
 }
 
 #[get("/api/plans/{db_id}")]
@@ -39,9 +64,57 @@ async fn get_plan_by_id(
     }
     let plan = plan.unwrap();
     debug!("Got plan: {:?}", plan);
+    let plan_pause = app_state.data_layer.get_plan_pause(&plan.id).await;
+    if plan_pause.is_err() {
+        error!("Failed to get plan pause: {:?}", plan_pause);
+        return HttpResponse::InternalServerError().body(format!("{:?}", plan_pause));
+    }
+    let plan_pauses: Vec<PlanPauseDefinition> = plan_pause.unwrap().into_iter().collect();
+    let mut plan = json!(plan);
+    add_next_fire_times(&mut plan);
+    add_pause(&mut plan, &plan_pauses);
     HttpResponse::Ok().json(plan)
 }
 
+// Add "paused" and "paused_until" (RFC 3339, null until it is resumed) to a plan
+fn add_pause(plan: &mut serde_json::Value, plan_pauses: &[PlanPauseDefinition]) {
+    let plan_pause = plan["id"].as_str().and_then(|plan_id| {
+        plan_pauses
+            .iter()
+            .find(|plan_pause| plan_pause.plan_id == plan_id)
+    });
+    plan["paused"] = json!(plan_pause.is_some());
+    plan["paused_until"] = json!(plan_pause.and_then(|plan_pause| plan_pause.paused_until.clone()));
+}
+
+// Add "next_fire_times" (RFC 3339) to each plan item that has a cron expression
+fn add_next_fire_times(plan: &mut serde_json::Value) {
+    let plan_timezone = plan["metadata"]["timezone"].as_str().map(str::to_string);
+    let Some(plan_items) = plan["plans"].as_array_mut() else {
+        return;
+    };
+    let now = Utc::now();
+    for plan_item in plan_items.iter_mut() {
+        let Some(cron_expression) = plan_item["cron_expression"].as_str() else {
+            continue;
+        };
+        // The timezone of the plan item overrides the timezone of the plan
+        let timezone = plan_item["timezone"].as_str().or(plan_timezone.as_deref());
+        let next_fire_times = match CronSchedule::parse(cron_expression, timezone) {
+            Ok(schedule) => schedule
+                .upcoming_after(&now, NEXT_FIRE_TIMES_COUNT)
+                .iter()
+                .map(|datetime| datetime.to_rfc3339())
+                .collect::<Vec<String>>(),
+            Err(error) => {
+                debug!("Failed to get the next fire times: {:?}", error);
+                Vec::new()
+            }
+        };
+        plan_item["next_fire_times"] = json!(next_fire_times);
+    }
+}
+
 #[derive(Deserialize, Validate)]
 struct PostPlanYamlRequest {
     yaml: String,
@@ -116,31 +189,153 @@ async fn delete_plan_by_id(
 struct RunPlanRequest {
     plan_id: String,
     plan_item_id: String,
+    // The params that override the metadata of the scaling components in this run (e.g. { "k8s_deployment": { "replicas": 40 } })
+    #[serde(default)]
+    overrides: ActionOverrides,
 }
+// Queue a manual run of a plan item. The status and the result can be followed with GET /api/actions/{id}.
 #[post("/api/run-plan")]
 async fn run_plan(
     request: web::Json<RunPlanRequest>,
     app_state: web::Data<AppState>,
 ) -> impl Responder {
     debug!("Running plan: {:?}", request);
+    // Only the enabled plans have a scaling planner that runs the action
+    let plans = app_state.data_layer.get_enabled_plans().await;
+    if plans.is_err() {
+        error!("Failed to get plans: {:?}", plans);
+        return HttpResponse::InternalServerError().body(format!("{:?}", plans));
+    }
+    let plans = plans.unwrap();
+    let plan_item = plans
+        .iter()
+        .filter(|plan| plan.id == request.plan_id)
+        .flat_map(|plan| plan.plans.iter())
+        .find(|plan_item| plan_item.id == request.plan_item_id);
+    let Some(plan_item) = plan_item else {
+        return HttpResponse::NotFound().body("Plan item not found in the enabled plans");
+    };
+    // The overrides can only change the scaling components of the plan item
+    for component_id in request.overrides.keys() {
+        let component_exists = plan_item
+            .scaling_components
+            .iter()
+            .any(|metadata| metadata["component_id"].as_str() == Some(component_id.as_str()));
+        if !component_exists {
+            return HttpResponse::BadRequest().body(format!(
+                "Scaling component {} is not in plan_item: {}",
+                component_id, plan_item.id
+            ));
+        }
+    }
+
     let result = app_state
         .data_layer
-        .send_plan_action(request.plan_id.clone(), request.plan_item_id.clone());
-
+        .send_plan_action(
+            request.plan_id.clone(),
+            request.plan_item_id.clone(),
+            &request.overrides,
+        )
+        .await;
     if result.is_err() {
         error!("Failed to run plan: {:?}", result);
         return HttpResponse::InternalServerError().body(format!("{:?}", result));
     }
-    debug!("Ran plan");
+    let action = result.unwrap();
+    debug!("Queued action: {}", action.id);
+    HttpResponse::Ok().json(json!({ "id": action.id, "status": action.status }))
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct PausePlanRequest {
+    // How long the plan is paused (e.g. "2h"). The plan is paused until it is resumed if it is not set.
+    #[serde(default)]
+    duration: Option<String>,
+}
+// Suspend the evaluation of a plan in the running scaling planner. {id} is the id of the plan (not the db_id).
+#[post("/api/plans/{id}/pause")]
+async fn pause_plan(
+    plan_id: web::Path<String>,
+    request: Option<web::Json<PausePlanRequest>>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    let plan_id = plan_id.into_inner();
+    let request = request
+        .map(|request| request.into_inner())
+        .unwrap_or_default();
+    debug!("Pausing plan: {} {:?}", plan_id, request);
+    let paused_until = match request.duration.as_deref() {
+        None => None,
+        Some(duration) => {
+            let duration = duration_str::parse_chrono(duration);
+            if duration.is_err() {
+                return HttpResponse::BadRequest()
+                    .body(format!("Invalid duration: {:?}", duration.err().unwrap()));
+            }
+            Some(Utc::now() + duration.unwrap())
+        }
+    };
+    let plans = app_state.data_layer.get_all_plans().await;
+    if plans.is_err() {
+        error!("Failed to get plans: {:?}", plans);
+        return HttpResponse::InternalServerError().body(format!("{:?}", plans));
+    }
+    if !plans.unwrap().iter().any(|plan| plan.id == plan_id) {
+        return HttpResponse::NotFound().body("Plan not found");
+    }
+
+    let result = app_state
+        .data_layer
+        .pause_plan(&plan_id, paused_until)
+        .await;
+    if result.is_err() {
+        error!("Failed to pause plan: {:?}", result);
+        return HttpResponse::InternalServerError().body(format!("{:?}", result));
+    }
+    HttpResponse::Ok().json(result.unwrap())
+}
+
+// Resume a paused plan. The scaling planner evaluates the plan again from the next tick.
+#[post("/api/plans/{id}/resume")]
+async fn resume_plan(plan_id: web::Path<String>, app_state: web::Data<AppState>) -> impl Responder {
+    let plan_id = plan_id.into_inner();
+    debug!("Resuming plan: {}", plan_id);
+    let result = app_state.data_layer.resume_plan(&plan_id).await;
+    if result.is_err() {
+        error!("Failed to resume plan: {:?}", result);
+        return HttpResponse::InternalServerError().body(format!("{:?}", result));
+    }
+    if !result.unwrap() {
+        return HttpResponse::NotFound().body("Plan is not paused");
+    }
     HttpResponse::Ok().body("ok")
 }
 
+// Replay the stored metrics data through a plan and get the timeline of the plan items that would have been fired
+#[post("/api/plans/backtest")]
+async fn post_backtest(
+    request: web::Json<BacktestRequest>,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    debug!("Backtesting plan: {}", request.definition.id);
+    let result = app_state
+        .data_layer
+        .send_planner_request(PlannerRequest::Backtest(request.into_inner()))
+        .await;
+    if result.is_err() {
+        error!("Failed to backtest plan: {:?}", result);
+        return HttpResponse::InternalServerError().body(format!("{:?}", result));
+    }
+    HttpResponse::Ok().json(result.unwrap())
+}
+
 #[cfg(test)]
 mod tests {
     use super::init;
     use crate::utils::test_utils::get_app_state_for_test;
     use actix_web::{test, App};
     use data_layer::data_layer::DataLayer;
+    use data_layer::types::planner_request::PlannerRequest;
     use serde_json::json;
 
     // Utility functions
@@ -148,6 +343,7 @@ mod tests {
         let yaml = r#"
 id: test1
 kind: ScalingPlan
+enabled: true
 metadata: {}
 variables:
   test1: test1
@@ -160,7 +356,8 @@ plans:
     cool_down: null
     priority: 1
     scaling_components:
-      - name: test1
+      - component_id: test1
+        name: test1
         value: 1
 ---
 id: test2
@@ -219,29 +416,242 @@ plans:
         }
     }
 
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_get_plans_with_next_fire_times() {
+        let app_state = get_app_state_for_test().await;
+        let yaml = r#"
+id: cron_plan
+kind: ScalingPlan
+metadata:
+  timezone: Asia/Seoul
+plans:
+  - id: daily
+    cron_expression: "0 9 * * *"
+    priority: 1
+    scaling_components:
+      - component_id: test1
+  - id: utc_hourly
+    cron_expression: "@hourly"
+    timezone: UTC
+    priority: 2
+    scaling_components:
+      - component_id: test1
+"#;
+        app_state.data_layer.add_plan_yaml(yaml).await.unwrap();
+        let app = test::init_service(App::new().app_data(app_state).configure(init)).await;
+        let req = test::TestRequest::get().uri("/api/plans").to_request();
+        let resp: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+        let plan_items = resp[0]["plans"].as_array().unwrap();
+
+        // 09:00 in Seoul is 00:00 in UTC
+        let next_fire_times = plan_items[0]["next_fire_times"].as_array().unwrap();
+        assert_eq!(next_fire_times.len(), 5);
+        assert!(next_fire_times[0]
+            .as_str()
+            .unwrap()
+            .ends_with("T00:00:00+00:00"));
+        let next_fire_times = plan_items[1]["next_fire_times"].as_array().unwrap();
+        assert!(next_fire_times[0]
+            .as_str()
+            .unwrap()
+            .ends_with(":00:00+00:00"));
+    }
+
     // [GET] /api/run-plan
     #[actix_web::test]
     #[tracing_test::traced_test]
     async fn test_run_plan() {
         let app_state = get_app_state_for_test().await;
         let mut receiver = app_state.data_layer.subscribe_action();
-        // add_plans_for_test(&app_state.data_layer).await;
-        let app = test::init_service(App::new().app_data(app_state).configure(init)).await;
+        add_plan_yaml_for_test(&app_state.data_layer).await;
+        let app = test::init_service(App::new().app_data(app_state.clone()).configure(init)).await;
         let req = test::TestRequest::post()
             .uri("/api/run-plan")
             .set_json(json!({
-                "plan_id": "plan_id_1",
-                "plan_item_id": "plan_item_id_1"
+                "plan_id": "test1",
+                "plan_item_id": "test1"
             }))
             .to_request();
-        let resp = test::call_service(&app, req).await;
-        assert!(resp.status().is_success());
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["status"], "QUEUED");
+        let id = resp["id"].as_str().unwrap();
 
+        // The action is queued and the scaling planners are notified
         let receiver_result = receiver.recv().await;
         assert!(receiver_result.is_ok());
-
         let value = receiver_result.unwrap();
-        assert_eq!(value.get("plan_id").unwrap(), "plan_id_1");
-        assert_eq!(value.get("plan_item_id").unwrap(), "plan_item_id_1");
+        assert_eq!(value.get("id").unwrap(), id);
+        assert_eq!(value.get("plan_id").unwrap(), "test1");
+        assert_eq!(value.get("plan_item_id").unwrap(), "test1");
+        let action = app_state.data_layer.get_action(id).await.unwrap().unwrap();
+        assert_eq!(action.plan_item_id, "test1");
+        assert!(action.overrides_json.is_none());
+
+        // Run with the parameter overrides
+        let req = test::TestRequest::post()
+            .uri("/api/run-plan")
+            .set_json(json!({
+                "plan_id": "test1",
+                "plan_item_id": "test1",
+                "overrides": { "test1": { "value": 40 } }
+            }))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let id = resp["id"].as_str().unwrap();
+        let action = app_state.data_layer.get_action(id).await.unwrap().unwrap();
+        let overrides: serde_json::Value =
+            serde_json::from_str(action.overrides_json.as_ref().unwrap()).unwrap();
+        assert_eq!(overrides, json!({ "test1": { "value": 40 } }));
+
+        // Unknown scaling component in the overrides
+        let req = test::TestRequest::post()
+            .uri("/api/run-plan")
+            .set_json(json!({
+                "plan_id": "test1",
+                "plan_item_id": "test1",
+                "overrides": { "unknown": { "value": 40 } }
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        // Unknown plan item
+        let req = test::TestRequest::post()
+            .uri("/api/run-plan")
+            .set_json(json!({
+                "plan_id": "test1",
+                "plan_item_id": "test2"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    // [POST] /api/plans/{id}/pause, [POST] /api/plans/{id}/resume
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_pause_plan() {
+        let app_state = get_app_state_for_test().await;
+        add_plan_yaml_for_test(&app_state.data_layer).await;
+        let app = test::init_service(App::new().app_data(app_state.clone()).configure(init)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/plans/test1/pause")
+            .set_json(json!({ "duration": "2h" }))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["plan_id"], "test1");
+        assert!(resp["paused_until"].is_string());
+
+        // The plans have the paused state
+        let req = test::TestRequest::get().uri("/api/plans").to_request();
+        let resp: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+        let plan = resp.iter().find(|plan| plan["id"] == "test1").unwrap();
+        assert_eq!(plan["paused"], true);
+        assert!(plan["paused_until"].is_string());
+        let plan = resp.iter().find(|plan| plan["id"] == "test2").unwrap();
+        assert_eq!(plan["paused"], false);
+        let db_id = resp.iter().find(|plan| plan["id"] == "test1").unwrap()["db_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let req = test::TestRequest::get()
+            .uri(format!("/api/plans/{}", db_id).as_str())
+            .to_request();
+        let plan: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(plan["paused"], true);
+        assert!(plan["paused_until"].is_string());
+
+        // Paused until it is resumed without a duration
+        let req = test::TestRequest::post()
+            .uri("/api/plans/test2/pause")
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(resp["paused_until"].is_null());
+
+        let req = test::TestRequest::post()
+            .uri("/api/plans/test1/resume")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert!(app_state
+            .data_layer
+            .get_plan_pause("test1")
+            .await
+            .unwrap()
+            .is_none());
+
+        // Not paused, unknown plan and invalid duration
+        let req = test::TestRequest::post()
+            .uri("/api/plans/test1/resume")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let req = test::TestRequest::post()
+            .uri("/api/plans/unknown/pause")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let req = test::TestRequest::post()
+            .uri("/api/plans/test1/pause")
+            .set_json(json!({ "duration": "two hours" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    // [POST] /api/plans/backtest
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_post_backtest() {
+        let app_state = get_app_state_for_test().await;
+        let app = test::init_service(App::new().app_data(app_state.clone()).configure(init)).await;
+        let body = json!({
+            "definition": {
+                "id": "plan_id_1",
+                "kind": "ScalingPlan",
+                "metadata": {},
+                "plans": []
+            },
+            "from": 1000,
+            "to": 2000
+        });
+
+        // No handler for the planner requests
+        let req = test::TestRequest::post()
+            .uri("/api/plans/backtest")
+            .set_json(body.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_server_error());
+
+        // Fake handler that returns the id of the plan and the time range
+        let mut receiver = app_state
+            .data_layer
+            .take_planner_request_receiver()
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            while let Some((request, responder)) = receiver.recv().await {
+                let PlannerRequest::Backtest(request) = request else {
+                    continue;
+                };
+                let _ = responder.send(Ok(json!([{
+                    "plan_id": request.definition.id,
+                    "from": request.from,
+                    "to": request.to,
+                }])));
+            }
+        });
+        let req = test::TestRequest::post()
+            .uri("/api/plans/backtest")
+            .set_json(body)
+            .to_request();
+        let resp: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 1);
+        assert_eq!(resp[0]["plan_id"], "plan_id_1");
+        assert_eq!(resp[0]["from"], 1000);
+        assert_eq!(resp[0]["to"], 2000);
     }
 }
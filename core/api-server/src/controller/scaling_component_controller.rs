@@ -1,13 +1,15 @@
 use crate::app_state::AppState;
 use actix_web::{get, post, web, HttpResponse, Responder};
+use data_layer::types::planner_request::{PlannerRequest, PlannerUnavailableError};
 use serde::Deserialize;
-use tracing::debug;
+use tracing::{debug, error};
 use validator::Validate;
 
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(get_scaling_components)
         .service(get_scaling_component_yaml)
-        .service(post_scaling_component_yaml);
+        .service(post_scaling_component_yaml)
+        .service(get_circuit_breakers);
     // .service(get_scaling_component_by_id)
     // .service(post_scaling_components)
     // .service(put_scaling_component_by_id)
@@ -54,6 +56,26 @@ async fn post_scaling_component_yaml(
     HttpResponse::Ok().body("ok")
 }
 
+// The circuit breakers of the scaling components that have metadata.circuit_breaker (component_id => state)
+#[get("/api/scaling-components/circuit-breakers")]
+async fn get_circuit_breakers(app_state: web::Data<AppState>) -> impl Responder {
+    let result = app_state
+        .data_layer
+        .send_planner_request(PlannerRequest::GetCircuitBreakers)
+        .await;
+    if let Err(error) = result.as_ref() {
+        // The scaling planners run only in the leader
+        if error.is::<PlannerUnavailableError>() {
+            return HttpResponse::ServiceUnavailable().body(error.to_string());
+        }
+    }
+    if result.is_err() {
+        error!("Failed to get circuit breakers: {:?}", result);
+        return HttpResponse::InternalServerError().body(format!("{:?}", result));
+    }
+    HttpResponse::Ok().json(result.unwrap())
+}
+
 // #[get("/api/scaling-components/{db_id}")]
 // async fn get_scaling_component_by_id(
 //     db_id: web::Path<String>,
@@ -129,8 +151,12 @@ mod tests {
     use crate::utils::test_utils::get_app_state_for_test;
 
     use super::init;
-    use actix_web::{test, App};
-    use data_layer::data_layer::DataLayer;
+    use actix_web::{http::StatusCode, test, App};
+    use data_layer::{
+        data_layer::DataLayer,
+        types::planner_request::{PlannerRequest, PlannerUnavailableError},
+    };
+    use serde_json::json;
 
     // Utility functions
     async fn sync_scaling_components_for_test(data_layer: &DataLayer) {
@@ -179,4 +205,57 @@ enabled: true
             }
         }
     }
+
+    // [GET] /api/scaling-components/circuit-breakers
+
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_get_circuit_breakers() {
+        let app_state = get_app_state_for_test().await;
+        // A fake handler of the main application
+        let mut receiver = app_state
+            .data_layer
+            .take_planner_request_receiver()
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            while let Some((request, responder)) = receiver.recv().await {
+                let PlannerRequest::GetCircuitBreakers = request else {
+                    continue;
+                };
+                let _ = responder.send(Ok(json!({
+                    "test_component_1": { "state": "OPEN", "consecutive_failures": 5 }
+                })));
+            }
+        });
+        let app = test::init_service(App::new().app_data(app_state).configure(init)).await;
+        let req = test::TestRequest::get()
+            .uri("/api/scaling-components/circuit-breakers")
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["test_component_1"]["state"], "OPEN");
+    }
+
+    #[actix_web::test]
+    #[tracing_test::traced_test]
+    async fn test_get_circuit_breakers_in_follower() {
+        let app_state = get_app_state_for_test().await;
+        // A fake handler of a follower that doesn't run the scaling planners
+        let mut receiver = app_state
+            .data_layer
+            .take_planner_request_receiver()
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            while let Some((_, responder)) = receiver.recv().await {
+                let _ = responder.send(Err(PlannerUnavailableError.into()));
+            }
+        });
+        let app = test::init_service(App::new().app_data(app_state).configure(init)).await;
+        let req = test::TestRequest::get()
+            .uri("/api/scaling-components/circuit-breakers")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
 }
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE plan_log
+ADD COLUMN status TEXT;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE plan
+ADD COLUMN behavior TEXT;
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TABLE cron_last_fired (
+  plan_id TEXT,
+  plan_item_id TEXT,
+  last_fired_at TEXT,
+  PRIMARY KEY (plan_id, plan_item_id)
+);
\ No newline at end of file
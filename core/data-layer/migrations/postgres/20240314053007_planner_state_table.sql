@@ -0,0 +1,6 @@
+-- Add migration script here
+CREATE TABLE planner_state (
+  plan_id TEXT PRIMARY KEY,
+  state TEXT,
+  updated_at TEXT
+);
\ No newline at end of file
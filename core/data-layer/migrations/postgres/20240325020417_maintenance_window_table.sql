@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE maintenance_window (
+  db_id TEXT PRIMARY KEY,
+  id TEXT UNIQUE,
+  definition TEXT,
+  enabled BOOLEAN,
+  yaml TEXT,
+  created_at TEXT,
+  updated_at TEXT
+);
\ No newline at end of file
@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE pending_action (
+  id TEXT PRIMARY KEY,
+  plan_db_id TEXT,
+  plan_id TEXT,
+  plan_item_id TEXT,
+  plan_item_json TEXT,
+  scaling_components_json TEXT,
+  status TEXT,
+  created_at TEXT,
+  expires_at TEXT,
+  updated_at TEXT
+);
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TABLE leader_lease (
+  name TEXT PRIMARY KEY,
+  holder TEXT,
+  expires_at TEXT,
+  updated_at TEXT
+);
\ No newline at end of file
@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE plan_action (
+  id TEXT PRIMARY KEY,
+  plan_id TEXT,
+  plan_item_id TEXT,
+  status TEXT,
+  result_json TEXT,
+  error TEXT,
+  created_at TEXT,
+  started_at TEXT,
+  finished_at TEXT,
+  updated_at TEXT
+);
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE plan_action ADD COLUMN overrides_json TEXT;
+ALTER TABLE plan_log ADD COLUMN overrides_json TEXT;
@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TABLE plan_pause (
+  plan_id TEXT PRIMARY KEY,
+  paused_at TEXT,
+  paused_until TEXT,
+  updated_at TEXT
+);
\ No newline at end of file
@@ -0,0 +1,294 @@
+use super::{format_timestamp, DataLayer};
+use crate::types::action::{ActionDefinition, ActionOverrides, ActionStatus};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use sqlx::{any::AnyRow, Row};
+use std::sync::Mutex;
+use ulid::{Generator, Ulid};
+
+// The ids of the actions increase even within a millisecond so that the queued actions run in the order they were queued
+static ACTION_ID_GENERATOR: Lazy<Mutex<Generator>> = Lazy::new(|| Mutex::new(Generator::new()));
+
+fn generate_action_id() -> String {
+    let Ok(mut generator) = ACTION_ID_GENERATOR.lock() else {
+        return Ulid::new().to_string();
+    };
+    generator
+        .generate()
+        .unwrap_or_else(|_| Ulid::new())
+        .to_string()
+}
+
+fn row_to_action(row: &AnyRow) -> Result<ActionDefinition> {
+    Ok(ActionDefinition {
+        id: row.try_get("id")?,
+        plan_id: row.try_get("plan_id")?,
+        plan_item_id: row.try_get("plan_item_id")?,
+        overrides_json: row.try_get("overrides_json")?,
+        status: row.try_get("status")?,
+        result_json: row.try_get("result_json")?,
+        error: row.try_get("error")?,
+        created_at: row.try_get("created_at")?,
+        started_at: row.try_get("started_at")?,
+        finished_at: row.try_get("finished_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+impl DataLayer {
+    // Queue a manual run of a plan item with the parameter overrides of the scaling components
+    pub async fn add_action(
+        &self,
+        plan_id: &str,
+        plan_item_id: &str,
+        overrides: &ActionOverrides,
+    ) -> Result<ActionDefinition> {
+        let now = format_timestamp(Utc::now());
+        let overrides_json = if overrides.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(overrides)?)
+        };
+        let action = ActionDefinition {
+            id: generate_action_id(),
+            plan_id: plan_id.to_string(),
+            plan_item_id: plan_item_id.to_string(),
+            overrides_json,
+            status: ActionStatus::Queued.to_string(),
+            result_json: None,
+            error: None,
+            created_at: now.clone(),
+            started_at: None,
+            finished_at: None,
+            updated_at: now,
+        };
+        let query_string = "INSERT INTO plan_action (id, plan_id, plan_item_id, overrides_json, status, created_at, updated_at) VALUES ($1,$2,$3,$4,$5,$6,$7)";
+        let result = sqlx::query(query_string)
+            .bind(action.id.clone())
+            .bind(action.plan_id.clone())
+            .bind(action.plan_item_id.clone())
+            .bind(action.overrides_json.clone())
+            .bind(action.status.clone())
+            .bind(action.created_at.clone())
+            .bind(action.updated_at.clone())
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(action)
+    }
+    // Get an action by id
+    pub async fn get_action(&self, id: &str) -> Result<Option<ActionDefinition>> {
+        let result = sqlx::query("SELECT * FROM plan_action WHERE id=$1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        match result.unwrap() {
+            Some(row) => Ok(Some(row_to_action(&row)?)),
+            None => Ok(None),
+        }
+    }
+    // Get the queued actions of a plan (the oldest first)
+    pub async fn get_queued_actions(&self, plan_id: &str) -> Result<Vec<ActionDefinition>> {
+        let result =
+            sqlx::query("SELECT * FROM plan_action WHERE plan_id=$1 AND status=$2 ORDER BY created_at ASC, id ASC")
+                .bind(plan_id)
+                .bind(ActionStatus::Queued.to_string())
+                .fetch_all(&self.pool)
+                .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let mut actions = Vec::new();
+        for row in result.unwrap() {
+            actions.push(row_to_action(&row)?);
+        }
+        Ok(actions)
+    }
+    // Change a queued action to RUNNING. Returns false if another scaling planner already started it.
+    pub async fn start_action(&self, id: &str) -> Result<bool> {
+        let query_string =
+            "UPDATE plan_action SET status=$1, started_at=$2, updated_at=$3 WHERE id=$4 AND status=$5";
+        let now = format_timestamp(Utc::now());
+        let result = sqlx::query(query_string)
+            .bind(ActionStatus::Running.to_string())
+            .bind(now.clone())
+            .bind(now)
+            .bind(id)
+            .bind(ActionStatus::Queued.to_string())
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(result.unwrap().rows_affected() > 0)
+    }
+    // Fail the RUNNING actions of a plan that can't finish anymore. Returns the number of the failed actions.
+    pub async fn fail_running_actions(&self, plan_id: &str, error: &str) -> Result<u64> {
+        let query_string = "UPDATE plan_action SET status=$1, error=$2, finished_at=$3, updated_at=$4 WHERE plan_id=$5 AND status=$6";
+        let now = format_timestamp(Utc::now());
+        let result = sqlx::query(query_string)
+            .bind(ActionStatus::Failed.to_string())
+            .bind(error)
+            .bind(now.clone())
+            .bind(now)
+            .bind(plan_id)
+            .bind(ActionStatus::Running.to_string())
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(result.unwrap().rows_affected())
+    }
+    // Save the result of an action (SUCCEEDED or FAILED)
+    pub async fn finish_action(
+        &self,
+        id: &str,
+        status: ActionStatus,
+        result: &[Value],
+        error: Option<String>,
+    ) -> Result<()> {
+        let query_string = "UPDATE plan_action SET status=$1, result_json=$2, error=$3, finished_at=$4, updated_at=$5 WHERE id=$6";
+        let now = format_timestamp(Utc::now());
+        let result = sqlx::query(query_string)
+            .bind(status.to_string())
+            .bind(serde_json::to_string(result)?)
+            .bind(error)
+            .bind(now.clone())
+            .bind(now)
+            .bind(id)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_actions() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+
+        let overrides: ActionOverrides = HashMap::from([(
+            "component_1".to_string(),
+            HashMap::from([("replicas".to_string(), json!(40))]),
+        )]);
+        let first = data_layer
+            .add_action("plan_1", "item_1", &overrides)
+            .await
+            .unwrap();
+        let second = data_layer
+            .add_action("plan_1", "item_2", &HashMap::new())
+            .await
+            .unwrap();
+        data_layer
+            .add_action("plan_2", "item_1", &HashMap::new())
+            .await
+            .unwrap();
+        let queued = data_layer.get_queued_actions("plan_1").await.unwrap();
+        assert_eq!(
+            queued
+                .iter()
+                .map(|action| action.id.clone())
+                .collect::<Vec<_>>(),
+            vec![first.id.clone(), second.id.clone()]
+        );
+
+        // Started only once
+        assert!(data_layer.start_action(&first.id).await.unwrap());
+        assert!(!data_layer.start_action(&first.id).await.unwrap());
+        assert_eq!(
+            data_layer.get_queued_actions("plan_1").await.unwrap().len(),
+            1
+        );
+        let overrides_json = queued[0].overrides_json.as_ref().unwrap();
+        assert_eq!(
+            serde_json::from_str::<ActionOverrides>(overrides_json).unwrap(),
+            overrides
+        );
+        assert!(queued[1].overrides_json.is_none());
+
+        data_layer
+            .finish_action(
+                &first.id,
+                ActionStatus::Failed,
+                &[json!({ "component_id": "component_1", "status": "FAIL" })],
+                Some("Failed to apply".to_string()),
+            )
+            .await
+            .unwrap();
+        let action = data_layer.get_action(&first.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Failed.to_string());
+        assert_eq!(action.error, Some("Failed to apply".to_string()));
+        assert!(action.started_at.is_some());
+        assert!(action.finished_at.is_some());
+        assert!(action.result_json.unwrap().contains("component_1"));
+        assert!(data_layer.get_action("unknown").await.unwrap().is_none());
+
+        // Only the running actions of the plan are failed
+        assert!(data_layer.start_action(&second.id).await.unwrap());
+        assert_eq!(
+            data_layer
+                .fail_running_actions("plan_2", "Stopped")
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            data_layer
+                .fail_running_actions("plan_1", "Stopped")
+                .await
+                .unwrap(),
+            1
+        );
+        let action = data_layer.get_action(&second.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Failed.to_string());
+        assert_eq!(action.error, Some("Stopped".to_string()));
+        assert!(action.finished_at.is_some());
+        let action = data_layer.get_action(&first.id).await.unwrap().unwrap();
+        assert_eq!(action.error, Some("Failed to apply".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_queued_actions_order() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+
+        // The actions that are queued in the same millisecond also run in the order they were queued
+        let mut ids = Vec::new();
+        for index in 0..20 {
+            let action = data_layer
+                .add_action(
+                    "plan_1",
+                    format!("item_{}", index).as_str(),
+                    &HashMap::new(),
+                )
+                .await
+                .unwrap();
+            ids.push(action.id);
+        }
+        let queued = data_layer.get_queued_actions("plan_1").await.unwrap();
+        assert_eq!(
+            queued
+                .into_iter()
+                .map(|action| action.id)
+                .collect::<Vec<_>>(),
+            ids
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use super::{format_timestamp, DataLayer};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use std::collections::HashMap;
+
+impl DataLayer {
+    // Get the last fired occurrences of the cron expressions in a plan (plan_item_id => last_fired_at)
+    pub async fn get_cron_last_fired(
+        &self,
+        plan_id: &str,
+    ) -> Result<HashMap<String, DateTime<Utc>>> {
+        let query_string =
+            "SELECT plan_item_id, last_fired_at FROM cron_last_fired WHERE plan_id=$1";
+        let result = sqlx::query(query_string)
+            .bind(plan_id)
+            .fetch_all(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let mut cron_last_fired = HashMap::new();
+        for row in result.unwrap() {
+            let plan_item_id: String = row.try_get("plan_item_id")?;
+            let last_fired_at: String = row.try_get("last_fired_at")?;
+            let last_fired_at = DateTime::parse_from_rfc3339(last_fired_at.as_str())?;
+            cron_last_fired.insert(plan_item_id, last_fired_at.with_timezone(&Utc));
+        }
+        Ok(cron_last_fired)
+    }
+    // Save the last fired occurrence of the cron expression of a plan item
+    pub async fn set_cron_last_fired(
+        &self,
+        plan_id: &str,
+        plan_item_id: &str,
+        last_fired_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let query_string = "INSERT INTO cron_last_fired (plan_id, plan_item_id, last_fired_at) VALUES ($1,$2,$3) ON CONFLICT (plan_id, plan_item_id) DO UPDATE SET last_fired_at=$4";
+        let last_fired_at = format_timestamp(last_fired_at);
+        let result = sqlx::query(query_string)
+            .bind(plan_id)
+            .bind(plan_item_id)
+            .bind(last_fired_at.clone())
+            .bind(last_fired_at)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_cron_last_fired() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        let fired_at = Utc.with_ymd_and_hms(2024, 3, 12, 0, 0, 0).unwrap();
+        data_layer
+            .set_cron_last_fired("plan_1", "item_1", fired_at)
+            .await
+            .unwrap();
+        // Overwrite the last fired occurrence
+        let fired_at = fired_at + chrono::Duration::hours(1);
+        data_layer
+            .set_cron_last_fired("plan_1", "item_1", fired_at)
+            .await
+            .unwrap();
+        data_layer
+            .set_cron_last_fired("plan_2", "item_1", fired_at)
+            .await
+            .unwrap();
+
+        let cron_last_fired = data_layer.get_cron_last_fired("plan_1").await.unwrap();
+        assert_eq!(cron_last_fired.len(), 1);
+        assert_eq!(cron_last_fired.get("item_1"), Some(&fired_at));
+    }
+}
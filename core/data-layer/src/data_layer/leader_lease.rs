@@ -0,0 +1,161 @@
+use super::DataLayer;
+use anyhow::{anyhow, Result};
+use chrono::Duration;
+use sqlx::any::AnyKind;
+
+/**
+ * The SQL expressions of the current time and the expiration of the lease on the clock of the database
+ * - All instances compare the lease with the same clock, so the clock skew between the instances doesn't matter
+ * - The timestamps have the same format as format_timestamp (RFC3339 with milliseconds in UTC)
+ */
+fn get_lease_time_sql(database_kind: AnyKind, lease_duration: Duration) -> (String, String) {
+    let lease_duration_ms = lease_duration.num_milliseconds();
+    match database_kind {
+        AnyKind::Postgres => {
+            let format = r#"'YYYY-MM-DD"T"HH24:MI:SS.MS"Z"'"#;
+            (
+                format!("to_char(now() AT TIME ZONE 'UTC', {})", format),
+                format!(
+                    "to_char((now() + interval '{} milliseconds') AT TIME ZONE 'UTC', {})",
+                    lease_duration_ms, format
+                ),
+            )
+        }
+        AnyKind::Sqlite => (
+            "strftime('%Y-%m-%dT%H:%M:%fZ', 'now')".to_string(),
+            format!(
+                "strftime('%Y-%m-%dT%H:%M:%fZ', 'now', '+{}.{:03} seconds')",
+                lease_duration_ms / 1000,
+                lease_duration_ms % 1000
+            ),
+        ),
+        AnyKind::MySql => {
+            panic!("MySQL is not supported yet");
+        }
+    }
+}
+
+impl DataLayer {
+    /**
+     * Acquire or renew the leader lease
+     * - The holder of the lease renews it. Another instance can acquire it only after it expires.
+     * - Returns whether the holder has the lease for lease_duration
+     * - The expiration and the takeover use the clock of the database, not the clock of the instance
+     */
+    pub async fn try_acquire_leader_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        lease_duration: Duration,
+    ) -> Result<bool> {
+        let (now, expires_at) = get_lease_time_sql(self.pool.any_kind(), lease_duration);
+
+        // The first instance creates the lease row
+        let query_string = format!(
+            "INSERT INTO leader_lease (name, holder, expires_at, updated_at) VALUES ($1,$2,{},{}) ON CONFLICT (name) DO NOTHING",
+            expires_at, now
+        );
+        let result = sqlx::query(&query_string)
+            .bind(name)
+            .bind(holder)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+
+        // Only one instance can update the row in a single statement
+        let query_string = format!(
+            "UPDATE leader_lease SET holder=$1, expires_at={}, updated_at={} WHERE name=$2 AND (holder=$3 OR expires_at<={})",
+            expires_at, now, now
+        );
+        let result = sqlx::query(&query_string)
+            .bind(holder)
+            .bind(name)
+            .bind(holder)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(result.unwrap().rows_affected() == 1)
+    }
+
+    // Release the leader lease so that another instance can acquire it without waiting for the expiration
+    pub async fn release_leader_lease(&self, name: &str, holder: &str) -> Result<()> {
+        let (now, _) = get_lease_time_sql(self.pool.any_kind(), Duration::zero());
+        let query_string = format!(
+            "UPDATE leader_lease SET expires_at={}, updated_at={} WHERE name=$1 AND holder=$2",
+            now, now
+        );
+        let result = sqlx::query(&query_string)
+            .bind(name)
+            .bind(holder)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_leader_lease() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        let lease_duration = Duration::seconds(60);
+
+        assert!(data_layer
+            .try_acquire_leader_lease("leader", "instance_1", lease_duration)
+            .await
+            .unwrap());
+        // The database clock keeps the format of format_timestamp
+        let expires_at: String =
+            sqlx::query_scalar("SELECT expires_at FROM leader_lease WHERE name='leader'")
+                .fetch_one(&data_layer.pool)
+                .await
+                .unwrap();
+        let expires_at = chrono::DateTime::parse_from_rfc3339(&expires_at).unwrap();
+        let remaining = expires_at.signed_duration_since(chrono::Utc::now());
+        assert!(remaining > Duration::seconds(50) && remaining <= lease_duration);
+        // Only the holder can renew the lease until it expires
+        assert!(!data_layer
+            .try_acquire_leader_lease("leader", "instance_2", lease_duration)
+            .await
+            .unwrap());
+        assert!(data_layer
+            .try_acquire_leader_lease("leader", "instance_1", lease_duration)
+            .await
+            .unwrap());
+
+        // Another instance acquires the released lease
+        data_layer
+            .release_leader_lease("leader", "instance_1")
+            .await
+            .unwrap();
+        assert!(data_layer
+            .try_acquire_leader_lease("leader", "instance_2", lease_duration)
+            .await
+            .unwrap());
+        assert!(!data_layer
+            .try_acquire_leader_lease("leader", "instance_1", lease_duration)
+            .await
+            .unwrap());
+
+        // Another instance acquires the expired lease
+        assert!(data_layer
+            .try_acquire_leader_lease("expiring", "instance_1", Duration::milliseconds(10))
+            .await
+            .unwrap());
+        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        assert!(data_layer
+            .try_acquire_leader_lease("expiring", "instance_2", lease_duration)
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,209 @@
+use super::DataLayer;
+use crate::{types::object_kind::ObjectKind, MaintenanceWindowDefinition};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_valid::Validate;
+use sqlx::{any::AnyQueryResult, Row};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+impl DataLayer {
+    pub async fn add_maintenance_window_yaml(&self, yaml: &str) -> Result<()> {
+        self.add_maintenance_window_yaml_for_unmatched_ids(yaml, true)
+            .await
+    }
+    pub async fn add_maintenance_window_yaml_for_unmatched_ids(
+        &self,
+        yaml: &str,
+        rewrite: bool,
+    ) -> Result<()> {
+        let deserializer = serde_yaml::Deserializer::from_str(yaml);
+        let mut maintenance_windows: Vec<(MaintenanceWindowDefinition, String)> = Vec::new();
+
+        let mut db_maintenance_window_ids: HashSet<String> = HashSet::new();
+        if !rewrite {
+            // The maintenance windows that are already in the database are not overwritten
+            for maintenance_window in self.get_all_maintenance_windows().await? {
+                db_maintenance_window_ids.insert(maintenance_window.id);
+            }
+        }
+
+        for document in deserializer {
+            let value = serde_yaml::Value::deserialize(document)?;
+            let kind = value.get("kind").and_then(serde_yaml::Value::as_str);
+            if kind.is_none() || kind.unwrap() != ObjectKind::MaintenanceWindow.to_string() {
+                continue;
+            }
+            let parsed = serde_yaml::from_value::<MaintenanceWindowDefinition>(value.clone())?;
+            parsed.validate()?;
+            parsed.validate_schedule()?;
+            let document_yaml = serde_yaml::to_string(&value)?;
+            if db_maintenance_window_ids.contains(parsed.id.as_str()) {
+                continue;
+            }
+            maintenance_windows.push((parsed, document_yaml));
+        }
+
+        self.add_maintenance_windows(maintenance_windows).await
+    }
+    // Add multiple maintenance windows to the database
+    pub async fn add_maintenance_windows(
+        &self,
+        maintenance_windows: Vec<(MaintenanceWindowDefinition, String)>,
+    ) -> Result<()> {
+        for (maintenance_window, yaml) in maintenance_windows {
+            let definition_string = serde_json::to_string(&maintenance_window)?;
+            let query_string = "INSERT INTO maintenance_window (db_id, id, definition, enabled, yaml, created_at, updated_at) VALUES ($1,$2,$3,$4,$5,$6,$7) ON CONFLICT (id) DO UPDATE SET (definition, enabled, yaml, updated_at) = ($8, $9, $10, $11)";
+            let db_id = Uuid::new_v4().to_string();
+            let updated_at = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+            let result = sqlx::query(query_string)
+                // Values for insert
+                .bind(db_id)
+                .bind(maintenance_window.id)
+                .bind(definition_string.clone())
+                .bind(maintenance_window.enabled)
+                .bind(yaml.clone())
+                .bind(updated_at.clone())
+                .bind(updated_at.clone())
+                // Values for update
+                .bind(definition_string)
+                .bind(maintenance_window.enabled)
+                .bind(yaml)
+                .bind(updated_at)
+                .execute(&self.pool)
+                .await;
+            if result.is_err() {
+                return Err(anyhow!(result.err().unwrap().to_string()));
+            }
+        }
+        Ok(())
+    }
+    // Get all maintenance windows from the database
+    pub async fn get_all_maintenance_windows(&self) -> Result<Vec<MaintenanceWindowDefinition>> {
+        let query_string = "SELECT db_id, definition, enabled FROM maintenance_window";
+        let result = sqlx::query(query_string).fetch_all(&self.pool).await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let mut maintenance_windows = Vec::new();
+        for row in result.unwrap() {
+            let definition = row.try_get::<String, _>("definition")?;
+            let mut maintenance_window =
+                serde_json::from_str::<MaintenanceWindowDefinition>(definition.as_str())?;
+            maintenance_window.db_id = row.try_get::<String, _>("db_id")?;
+            maintenance_window.enabled = row.try_get::<bool, _>("enabled")?;
+            maintenance_windows.push(maintenance_window);
+        }
+        Ok(maintenance_windows)
+    }
+    // Get enabled maintenance windows
+    pub async fn get_enabled_maintenance_windows(
+        &self,
+    ) -> Result<Vec<MaintenanceWindowDefinition>> {
+        let maintenance_windows = self.get_all_maintenance_windows().await?;
+        Ok(maintenance_windows
+            .into_iter()
+            .filter(|maintenance_window| maintenance_window.enabled)
+            .collect())
+    }
+    // Delete all maintenance windows from the database
+    pub async fn delete_all_maintenance_windows(&self) -> Result<()> {
+        let query_string = "DELETE FROM maintenance_window";
+        let result = sqlx::query(query_string).execute(&self.pool).await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(())
+    }
+    // Delete a maintenance window
+    pub async fn delete_maintenance_window(&self, db_id: String) -> Result<AnyQueryResult> {
+        let query_string = "DELETE FROM maintenance_window WHERE db_id=$1";
+        let result = sqlx::query(query_string)
+            .bind(db_id)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let result = result.unwrap();
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("No rows affected"));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = r#"
+kind: MaintenanceWindow
+id: db_migration
+start_time: "2024-03-25T01:00:00Z"
+end_time: "2024-03-25T03:00:00Z"
+plan_ids: [plan_1]
+---
+kind: MaintenanceWindow
+id: deploy_freeze
+cron_expression: "0 2 * * SAT"
+duration: 2h
+enabled: false
+"#;
+
+    #[tokio::test]
+    async fn test_maintenance_window() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        data_layer.add_maintenance_window_yaml(YAML).await.unwrap();
+        let maintenance_windows = data_layer.get_all_maintenance_windows().await.unwrap();
+        assert_eq!(maintenance_windows.len(), 2);
+        let enabled = data_layer.get_enabled_maintenance_windows().await.unwrap();
+        assert_eq!(enabled.len(), 1);
+        assert_eq!(enabled[0].id, "db_migration");
+        assert_eq!(enabled[0].plan_ids, vec!["plan_1".to_string()]);
+        assert!(!enabled[0].db_id.is_empty());
+
+        // The existing maintenance windows are kept for the unmatched ids
+        let yaml = YAML.replace("enabled: false", "enabled: true");
+        data_layer
+            .add_maintenance_window_yaml_for_unmatched_ids(&yaml, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            data_layer
+                .get_enabled_maintenance_windows()
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        data_layer.add_maintenance_window_yaml(&yaml).await.unwrap();
+        assert_eq!(
+            data_layer
+                .get_enabled_maintenance_windows()
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // An invalid schedule is rejected
+        let yaml = "kind: MaintenanceWindow\nid: invalid\ncron_expression: \"0 2 * * *\"\n";
+        assert!(data_layer.add_maintenance_window_yaml(yaml).await.is_err());
+
+        data_layer
+            .delete_maintenance_window(enabled[0].db_id.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            data_layer
+                .get_all_maintenance_windows()
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -279,6 +279,44 @@ impl DataLayer {
         }
         Ok(metric_values)
     }
+    // Get the metrics data between the time range from the database (e.g. for backtesting)
+    // It returns the same structure as the in-memory metrics data: metric_id => (ULID => MetricsDataItem)
+    pub async fn get_metrics_data_map_from_db(
+        &self,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<HashMap<String, BTreeMap<String, MetricsDataItem>>> {
+        let timestamp_ms = |time: SystemTime| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or(0)
+        };
+        let from_ulid = Ulid::from_parts(timestamp_ms(from), 0);
+        let to_ulid = Ulid::from_parts(timestamp_ms(to), u128::MAX);
+        let query_string =
+            "SELECT id, metric_id, json_value FROM metrics_data WHERE id >= $1 AND id <= $2 ORDER BY id";
+        let result = sqlx::query(query_string)
+            .bind(from_ulid.to_string())
+            .bind(to_ulid.to_string())
+            .fetch_all(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let result = result.unwrap();
+        let mut metrics_data_map: HashMap<String, BTreeMap<String, MetricsDataItem>> =
+            HashMap::new();
+        for row in result {
+            let id: String = row.try_get("id")?;
+            let metric_id: String = row.try_get("metric_id")?;
+            let json_value: String = row.try_get("json_value")?;
+            metrics_data_map
+                .entry(metric_id)
+                .or_default()
+                .insert(id, MetricsDataItem { json_value });
+        }
+        Ok(metrics_data_map)
+    }
 }
 
 #[cfg(test)]
@@ -515,4 +553,43 @@ mod tests {
             });
         assert_eq!(read_idx, loop_cnt);
     }
+
+    #[tokio::test]
+    async fn test_get_metrics_data_map_from_db() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        let from = SystemTime::now() - Duration::from_millis(1);
+        let json_value = json!([{"name": "test", "value": 1.0}]).to_string();
+        for metric_id in ["metric_1", "metric_1", "metric_2"] {
+            let result = data_layer
+                .add_metrics_data_into_db("vector", metric_id, json_value.as_str())
+                .await;
+            assert!(result.is_ok());
+        }
+        let to = SystemTime::now();
+
+        let metrics_data_map = data_layer
+            .get_metrics_data_map_from_db(from, to)
+            .await
+            .unwrap();
+        assert_eq!(metrics_data_map.get("metric_1").unwrap().len(), 2);
+        assert_eq!(metrics_data_map.get("metric_2").unwrap().len(), 1);
+        assert_eq!(
+            metrics_data_map
+                .get("metric_2")
+                .unwrap()
+                .first_key_value()
+                .unwrap()
+                .1
+                .json_value,
+            json_value
+        );
+
+        // Out of the time range
+        let metrics_data_map = data_layer
+            .get_metrics_data_map_from_db(from - Duration::from_secs(60), from)
+            .await
+            .unwrap();
+        assert!(metrics_data_map.is_empty());
+    }
 }
@@ -1,23 +1,38 @@
+mod actions;
+mod cron_last_fired;
+mod leader_lease;
+mod maintenance_window;
 mod metric;
 mod metrics_data;
+mod pending_actions;
 mod plan_logs;
+mod plan_pause;
+mod planner_state;
 mod scaling_component;
 mod scaling_plan;
 
-use crate::types::metrics_data_item::MetricsDataItem;
+use crate::reader::definition_validator::validate_definition_yaml;
+use crate::types::{
+    action::{ActionDefinition, ActionOverrides},
+    metrics_data_item::MetricsDataItem,
+    planner_request::{PlannerRequest, PlannerResponder},
+};
 use anyhow::{anyhow, Result};
-use chrono::Utc;
+use chrono::{DateTime, SecondsFormat, Utc};
 use once_cell::sync::Lazy;
 use serde_json::json;
 use sqlx::{
     any::{AnyKind, AnyPoolOptions},
     AnyPool, Row,
 };
-use std::{collections::HashMap, path::Path};
 use std::{
     collections::{BTreeMap, LinkedList},
     fs::File,
 };
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 use std::{
     io::Read,
     sync::{Arc, RwLock},
@@ -28,6 +43,11 @@ use tracing::{debug, error, info};
 const DEFAULT_DB_URL: &str = "sqlite://wave.db";
 const DEFAULT_METRICS_DATA_BUFFER_SIZE_KB: u64 = 500_000;
 
+// The timestamps in the TEXT columns have a fixed format so that they can be compared as strings in the queries
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
 /**
 **MetricsData is a struct to store metrics data**
  */
@@ -59,6 +79,12 @@ pub struct DataLayer {
     pool: AnyPool,
     metrics_data: SharedMetricsData,
     action_sender: tokio::sync::broadcast::Sender<serde_json::Value>,
+    // The plan_id of a paused or resumed plan for the running scaling planners
+    plan_pause_sender: tokio::sync::broadcast::Sender<String>,
+    // For the requests to the scaling planners (e.g. backtest). The receiver is taken by the handler in the main application.
+    planner_request_sender: tokio::sync::mpsc::Sender<(PlannerRequest, PlannerResponder)>,
+    planner_request_receiver:
+        tokio::sync::Mutex<Option<tokio::sync::mpsc::Receiver<(PlannerRequest, PlannerResponder)>>>,
 }
 
 impl DataLayer {
@@ -84,18 +110,24 @@ impl DataLayer {
             metrics_data.enable_metrics_log = enable_metrics_log;
         }
         let (action_sender, _) = tokio::sync::broadcast::channel::<serde_json::Value>(16);
+        let (plan_pause_sender, _) = tokio::sync::broadcast::channel::<String>(16);
+        let (planner_request_sender, planner_request_receiver) =
+            tokio::sync::mpsc::channel::<(PlannerRequest, PlannerResponder)>(16);
 
         DataLayer {
             pool: DataLayer::get_pool(sql_url).await,
             metrics_data: METRICS_DATA.clone(),
             action_sender,
+            plan_pause_sender,
+            planner_request_sender,
+            planner_request_receiver: tokio::sync::Mutex::new(Some(planner_request_receiver)),
         }
     }
 
     pub async fn sync(&self, definition_path: &str) {
         self.migrate().await;
 
-        // TODO: Validate the definition file before loading it into the database
+        // The definition file is validated in add_definitions before it is loaded into the database
         let is_empty = definition_path.is_empty();
         let exists = Path::new(definition_path).exists();
         if !is_empty && exists {
@@ -150,6 +182,18 @@ impl DataLayer {
 
         debug!("Connecting to the database: {}", sql_url);
 
+        // An in-memory SQLite database lives only as long as its connections, so the pool keeps a single connection open.
+        // The migrations and the queries use the same database.
+        if sql_url.contains(":memory:") {
+            return AnyPoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect(sql_url)
+                .await
+                .unwrap();
+        }
+
         AnyPoolOptions::new()
             .max_connections(5)
             .connect(sql_url)
@@ -194,10 +238,10 @@ impl DataLayer {
                 // watch all data for changed definition
                 let query_string = match database_kind {
                     AnyKind::Postgres => {
-                        "(SELECT updated_at FROM metric) UNION (SELECT updated_at FROM scaling_component) UNION (SELECT updated_at FROM plan)"
+                        "(SELECT updated_at FROM metric) UNION (SELECT updated_at FROM scaling_component) UNION (SELECT updated_at FROM plan) UNION (SELECT updated_at FROM maintenance_window)"
                     }
                     AnyKind::Sqlite => {
-                        "SELECT updated_at FROM metric; SELECT updated_at FROM scaling_component; SELECT updated_at FROM plan;"
+                        "SELECT updated_at FROM metric; SELECT updated_at FROM scaling_component; SELECT updated_at FROM plan; SELECT updated_at FROM maintenance_window;"
                     }
                     AnyKind::MySql => {
                         // Return error because MySQL is not supported yet
@@ -235,6 +279,21 @@ impl DataLayer {
     pub async fn add_definitions(&self, yaml_str: &str) -> Result<()> {
         debug!("Loading the definition string into the database");
 
+        // Validate the plans against the ids in the YAML and in the database before saving anything
+        let known_metric_ids = self
+            .get_all_metrics()
+            .await?
+            .into_iter()
+            .map(|metric| metric.id)
+            .collect::<HashSet<String>>();
+        let known_component_kinds = self
+            .get_all_scaling_components()
+            .await?
+            .into_iter()
+            .map(|component| (component.id, component.component_kind))
+            .collect::<HashMap<String, String>>();
+        validate_definition_yaml(yaml_str, &known_metric_ids, &known_component_kinds)?;
+
         let metric_definitions_result = self
             .sync_metric_yaml_for_unmatched_ids(yaml_str, false)
             .await;
@@ -258,6 +317,16 @@ impl DataLayer {
             return Err(anyhow!("Failed to save plan definitions into DataLayer"));
         }
 
+        // Save definitions into DataLayer
+        let maintenance_window_definitions_result = self
+            .add_maintenance_window_yaml_for_unmatched_ids(yaml_str, false)
+            .await;
+        if maintenance_window_definitions_result.is_err() {
+            return Err(anyhow!(
+                "Failed to save maintenance window definitions into DataLayer"
+            ));
+        }
+
         Ok(())
     }
 
@@ -273,17 +342,51 @@ impl DataLayer {
         }
         Ok(())
     }
-    pub fn send_plan_action(&self, plan_id: String, plan_item_id: String) -> Result<()> {
-        let action = json!({
+    // Queue a manual run of a plan item and wake up the scaling planners.
+    // The action is kept in the database even if no scaling planner receives the notification.
+    pub async fn send_plan_action(
+        &self,
+        plan_id: String,
+        plan_item_id: String,
+        overrides: &ActionOverrides,
+    ) -> Result<ActionDefinition> {
+        let action = self.add_action(&plan_id, &plan_item_id, overrides).await?;
+        let _ = self.send_action(json!({
+            "id": action.id,
             "plan_id": plan_id,
             "plan_item_id": plan_item_id,
-        });
-        self.send_action(action)
+        }));
+        Ok(action)
     }
     // Get an receiver of the action
     pub fn subscribe_action(&self) -> tokio::sync::broadcast::Receiver<serde_json::Value> {
         self.action_sender.subscribe()
     }
+
+    // Send a request to the scaling planners and wait for the response
+    pub async fn send_planner_request(&self, request: PlannerRequest) -> Result<serde_json::Value> {
+        if self.planner_request_receiver.lock().await.is_some() {
+            return Err(anyhow!("There is no handler for the planner requests"));
+        }
+        let (responder, response_receiver) = tokio::sync::oneshot::channel();
+        let result = self.planner_request_sender.send((request, responder)).await;
+        if result.is_err() {
+            let error_message = result.err().unwrap().to_string();
+            return Err(anyhow!(error_message));
+        }
+        let response = response_receiver.await;
+        if response.is_err() {
+            let error_message = response.err().unwrap().to_string();
+            return Err(anyhow!(error_message));
+        }
+        response.unwrap()
+    }
+    // Take the receiver of the planner requests. Only one handler can take it.
+    pub async fn take_planner_request_receiver(
+        &self,
+    ) -> Option<tokio::sync::mpsc::Receiver<(PlannerRequest, PlannerResponder)>> {
+        self.planner_request_receiver.lock().await.take()
+    }
 }
 
 #[cfg(test)]
@@ -323,4 +426,27 @@ pub mod tests {
         data_layer.sync("").await;
         data_layer
     }
+
+    #[tokio::test]
+    async fn test_in_memory_database() {
+        let data_layer = DataLayer::new(
+            "sqlite::memory:",
+            DEFAULT_METRICS_DATA_BUFFER_SIZE_KB,
+            DEFAULT_ENABLE_METRICS_LOG,
+        )
+        .await;
+        data_layer.sync("").await;
+        // The concurrent queries run on the migrated database
+        let results = tokio::join!(
+            data_layer.get_queued_actions("plan_1"),
+            data_layer.get_queued_actions("plan_1"),
+            data_layer.get_pending_actions(None),
+            data_layer.get_pending_actions(None),
+            data_layer.get_cron_last_fired("plan_1"),
+            data_layer.get_cron_last_fired("plan_1"),
+        );
+        assert!(results.0.is_ok() && results.1.is_ok());
+        assert!(results.2.is_ok() && results.3.is_ok());
+        assert!(results.4.is_ok() && results.5.is_ok());
+    }
 }
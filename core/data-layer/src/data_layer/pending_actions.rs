@@ -0,0 +1,343 @@
+use super::{format_timestamp, DataLayer};
+use crate::types::pending_action::{PendingActionDefinition, PendingActionStatus};
+use crate::types::plan_item_definition::PlanItemDefinition;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{any::AnyRow, Row};
+use std::collections::HashMap;
+use ulid::Ulid;
+
+fn row_to_pending_action(row: &AnyRow) -> Result<PendingActionDefinition> {
+    Ok(PendingActionDefinition {
+        id: row.try_get("id")?,
+        plan_db_id: row.try_get("plan_db_id")?,
+        plan_id: row.try_get("plan_id")?,
+        plan_item_id: row.try_get("plan_item_id")?,
+        plan_item_json: row.try_get("plan_item_json")?,
+        scaling_components_json: row.try_get("scaling_components_json")?,
+        status: row.try_get("status")?,
+        created_at: row.try_get("created_at")?,
+        expires_at: row.try_get("expires_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+impl DataLayer {
+    // Save a fired plan item with the resolved params of its scaling components to wait for the approval
+    pub async fn add_pending_action(
+        &self,
+        plan_db_id: &str,
+        plan_id: &str,
+        plan_item: &PlanItemDefinition,
+        scaling_components: &[HashMap<String, Value>],
+        expires_at: DateTime<Utc>,
+    ) -> Result<PendingActionDefinition> {
+        let now = format_timestamp(Utc::now());
+        let pending_action = PendingActionDefinition {
+            id: Ulid::new().to_string(),
+            plan_db_id: plan_db_id.to_string(),
+            plan_id: plan_id.to_string(),
+            plan_item_id: plan_item.id.clone(),
+            plan_item_json: serde_json::to_string(plan_item)?,
+            scaling_components_json: serde_json::to_string(scaling_components)?,
+            status: PendingActionStatus::Pending.to_string(),
+            created_at: now.clone(),
+            expires_at: format_timestamp(expires_at),
+            updated_at: now,
+        };
+        let query_string = "INSERT INTO pending_action (id, plan_db_id, plan_id, plan_item_id, plan_item_json, scaling_components_json, status, created_at, expires_at, updated_at) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)";
+        let result = sqlx::query(query_string)
+            .bind(pending_action.id.clone())
+            .bind(pending_action.plan_db_id.clone())
+            .bind(pending_action.plan_id.clone())
+            .bind(pending_action.plan_item_id.clone())
+            .bind(pending_action.plan_item_json.clone())
+            .bind(pending_action.scaling_components_json.clone())
+            .bind(pending_action.status.clone())
+            .bind(pending_action.created_at.clone())
+            .bind(pending_action.expires_at.clone())
+            .bind(pending_action.updated_at.clone())
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(pending_action)
+    }
+    // Get the pending actions (the latest first). All statuses if status is None.
+    pub async fn get_pending_actions(
+        &self,
+        status: Option<PendingActionStatus>,
+    ) -> Result<Vec<PendingActionDefinition>> {
+        let result = match status {
+            Some(status) => {
+                sqlx::query("SELECT * FROM pending_action WHERE status=$1 ORDER BY id DESC")
+                    .bind(status.to_string())
+                    .fetch_all(&self.pool)
+                    .await
+            }
+            None => {
+                sqlx::query("SELECT * FROM pending_action ORDER BY id DESC")
+                    .fetch_all(&self.pool)
+                    .await
+            }
+        };
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let mut pending_actions = Vec::new();
+        for row in result.unwrap() {
+            pending_actions.push(row_to_pending_action(&row)?);
+        }
+        Ok(pending_actions)
+    }
+    // Get a pending action by id
+    pub async fn get_pending_action(&self, id: &str) -> Result<Option<PendingActionDefinition>> {
+        let result = sqlx::query("SELECT * FROM pending_action WHERE id=$1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        match result.unwrap() {
+            Some(row) => Ok(Some(row_to_pending_action(&row)?)),
+            None => Ok(None),
+        }
+    }
+    // Whether a plan item already has an action waiting for the approval
+    pub async fn has_pending_action(&self, plan_id: &str, plan_item_id: &str) -> Result<bool> {
+        let query_string =
+            "SELECT id FROM pending_action WHERE plan_id=$1 AND plan_item_id=$2 AND status=$3";
+        let result = sqlx::query(query_string)
+            .bind(plan_id)
+            .bind(plan_item_id)
+            .bind(PendingActionStatus::Pending.to_string())
+            .fetch_optional(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(result.unwrap().is_some())
+    }
+    // The last time an action of a plan item was rejected. None if none was rejected.
+    pub async fn get_last_rejected_at(
+        &self,
+        plan_id: &str,
+        plan_item_id: &str,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let query_string = "SELECT updated_at FROM pending_action WHERE plan_id=$1 AND plan_item_id=$2 AND status=$3 ORDER BY updated_at DESC LIMIT 1";
+        let result = sqlx::query(query_string)
+            .bind(plan_id)
+            .bind(plan_item_id)
+            .bind(PendingActionStatus::Rejected.to_string())
+            .fetch_optional(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let Some(row) = result.unwrap() else {
+            return Ok(None);
+        };
+        let updated_at: String = row.try_get("updated_at")?;
+        Ok(Some(
+            DateTime::parse_from_rfc3339(&updated_at)?.with_timezone(&Utc),
+        ))
+    }
+    // Change the status of a pending action only if it still has the "from" status.
+    // Returns false if the status was already changed (e.g. approved twice, expired).
+    pub async fn update_pending_action_status(
+        &self,
+        id: &str,
+        from: PendingActionStatus,
+        to: PendingActionStatus,
+    ) -> Result<bool> {
+        let query_string =
+            "UPDATE pending_action SET status=$1, updated_at=$2 WHERE id=$3 AND status=$4";
+        let result = sqlx::query(query_string)
+            .bind(to.to_string())
+            .bind(format_timestamp(Utc::now()))
+            .bind(id)
+            .bind(from.to_string())
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(result.unwrap().rows_affected() > 0)
+    }
+    // Expire the pending actions whose expires_at has passed. Returns the number of the expired actions.
+    pub async fn expire_pending_actions(&self, now: DateTime<Utc>) -> Result<u64> {
+        let query_string =
+            "UPDATE pending_action SET status=$1, updated_at=$2 WHERE status=$3 AND expires_at<=$4";
+        let now = format_timestamp(now);
+        let result = sqlx::query(query_string)
+            .bind(PendingActionStatus::Expired.to_string())
+            .bind(now.clone())
+            .bind(PendingActionStatus::Pending.to_string())
+            .bind(now)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(result.unwrap().rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn get_plan_item(id: &str) -> PlanItemDefinition {
+        serde_json::from_value(json!({
+            "id": id,
+            "expression": "true",
+            "scaling_components": [{ "component_id": "component_1", "replicas": 3 }],
+            "ui": null,
+            "require_approval": true,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pending_actions() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        let now = Utc::now();
+        let scaling_components = vec![HashMap::from([
+            ("component_id".to_string(), json!("component_1")),
+            ("replicas".to_string(), json!(3)),
+        ])];
+        let first = data_layer
+            .add_pending_action(
+                "db_id",
+                "plan_1",
+                &get_plan_item("item_1"),
+                &scaling_components,
+                now + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        // The ids (ULID) are ordered by the millisecond
+        tokio::time::sleep(tokio::time::Duration::from_millis(2)).await;
+        let second = data_layer
+            .add_pending_action(
+                "db_id",
+                "plan_1",
+                &get_plan_item("item_2"),
+                &scaling_components,
+                now - chrono::Duration::seconds(1),
+            )
+            .await
+            .unwrap();
+        assert!(data_layer
+            .has_pending_action("plan_1", "item_1")
+            .await
+            .unwrap());
+        assert!(!data_layer
+            .has_pending_action("plan_2", "item_1")
+            .await
+            .unwrap());
+        let saved = data_layer
+            .get_pending_action(first.id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved, first);
+        let saved_components: Vec<HashMap<String, Value>> =
+            serde_json::from_str(saved.scaling_components_json.as_str()).unwrap();
+        assert_eq!(saved_components, scaling_components);
+
+        // Only the second one has passed expires_at
+        assert_eq!(data_layer.expire_pending_actions(now).await.unwrap(), 1);
+        let expired = data_layer
+            .get_pending_actions(Some(PendingActionStatus::Expired))
+            .await
+            .unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, second.id);
+        assert!(!data_layer
+            .has_pending_action("plan_1", "item_2")
+            .await
+            .unwrap());
+
+        // The status changes only once
+        assert!(data_layer
+            .update_pending_action_status(
+                first.id.as_str(),
+                PendingActionStatus::Pending,
+                PendingActionStatus::Approved
+            )
+            .await
+            .unwrap());
+        assert!(!data_layer
+            .update_pending_action_status(
+                first.id.as_str(),
+                PendingActionStatus::Pending,
+                PendingActionStatus::Rejected
+            )
+            .await
+            .unwrap());
+        assert!(!data_layer
+            .update_pending_action_status(
+                second.id.as_str(),
+                PendingActionStatus::Pending,
+                PendingActionStatus::Approved
+            )
+            .await
+            .unwrap());
+
+        assert!(data_layer
+            .get_last_rejected_at("plan_1", "item_1")
+            .await
+            .unwrap()
+            .is_none());
+        tokio::time::sleep(tokio::time::Duration::from_millis(2)).await;
+        let third = data_layer
+            .add_pending_action(
+                "db_id",
+                "plan_1",
+                &get_plan_item("item_1"),
+                &scaling_components,
+                now + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        assert!(data_layer
+            .update_pending_action_status(
+                third.id.as_str(),
+                PendingActionStatus::Pending,
+                PendingActionStatus::Rejected
+            )
+            .await
+            .unwrap());
+        let rejected_at = data_layer
+            .get_last_rejected_at("plan_1", "item_1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(rejected_at >= now - chrono::Duration::seconds(1));
+        assert!(data_layer
+            .get_last_rejected_at("plan_1", "item_2")
+            .await
+            .unwrap()
+            .is_none());
+
+        // The latest first
+        let all = data_layer.get_pending_actions(None).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].id, third.id);
+        assert_eq!(all[0].status, "REJECTED");
+        assert_eq!(all[1].id, second.id);
+        assert_eq!(all[1].status, "EXPIRED");
+        assert_eq!(all[2].status, "APPROVED");
+        assert!(data_layer
+            .get_pending_action("unknown")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,5 +1,5 @@
 use super::DataLayer;
-use crate::types::plan_log_definition::PlanLogDefinition;
+use crate::types::plan_log_definition::{PlanLogDefinition, PlanLogStatus};
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde_json::json;
@@ -9,7 +9,7 @@ use ulid::Ulid;
 impl DataLayer {
     // Add plan log to the database
     pub async fn add_plan_logs(&self, plan_log: PlanLogDefinition) -> Result<()> {
-        let query_string = "INSERT INTO plan_log (id, plan_db_id, plan_id, plan_item_json, metric_values_json, metadata_values_json, fail_message) VALUES ($1,$2,$3,$4,$5,$6,$7)";
+        let query_string = "INSERT INTO plan_log (id, plan_db_id, plan_id, plan_item_json, metric_values_json, metadata_values_json, fail_message, status, variables_json, overrides_json) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)";
         let id = Ulid::new().to_string();
         let result = sqlx::query(query_string)
             // INTO
@@ -20,6 +20,9 @@ impl DataLayer {
             .bind(plan_log.metric_values_json)
             .bind(plan_log.metadata_values_json)
             .bind(plan_log.fail_message)
+            .bind(plan_log.status)
+            .bind(plan_log.variables_json)
+            .bind(plan_log.overrides_json)
             .execute(&self.pool)
             .await;
 
@@ -44,7 +47,7 @@ impl DataLayer {
         let to = Ulid::from_parts(to_date.timestamp_millis() as u64, 0).to_string();
 
         // Query
-        let mut query_string = "SELECT id, plan_db_id, plan_id, plan_item_json, metric_values_json, metadata_values_json, fail_message FROM plan_log WHERE id BETWEEN $1 AND $2".to_string();
+        let mut query_string = "SELECT id, plan_db_id, plan_id, plan_item_json, metric_values_json, metadata_values_json, fail_message, status, variables_json, overrides_json FROM plan_log WHERE id BETWEEN $1 AND $2".to_string();
         if plan_id.is_some() {
             query_string += " AND plan_id=$3";
         }
@@ -70,12 +73,16 @@ impl DataLayer {
                 metric_values_json: row.try_get("metric_values_json")?,
                 metadata_values_json: row.try_get("metadata_values_json")?,
                 fail_message: row.try_get("fail_message")?,
+                status: row.try_get("status")?,
+                variables_json: row.try_get("variables_json")?,
+                overrides_json: row.try_get("overrides_json")?,
             });
         }
         Ok(plan_logs)
     }
     pub async fn generate_plan_log_samples(&self, sample_size: usize) -> Result<()> {
         for _ in 0..sample_size {
+            let fail = rand::random();
             let plan_logs = PlanLogDefinition {
                 id: Ulid::new().to_string(),
                 plan_db_id: Ulid::new().to_string(),
@@ -94,11 +101,23 @@ impl DataLayer {
                     "id": Ulid::new().to_string(),
                 })
                 .to_string(),
-                fail_message: if rand::random() {
+                fail_message: if fail {
                     Some("test_fail_message".to_string())
                 } else {
                     None
                 },
+                status: if fail {
+                    Some(PlanLogStatus::Fail.to_string())
+                } else {
+                    Some(PlanLogStatus::Success.to_string())
+                },
+                variables_json: Some(
+                    json!({
+                        "$value": rand::random::<f64>(),
+                    })
+                    .to_string(),
+                ),
+                overrides_json: None,
             };
             self.add_plan_logs(plan_logs).await?;
         }
@@ -141,6 +160,9 @@ mod tests {
             metric_values_json: "test_metric_values_json".to_string(),
             metadata_values_json: "test_metadata_values_json".to_string(),
             fail_message: Some("test_fail_message".to_string()),
+            status: Some(PlanLogStatus::Fail.to_string()),
+            variables_json: Some("test_variables_json".to_string()),
+            overrides_json: Some("test_overrides_json".to_string()),
         }
     }
 
@@ -172,6 +194,8 @@ mod tests {
 
         let result = result.unwrap();
         assert_eq!(result[0].plan_db_id, plan_log_definition.plan_db_id);
+        assert_eq!(result[0].variables_json, plan_log_definition.variables_json);
+        assert_eq!(result[0].overrides_json, plan_log_definition.overrides_json);
 
         // Get a plan log from the database by plan_id
         let result = data_layer
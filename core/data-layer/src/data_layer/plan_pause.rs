@@ -0,0 +1,147 @@
+use super::{format_timestamp, DataLayer};
+use crate::types::plan_pause::PlanPauseDefinition;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{any::AnyRow, Row};
+
+fn row_to_plan_pause(row: &AnyRow) -> Result<PlanPauseDefinition> {
+    Ok(PlanPauseDefinition {
+        plan_id: row.try_get("plan_id")?,
+        paused_at: row.try_get("paused_at")?,
+        paused_until: row.try_get("paused_until")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+impl DataLayer {
+    // Pause a plan until the time (None until it is resumed). Pausing a paused plan replaces the pause.
+    pub async fn pause_plan(
+        &self,
+        plan_id: &str,
+        paused_until: Option<DateTime<Utc>>,
+    ) -> Result<PlanPauseDefinition> {
+        let now = format_timestamp(Utc::now());
+        let plan_pause = PlanPauseDefinition {
+            plan_id: plan_id.to_string(),
+            paused_at: now.clone(),
+            paused_until: paused_until.map(format_timestamp),
+            updated_at: now,
+        };
+        let query_string = "INSERT INTO plan_pause (plan_id, paused_at, paused_until, updated_at) VALUES ($1,$2,$3,$4) ON CONFLICT (plan_id) DO UPDATE SET (paused_at, paused_until, updated_at) = ($5,$6,$7)";
+        let result = sqlx::query(query_string)
+            .bind(plan_pause.plan_id.clone())
+            .bind(plan_pause.paused_at.clone())
+            .bind(plan_pause.paused_until.clone())
+            .bind(plan_pause.updated_at.clone())
+            .bind(plan_pause.paused_at.clone())
+            .bind(plan_pause.paused_until.clone())
+            .bind(plan_pause.updated_at.clone())
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let _ = self.plan_pause_sender.send(plan_pause.plan_id.clone());
+        Ok(plan_pause)
+    }
+    // Resume a paused plan. Returns false if the plan was not paused.
+    pub async fn resume_plan(&self, plan_id: &str) -> Result<bool> {
+        let was_paused = self.get_plan_pause(plan_id).await?.is_some();
+        let result = sqlx::query("DELETE FROM plan_pause WHERE plan_id=$1")
+            .bind(plan_id)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let _ = self.plan_pause_sender.send(plan_id.to_string());
+        Ok(was_paused)
+    }
+    // Get a receiver of the plan_id of the paused or resumed plans in this instance
+    pub fn subscribe_plan_pause(&self) -> tokio::sync::broadcast::Receiver<String> {
+        self.plan_pause_sender.subscribe()
+    }
+    // Get the pause of a plan if it is paused now
+    pub async fn get_plan_pause(&self, plan_id: &str) -> Result<Option<PlanPauseDefinition>> {
+        let query_string = "SELECT * FROM plan_pause WHERE plan_id=$1 AND (paused_until IS NULL OR paused_until>$2)";
+        let result = sqlx::query(query_string)
+            .bind(plan_id)
+            .bind(format_timestamp(Utc::now()))
+            .fetch_optional(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        match result.unwrap() {
+            Some(row) => Ok(Some(row_to_plan_pause(&row)?)),
+            None => Ok(None),
+        }
+    }
+    // Get the pauses of the plans that are paused now
+    pub async fn get_plan_pauses(&self) -> Result<Vec<PlanPauseDefinition>> {
+        let query_string = "SELECT * FROM plan_pause WHERE paused_until IS NULL OR paused_until>$1";
+        let result = sqlx::query(query_string)
+            .bind(format_timestamp(Utc::now()))
+            .fetch_all(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let mut plan_pauses = Vec::new();
+        for row in result.unwrap() {
+            plan_pauses.push(row_to_plan_pause(&row)?);
+        }
+        Ok(plan_pauses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_plan_pause() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        assert!(data_layer.get_plan_pause("plan_1").await.unwrap().is_none());
+        let mut receiver = data_layer.subscribe_plan_pause();
+
+        let plan_pause = data_layer.pause_plan("plan_1", None).await.unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), "plan_1");
+        assert_eq!(
+            data_layer.get_plan_pause("plan_1").await.unwrap(),
+            Some(plan_pause)
+        );
+        // Pausing again replaces the pause
+        let paused_until = Utc::now() + chrono::Duration::hours(2);
+        let plan_pause = data_layer
+            .pause_plan("plan_1", Some(paused_until))
+            .await
+            .unwrap();
+        assert_eq!(
+            plan_pause.paused_until,
+            Some(format_timestamp(paused_until))
+        );
+        assert_eq!(
+            data_layer.get_plan_pauses().await.unwrap(),
+            vec![plan_pause]
+        );
+
+        // An expired pause is ignored
+        data_layer
+            .pause_plan("plan_2", Some(Utc::now() - chrono::Duration::seconds(1)))
+            .await
+            .unwrap();
+        assert!(data_layer.get_plan_pause("plan_2").await.unwrap().is_none());
+        assert_eq!(data_layer.get_plan_pauses().await.unwrap().len(), 1);
+
+        // The running scaling planners are notified of every pause and resume
+        for _ in 0..2 {
+            receiver.try_recv().unwrap();
+        }
+        assert!(data_layer.resume_plan("plan_1").await.unwrap());
+        assert_eq!(receiver.try_recv().unwrap(), "plan_1");
+        assert!(!data_layer.resume_plan("plan_1").await.unwrap());
+        assert!(data_layer.get_plan_pause("plan_1").await.unwrap().is_none());
+    }
+}
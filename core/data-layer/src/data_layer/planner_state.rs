@@ -0,0 +1,85 @@
+use super::DataLayer;
+use crate::types::planner_state::PlannerState;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use sqlx::Row;
+
+impl DataLayer {
+    // Get the runtime state of a planner
+    pub async fn get_planner_state(&self, plan_id: &str) -> Result<Option<PlannerState>> {
+        let query_string = "SELECT state FROM planner_state WHERE plan_id=$1";
+        let result = sqlx::query(query_string)
+            .bind(plan_id)
+            .fetch_optional(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        let Some(row) = result.unwrap() else {
+            return Ok(None);
+        };
+        let state: String = row.try_get("state")?;
+        Ok(Some(serde_json::from_str(state.as_str())?))
+    }
+    // Save the runtime state of a planner
+    pub async fn save_planner_state(&self, plan_id: &str, state: &PlannerState) -> Result<()> {
+        let query_string = "INSERT INTO planner_state (plan_id, state, updated_at) VALUES ($1,$2,$3) ON CONFLICT (plan_id) DO UPDATE SET (state, updated_at) = ($4,$5)";
+        let state = serde_json::to_string(state)?;
+        let updated_at = Utc::now().to_rfc3339();
+        let result = sqlx::query(query_string)
+            .bind(plan_id)
+            .bind(state.clone())
+            .bind(updated_at.clone())
+            .bind(state)
+            .bind(updated_at)
+            .execute(&self.pool)
+            .await;
+        if result.is_err() {
+            return Err(anyhow!(result.err().unwrap().to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::planner_state::ComponentAction;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_planner_state() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        assert!(data_layer
+            .get_planner_state("plan_1")
+            .await
+            .unwrap()
+            .is_none());
+
+        let now = Utc::now();
+        let mut state = PlannerState {
+            last_plan_item_id: "item_1".to_string(),
+            component_actions: HashMap::from([(
+                "component_1".to_string(),
+                ComponentAction {
+                    plan_item_id: "item_1".to_string(),
+                    applied_at: now,
+                    cool_down_until: now + chrono::Duration::seconds(60),
+                },
+            )]),
+        };
+        data_layer
+            .save_planner_state("plan_1", &state)
+            .await
+            .unwrap();
+        state.last_plan_item_id = "item_2".to_string();
+        data_layer
+            .save_planner_state("plan_1", &state)
+            .await
+            .unwrap();
+
+        let saved_state = data_layer.get_planner_state("plan_1").await.unwrap();
+        assert_eq!(saved_state, Some(state));
+    }
+}
@@ -58,7 +58,8 @@ impl DataLayer {
             let variables_string = serde_json::to_string(&plan.variables).unwrap();
             let plans_string = serde_json::to_string(&plan.plans).unwrap();
             let metatdata_string = serde_json::to_string(&plan.metadata).unwrap();
-            let query_string = "INSERT INTO plan (db_id, id, metadata, variables, plans, enabled, yaml, created_at, updated_at) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9) ON CONFLICT (id) DO UPDATE SET (metadata, variables, plans, enabled, yaml, updated_at) = ($10, $11, $12, $13, $14, $15)";
+            let behavior_string = serde_json::to_string(&plan.behavior).unwrap();
+            let query_string = "INSERT INTO plan (db_id, id, metadata, variables, plans, enabled, yaml, created_at, updated_at, behavior) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10) ON CONFLICT (id) DO UPDATE SET (metadata, variables, plans, enabled, yaml, updated_at, behavior) = ($11, $12, $13, $14, $15, $16, $17)";
             let id = Uuid::new_v4().to_string();
             let updated_at = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
             let result = sqlx::query(query_string)
@@ -72,6 +73,7 @@ impl DataLayer {
                 .bind(yaml.clone())
                 .bind(updated_at.clone())
                 .bind(updated_at.clone())
+                .bind(behavior_string.clone())
                 // Values for update
                 .bind(metatdata_string.clone())
                 .bind(variables_string.clone())
@@ -79,6 +81,7 @@ impl DataLayer {
                 .bind(plan.enabled)
                 .bind(yaml)
                 .bind(updated_at.clone())
+                .bind(behavior_string)
                 .execute(&self.pool)
                 .await;
             if result.is_err() {
@@ -91,7 +94,7 @@ impl DataLayer {
     pub async fn get_all_plans(&self) -> Result<Vec<ScalingPlanDefinition>> {
         let mut plans: Vec<ScalingPlanDefinition> = Vec::new();
         let query_string =
-            "SELECT db_id, id, variables, plans, priority, metadata, enabled FROM plan";
+            "SELECT db_id, id, variables, plans, priority, metadata, enabled, behavior FROM plan";
         let result = sqlx::query(query_string).fetch_all(&self.pool).await;
         if result.is_err() {
             return Err(anyhow!(result.err().unwrap().to_string()));
@@ -133,6 +136,15 @@ impl DataLayer {
                 }
             }
 
+            let mut behavior = None;
+            let behavior_row = row.try_get::<&str, _>("behavior");
+            if behavior_row.is_ok() {
+                let json = serde_json::from_str(behavior_row.unwrap());
+                if json.is_ok() {
+                    behavior = json.unwrap();
+                }
+            }
+
             plans.push(ScalingPlanDefinition {
                 kind: ObjectKind::ScalingPlan,
                 db_id: row.try_get::<String, _>("db_id")?,
@@ -141,6 +153,7 @@ impl DataLayer {
                 variables,
                 plans: plan_items,
                 enabled: row.try_get::<bool, _>("enabled").unwrap_or(false),
+                behavior,
             });
         }
         Ok(plans)
@@ -158,7 +171,7 @@ impl DataLayer {
     pub async fn get_all_plans_json(&self) -> Result<Vec<serde_json::Value>> {
         let mut plans: Vec<serde_json::Value> = Vec::new();
         let query_string =
-            "SELECT db_id, id, variables, plans, priority, metadata, enabled, yaml, created_at, updated_at FROM plan";
+            "SELECT db_id, id, variables, plans, priority, metadata, enabled, yaml, created_at, updated_at, behavior FROM plan";
         let result = sqlx::query(query_string).fetch_all(&self.pool).await;
         if result.is_err() {
             return Err(anyhow!(result.err().unwrap().to_string()));
@@ -173,6 +186,7 @@ impl DataLayer {
                 "plans": serde_json::from_str::<serde_json::Value>(row.try_get::<String, _>("plans")?.as_str())?,
                 "metadata": serde_json::from_str::<serde_json::Value>(row.try_get::<String, _>("metadata")?.as_str())?,
                 "enabled": row.try_get::<bool, _>("enabled")?,
+                "behavior": row.try_get::<Option<String>, _>("behavior")?.and_then(|behavior| serde_json::from_str::<serde_json::Value>(behavior.as_str()).ok()),
                 "yaml": row.try_get::<String, _>("yaml")?,
                 "created_at": row.try_get::<Option<String>, _>("created_at")?,
                 "updated_at": row.try_get::<Option<String>, _>("updated_at")?,
@@ -184,7 +198,7 @@ impl DataLayer {
     // Get a plan from the database
     pub async fn get_plan_by_id(&self, db_id: String) -> Result<ScalingPlanDefinition> {
         let query_string =
-            "SELECT db_id, id, metadata, variables, plans, enabled FROM plan WHERE db_id=$1";
+            "SELECT db_id, id, metadata, variables, plans, enabled, behavior FROM plan WHERE db_id=$1";
         let result = sqlx::query(query_string)
             .bind(db_id)
             .fetch_one(&self.pool)
@@ -201,6 +215,10 @@ impl DataLayer {
             variables: serde_json::from_str(result.get("variables")).unwrap(),
             plans: serde_json::from_str(result.get("plans")).unwrap(),
             enabled: result.get("enabled"),
+            behavior: result
+                .get::<Option<String>, _>("behavior")
+                .and_then(|behavior| serde_json::from_str(behavior.as_str()).ok())
+                .flatten(),
         };
         Ok(plan)
     }
@@ -233,8 +251,9 @@ impl DataLayer {
     pub async fn update_plan(&self, plan: ScalingPlanDefinition) -> Result<AnyQueryResult> {
         let plans_string = serde_json::to_string(&plan.plans).unwrap();
         let metatdata_string = serde_json::to_string(&plan.metadata).unwrap();
+        let behavior_string = serde_json::to_string(&plan.behavior).unwrap();
         let query_string =
-            "UPDATE plan SET id=$1, metadata=$2, plans=$3, updated_at=$4, enabled=$5 WHERE db_id=$6";
+            "UPDATE plan SET id=$1, metadata=$2, plans=$3, updated_at=$4, enabled=$5, behavior=$6 WHERE db_id=$7";
         let updated_at = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
         let result = sqlx::query(query_string)
             // SET
@@ -243,6 +262,7 @@ impl DataLayer {
             .bind(plans_string)
             .bind(updated_at)
             .bind(plan.enabled)
+            .bind(behavior_string)
             // WHERE
             .bind(plan.db_id)
             .execute(&self.pool)
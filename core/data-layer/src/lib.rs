@@ -2,6 +2,7 @@ pub mod data_layer;
 pub mod reader;
 pub mod types;
 pub mod values_map;
+pub use crate::types::maintenance_window_definition::MaintenanceWindowDefinition;
 pub use crate::types::metric_definition::MetricDefinition;
 pub use crate::types::scaling_component_definition::ScalingComponentDefinition;
 pub use crate::types::scaling_plan_definition::ScalingPlanDefinition;
@@ -0,0 +1,545 @@
+use crate::{
+    types::{
+        apply_policy::ApplyPolicy,
+        max_actions::MaxActions,
+        object_kind::ObjectKind,
+        plan_expression_stats::PlanExpressionStats,
+        plan_item_definition::{ApplyMode, TRANSACTIONAL_COMPONENT_KINDS},
+    },
+    ScalingComponentDefinition, ScalingPlanDefinition,
+};
+use rquickjs::{CatchResultExt, CaughtError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_yaml::Deserializer;
+use std::collections::{HashMap, HashSet};
+use ts_rs::TS;
+
+/**
+ * DefinitionValidationError
+ * An error of a definition that is found before the definition is accepted
+ * - document_index: the index of the YAML document (0-based)
+ * - plan_item_id: the plan item that has the error (None for the variables and the metadata)
+ */
+#[derive(TS)]
+#[ts(
+    export,
+    export_to = "../web-app/src/types/bindings/definition-validation-error.ts"
+)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DefinitionValidationError {
+    pub document_index: usize,
+    pub plan_item_id: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for DefinitionValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.plan_item_id {
+            Some(plan_item_id) => write!(
+                f,
+                "[document {}] [plan item {}] {}",
+                self.document_index, plan_item_id, self.message
+            ),
+            None => write!(f, "[document {}] {}", self.document_index, self.message),
+        }
+    }
+}
+
+/**
+ * DefinitionValidationErrors
+ * The errors of the definitions. It can be downcast from anyhow::Error to return the structured errors.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefinitionValidationErrors(pub Vec<DefinitionValidationError>);
+
+impl std::fmt::Display for DefinitionValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let messages = self
+            .0
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<String>>();
+        write!(f, "Invalid definitions\n{}", messages.join("\n"))
+    }
+}
+
+impl std::error::Error for DefinitionValidationErrors {}
+
+/**
+Validate the scaling plans in the definition YAML before the definitions are accepted
+- The expressions and the variables are compiled in QuickJS (they are not executed)
+- The metric ids in get() should be defined in the YAML or in known_metric_ids
+- The stats in get() should be one of PlanExpressionStats
+- The component ids of the plan items should be defined in the YAML or in known_component_kinds (id => component_kind)
+- The scaling components of the transactional plan items should be one of TRANSACTIONAL_COMPONENT_KINDS
+- The timeout, retry, circuit_breaker and max_actions in the metadata of the scaling components should be valid
+- max_actions in the metadata of the scaling plans should be valid
+- The documents that cannot be parsed are left to the parsers
+ */
+pub fn validate_definition_yaml(
+    yaml: &str,
+    known_metric_ids: &HashSet<String>,
+    known_component_kinds: &HashMap<String, String>,
+) -> Result<(), DefinitionValidationErrors> {
+    validate(yaml, Some((known_metric_ids, known_component_kinds)))
+}
+
+/**
+Validate the plan expressions in the definition YAML without the metric and component ids
+- The expressions and the variables are compiled and the stats in get() are checked
+- The metadata of the scaling components and max_actions of the scaling plans are checked
+- The ids may be defined in another file or in the database, so they are checked when the definitions are added (validate_definition_yaml)
+ */
+pub fn validate_plan_expressions(yaml: &str) -> Result<(), DefinitionValidationErrors> {
+    validate(yaml, None)
+}
+
+// known_ids: the known metric ids and component ids with their kinds. The ids are not checked if None.
+fn validate(
+    yaml: &str,
+    known_ids: Option<(&HashSet<String>, &HashMap<String, String>)>,
+) -> Result<(), DefinitionValidationErrors> {
+    let mut ids =
+        known_ids.map(|(metric_ids, component_ids)| (metric_ids.clone(), component_ids.clone()));
+    let mut plans: Vec<(usize, ScalingPlanDefinition)> = Vec::new();
+    let mut errors = Vec::new();
+    let mut last_error: Option<String> = None;
+    for (document_index, document) in Deserializer::from_str(yaml).enumerate() {
+        // A document with an error (e.g. a duplicate key) is skipped.
+        // After a syntax error, serde_yaml returns the same error for every next document, so the rest is not read.
+        let value = match serde_yaml::Value::deserialize(document) {
+            Ok(value) => value,
+            Err(error) => {
+                let error = error.to_string();
+                if last_error.as_ref() == Some(&error) {
+                    break;
+                }
+                last_error = Some(error);
+                continue;
+            }
+        };
+        let kind = value.get("kind").and_then(serde_yaml::Value::as_str);
+        let id = value.get("id").and_then(serde_yaml::Value::as_str);
+        match (kind, id) {
+            (Some(kind), Some(id)) if kind == ObjectKind::Metric.to_string() => {
+                if let Some((metric_ids, _)) = ids.as_mut() {
+                    metric_ids.insert(id.to_string());
+                }
+            }
+            (Some(kind), Some(_)) if kind == ObjectKind::ScalingComponent.to_string() => {
+                if let Ok(component) = serde_yaml::from_value::<ScalingComponentDefinition>(value) {
+                    if let Some((_, component_kinds)) = ids.as_mut() {
+                        component_kinds
+                            .insert(component.id.clone(), component.component_kind.clone());
+                    }
+                    for message in validate_component_metadata(&component) {
+                        errors.push(DefinitionValidationError {
+                            document_index,
+                            plan_item_id: None,
+                            message,
+                        });
+                    }
+                }
+            }
+            (Some(kind), _) if kind == ObjectKind::ScalingPlan.to_string() => {
+                if let Ok(plan) = serde_yaml::from_value::<ScalingPlanDefinition>(value) {
+                    if let Err(error) = MaxActions::from_metadata(&plan.metadata) {
+                        errors.push(DefinitionValidationError {
+                            document_index,
+                            plan_item_id: None,
+                            message: format!("The scaling plan {}: {}", plan.id, error),
+                        });
+                    }
+                    plans.push((document_index, plan));
+                }
+            }
+            _ => {}
+        }
+    }
+    if !plans.is_empty() {
+        validate_plans(&plans, ids.as_ref(), &mut errors);
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(DefinitionValidationErrors(errors))
+    }
+}
+
+// The metadata of a scaling component that is parsed when the scaling component is added
+fn validate_component_metadata(component: &ScalingComponentDefinition) -> Vec<String> {
+    let mut messages = Vec::new();
+    if let Err(error) = ApplyPolicy::from_metadata(&component.metadata) {
+        messages.push(format!("The scaling component {}: {}", component.id, error));
+    }
+    if let Err(error) = MaxActions::from_metadata(&component.metadata) {
+        messages.push(format!("The scaling component {}: {}", component.id, error));
+    }
+    messages
+}
+
+// Compile the expressions and the variables of the scaling plans and check their ids (if Some)
+fn validate_plans(
+    plans: &[(usize, ScalingPlanDefinition)],
+    ids: Option<&(HashSet<String>, HashMap<String, String>)>,
+    errors: &mut Vec<DefinitionValidationError>,
+) {
+    let Ok(runtime) = rquickjs::Runtime::new() else {
+        return;
+    };
+    let Ok(context) = rquickjs::Context::full(&runtime) else {
+        return;
+    };
+    let metric_ids = ids.map(|(metric_ids, _)| metric_ids);
+    let component_kinds = ids.map(|(_, component_kinds)| component_kinds);
+    for (document_index, plan) in plans.iter() {
+        let mut push_error = |plan_item_id: Option<&str>, message: String| {
+            errors.push(DefinitionValidationError {
+                document_index: *document_index,
+                plan_item_id: plan_item_id.map(str::to_string),
+                message,
+            });
+        };
+
+        // The variables are evaluated as "var $key = value;"
+        let mut variable_keys = plan.variables.keys().collect::<Vec<&String>>();
+        variable_keys.sort();
+        for key in variable_keys {
+            let Some(Value::String(variable)) = plan.variables.get(key) else {
+                continue;
+            };
+            if let Err(error) = compile_js(&context, &format!("var ${} = {};", key, variable)) {
+                push_error(
+                    None,
+                    format!("Failed to compile the variable ${}: {}", key, error),
+                );
+            }
+            for message in validate_get_calls(variable, metric_ids) {
+                push_error(None, format!("The variable ${}: {}", key, message));
+            }
+        }
+
+        for plan_item in plan.plans.iter() {
+            if let Some(expression) = plan_item.expression.as_ref() {
+                if let Err(error) = compile_js(&context, expression) {
+                    push_error(
+                        Some(&plan_item.id),
+                        format!("Failed to compile the expression: {}", error),
+                    );
+                }
+                for message in validate_get_calls(expression, metric_ids) {
+                    push_error(Some(&plan_item.id), message);
+                }
+            }
+            let Some(component_kinds) = component_kinds else {
+                continue;
+            };
+            let transactional = plan_item.apply_mode == Some(ApplyMode::Transactional);
+            for metadata in plan_item.scaling_components.iter() {
+                let Some(component_id) = metadata.get("component_id").and_then(Value::as_str)
+                else {
+                    continue;
+                };
+                match component_kinds.get(component_id) {
+                    None => push_error(
+                        Some(&plan_item.id),
+                        format!("Unknown component_id: {}", component_id),
+                    ),
+                    Some(component_kind)
+                        if transactional
+                            && !TRANSACTIONAL_COMPONENT_KINDS.contains(&component_kind.as_str()) =>
+                    {
+                        push_error(
+                            Some(&plan_item.id),
+                            format!(
+                                "The transactional apply mode does not support {} ({}). It supports {}",
+                                component_id,
+                                component_kind,
+                                TRANSACTIONAL_COMPONENT_KINDS.join(", ")
+                            ),
+                        )
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+}
+
+// Compile the source as the body of a function so that it is not executed
+fn compile_js(context: &rquickjs::Context, source: &str) -> Result<(), String> {
+    context.with(|ctx| {
+        let function_constructor = ctx
+            .globals()
+            .get::<_, rquickjs::Function>("Function")
+            .map_err(|error| error.to_string())?;
+        match function_constructor
+            .call::<_, rquickjs::Value>((source,))
+            .catch(ctx)
+        {
+            Ok(_) => Ok(()),
+            Err(CaughtError::Exception(exception)) => Err(exception
+                .message()
+                .unwrap_or_else(|| "Unknown error".to_string())),
+            Err(error) => Err(error.to_string()),
+        }
+    })
+}
+
+// Check the metric ids (if Some) and the stats of the get() calls in the source (e.g. get({ metric_id: 'cpu', stats: 'avg' }))
+fn validate_get_calls(source: &str, metric_ids: Option<&HashSet<String>>) -> Vec<String> {
+    let re_get_fn = regex::Regex::new(r"get\([^)]*\)").unwrap();
+    let re_metric_id = regex::Regex::new(r#"metric_id\s*:\s*['"]([^'"]*)['"]"#).unwrap();
+    let re_stats = regex::Regex::new(r#"stats\s*:\s*['"]([^'"]*)['"]"#).unwrap();
+    let mut messages = Vec::new();
+    for get_call in re_get_fn.find_iter(source) {
+        let get_call = get_call.as_str();
+        if let (Some(metric_ids), Some(captures)) = (metric_ids, re_metric_id.captures(get_call)) {
+            let metric_id = &captures[1];
+            if !metric_ids.contains(metric_id) {
+                messages.push(format!("Unknown metric_id in get(): {}", metric_id));
+            }
+        }
+        if let Some(captures) = re_stats.captures(get_call) {
+            let stats = &captures[1];
+            if PlanExpressionStats::from_name(stats).is_none() {
+                let names = PlanExpressionStats::ALL
+                    .iter()
+                    .map(|stats| stats.to_string())
+                    .collect::<Vec<String>>();
+                messages.push(format!(
+                    "Unknown stats in get(): {}. It should be one of {}",
+                    stats,
+                    names.join(", ")
+                ));
+            }
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = r#"
+---
+kind: Metric
+id: cpu_metric
+collector: vector
+---
+kind: ScalingComponent
+id: web_component
+component_kind: k8s-deployment
+---
+kind: ScalingPlan
+id: scaling_plan
+metadata: {}
+variables:
+  threshold: "get({ metric_id: 'memory_metric', stats: 'avg' })"
+plans:
+  - id: valid_item
+    expression: "get({ metric_id: 'cpu_metric', stats: 'avg', period_sec: 60 }) > $threshold"
+    priority: 2
+    scaling_components:
+      - component_id: web_component
+        replicas: 3
+  - id: broken_item
+    expression: "get({ metric_id: 'cpu_metric', stats: 'median' }) > (1"
+    priority: 1
+    scaling_components:
+      - component_id: db_component
+        replicas: 1
+"#;
+
+    #[test]
+    fn test_validate_definition_yaml() {
+        let errors = validate_definition_yaml(YAML, &HashSet::new(), &HashMap::new())
+            .unwrap_err()
+            .0;
+        let messages = errors
+            .iter()
+            .map(|error| (error.plan_item_id.clone(), error.message.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(errors.len(), 4);
+        assert!(errors.iter().all(|error| error.document_index == 2));
+        assert_eq!(
+            messages[0],
+            (
+                None,
+                "The variable $threshold: Unknown metric_id in get(): memory_metric".to_string()
+            )
+        );
+        assert_eq!(messages[1].0, Some("broken_item".to_string()));
+        assert!(messages[1]
+            .1
+            .starts_with("Failed to compile the expression"));
+        assert!(messages[2].1.starts_with("Unknown stats in get(): median"));
+        assert_eq!(
+            messages[3],
+            (
+                Some("broken_item".to_string()),
+                "Unknown component_id: db_component".to_string()
+            )
+        );
+
+        // The ids that are already known (e.g. in the database) are resolved
+        let errors = validate_definition_yaml(
+            YAML,
+            &HashSet::from(["memory_metric".to_string()]),
+            &HashMap::from([("db_component".to_string(), "wa-logger".to_string())]),
+        )
+        .unwrap_err()
+        .0;
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_validate_definition_yaml_does_not_execute() {
+        let yaml = r#"
+kind: ScalingPlan
+id: scaling_plan
+metadata: {}
+plans:
+  - id: loop_item
+    expression: "while(true) {}"
+    priority: 1
+    scaling_components: []
+"#;
+        assert!(validate_definition_yaml(yaml, &HashSet::new(), &HashMap::new()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_component_metadata() {
+        let yaml = r#"
+---
+kind: ScalingComponent
+id: valid_component
+component_kind: k8s-deployment
+metadata:
+  timeout: 10s
+  retry:
+    max_attempts: 3
+  circuit_breaker:
+    failure_threshold: 5
+  max_actions: 6 per 1h
+---
+kind: ScalingComponent
+id: invalid_component
+component_kind: k8s-deployment
+metadata:
+  timeout: ten seconds
+  max_actions: 6
+"#;
+        // The metadata is checked with or without the known ids
+        let errors = validate_plan_expressions(yaml).unwrap_err().0;
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|error| error.document_index == 1));
+        assert!(errors[0]
+            .message
+            .starts_with("The scaling component invalid_component: Invalid timeout: ten seconds"));
+        assert!(errors[1]
+            .message
+            .starts_with("The scaling component invalid_component: Invalid max_actions: 6"));
+        let errors = validate_definition_yaml(yaml, &HashSet::new(), &HashMap::new())
+            .unwrap_err()
+            .0;
+        assert_eq!(errors.len(), 2);
+
+        let yaml = r#"
+kind: ScalingPlan
+id: scaling_plan
+metadata:
+  max_actions: 0 per 1h
+plans: []
+"#;
+        let errors = validate_plan_expressions(yaml).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].plan_item_id, None);
+        assert!(errors[0]
+            .message
+            .starts_with("The scaling plan scaling_plan: Invalid max_actions: 0 per 1h"));
+    }
+
+    #[test]
+    fn test_validate_transactional_component_kinds() {
+        let yaml = r#"
+---
+kind: ScalingComponent
+id: k8s_component
+component_kind: kubernetes-deployment
+---
+kind: ScalingComponent
+id: lambda_component
+component_kind: aws-lambda
+---
+kind: ScalingPlan
+id: scaling_plan
+metadata: {}
+plans:
+  - id: transactional_item
+    expression: "true"
+    priority: 1
+    apply_mode: transactional
+    scaling_components:
+      - component_id: k8s_component
+      - component_id: lambda_component
+      - component_id: logger_component
+  - id: independent_item
+    expression: "true"
+    priority: 2
+    scaling_components:
+      - component_id: lambda_component
+"#;
+        // The kinds of the scaling components in the database are also checked
+        let errors = validate_definition_yaml(
+            yaml,
+            &HashSet::new(),
+            &HashMap::from([(
+                "logger_component".to_string(),
+                "cloudflare-rule".to_string(),
+            )]),
+        )
+        .unwrap_err()
+        .0;
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].plan_item_id,
+            Some("transactional_item".to_string())
+        );
+        assert!(errors[0].message.starts_with(
+            "The transactional apply mode does not support lambda_component (aws-lambda)"
+        ));
+        assert!(errors[1].message.starts_with(
+            "The transactional apply mode does not support logger_component (cloudflare-rule)"
+        ));
+    }
+
+    #[test]
+    fn test_validate_plan_expressions() {
+        // The unknown ids are not errors without the known ids
+        let errors = validate_plan_expressions(YAML).unwrap_err().0;
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0]
+            .message
+            .starts_with("Failed to compile the expression"));
+        assert!(errors[1]
+            .message
+            .starts_with("Unknown stats in get(): median"));
+
+        // A document that cannot be parsed does not skip the other documents
+        let yaml = format!("---\nkind: Metric\nkind: Metric\n{}", YAML);
+        let errors = validate_definition_yaml(&yaml, &HashSet::new(), &HashMap::new())
+            .unwrap_err()
+            .0;
+        assert_eq!(errors.len(), 4);
+
+        // A syntax error stops the validation of the rest
+        let yaml = format!(
+            "---\nkind----this--line--is--invalid\nid: metric_id\n{}",
+            YAML
+        );
+        assert!(validate_definition_yaml(&yaml, &HashSet::new(), &HashMap::new()).is_ok());
+    }
+}
@@ -1 +1,2 @@
+pub mod definition_validator;
 pub mod wave_definition_reader;
@@ -1,4 +1,8 @@
-use crate::{MetricDefinition, ScalingComponentDefinition, ScalingPlanDefinition};
+use super::definition_validator::validate_plan_expressions;
+use crate::{
+    MaintenanceWindowDefinition, MetricDefinition, ScalingComponentDefinition,
+    ScalingPlanDefinition,
+};
 use anyhow::Result;
 use serde::Deserialize;
 use serde_valid::Validate;
@@ -11,6 +15,7 @@ pub struct ParserResult {
     pub metric_definitions: Vec<MetricDefinition>,
     pub scaling_plan_definitions: Vec<ScalingPlanDefinition>,
     pub scaling_component_definitions: Vec<ScalingComponentDefinition>,
+    pub maintenance_window_definitions: Vec<MaintenanceWindowDefinition>,
 }
 
 pub fn read_definition_yaml_file<P>(path: P) -> Result<ParserResult>
@@ -49,6 +54,12 @@ where
                     parsed.validate()?;
                     result.scaling_component_definitions.push(parsed);
                 }
+                "MaintenanceWindow" => {
+                    let parsed = serde_yaml::from_value::<MaintenanceWindowDefinition>(value)?;
+                    parsed.validate()?;
+                    parsed.validate_schedule()?;
+                    result.maintenance_window_definitions.push(parsed);
+                }
                 _ => error!("Not Found: {:?}", kind),
             }
         } else {
@@ -58,6 +69,8 @@ where
     Ok(result)
 }
 
+// The metric and component ids that the plans refer to may be defined in another YAML or in the database,
+// so only the plan expressions are validated here
 pub fn read_definition_yaml(yaml: &str) -> Result<ParserResult> {
     let deserializer = Deserializer::from_str(yaml);
     // For result
@@ -83,18 +96,26 @@ pub fn read_definition_yaml(yaml: &str) -> Result<ParserResult> {
                     parsed.validate()?;
                     result.scaling_component_definitions.push(parsed);
                 }
+                "MaintenanceWindow" => {
+                    let parsed = serde_yaml::from_value::<MaintenanceWindowDefinition>(value)?;
+                    parsed.validate()?;
+                    parsed.validate_schedule()?;
+                    result.maintenance_window_definitions.push(parsed);
+                }
                 _ => error!("Not Found: {:?}", kind),
             }
         } else {
             // TODO: "kind" doesn't exist
         }
     }
+    validate_plan_expressions(yaml)?;
     Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::reader::definition_validator::DefinitionValidationErrors;
     use anyhow::Result;
 
     #[test]
@@ -138,11 +159,49 @@ plans:
       desired: "Math.floor(metric_id / 10)"
       min: 1
       max: 5
-      cooldown: 300"#;
+      cooldown: 300
+---
+kind: MaintenanceWindow
+id: maintenance_window_id
+cron_expression: "0 2 * * SAT"
+duration: 2h
+plan_ids: [scaling_plan_id]"#;
         let result = read_definition_yaml(yaml)?;
         assert_eq!(result.metric_definitions.len(), 1);
         assert_eq!(result.scaling_plan_definitions.len(), 1);
         assert_eq!(result.scaling_component_definitions.len(), 1);
+        assert_eq!(result.maintenance_window_definitions.len(), 1);
         Ok(())
     }
+
+    #[test]
+    fn test_read_definition_yaml_with_invalid_expression() {
+        let yaml = r#"
+---
+kind: Metric
+id: metric_id
+collector: vector
+metadata: {}
+---
+kind: ScalingPlan
+id: scaling_plan_id
+metadata: {}
+plans:
+  - id: plan_id
+    expression: "get({ metric_id: 'other_metric_id', stats: 'median' }) >= 30"
+    priority: 1
+    scaling_components:
+      - component_id: other_component_id
+"#;
+        // The ids can be defined in another YAML or in the database
+        assert!(read_definition_yaml(&yaml.replace("median", "avg")).is_ok());
+        let error = read_definition_yaml(yaml).unwrap_err();
+        let errors = &error
+            .downcast_ref::<DefinitionValidationErrors>()
+            .unwrap()
+            .0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].document_index, 1);
+        assert_eq!(errors[0].plan_item_id, Some("plan_id".to_string()));
+    }
 }
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use ts_rs::TS;
+
+// The parameters that replace the metadata of the scaling components in a manual run (component_id => { key => value })
+pub type ActionOverrides = HashMap<String, HashMap<String, Value>>;
+
+/**
+ * ActionStatus
+ * - Queued: waiting for the scaling planner of the plan to run it
+ * - Running: the scaling components are being applied
+ * - Succeeded: every scaling component of the plan item was applied
+ * - Failed: the plan item was not found or a scaling component failed
+ */
+#[derive(TS)]
+#[ts(export, export_to = "../web-app/src/types/bindings/action-status.ts")]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ActionStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl std::fmt::Display for ActionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionStatus::Queued => write!(f, "QUEUED"),
+            ActionStatus::Running => write!(f, "RUNNING"),
+            ActionStatus::Succeeded => write!(f, "SUCCEEDED"),
+            ActionStatus::Failed => write!(f, "FAILED"),
+        }
+    }
+}
+
+/**
+ * ActionDefinition
+ * A manual run of a plan item (POST /api/run-plan) in the action queue
+ * - overrides_json: the parameters that override the scaling_components of the plan item (JSON object of ActionOverrides)
+ * - status: "QUEUED", "RUNNING", "SUCCEEDED" or "FAILED"
+ * - result_json: the result of each scaling component (JSON array) after it ran
+ * - error: why it failed
+ * - created_at, started_at, finished_at, updated_at: RFC 3339
+ */
+#[derive(TS)]
+#[ts(
+    export,
+    export_to = "../web-app/src/types/bindings/action-definition.ts"
+)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ActionDefinition {
+    pub id: String,
+    pub plan_id: String,
+    pub plan_item_id: String,
+    pub overrides_json: Option<String>,
+    pub status: String,
+    pub result_json: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub updated_at: String,
+}
@@ -0,0 +1,219 @@
+use anyhow::Result;
+use chrono::Duration;
+use rand::Rng;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// metadata of a scaling component definition
+pub const TIMEOUT_KEY: &str = "timeout";
+pub const RETRY_KEY: &str = "retry";
+pub const CIRCUIT_BREAKER_KEY: &str = "circuit_breaker";
+
+const DEFAULT_RETRY_BACKOFF: &str = "1s";
+const DEFAULT_RETRY_MAX_BACKOFF: &str = "30s";
+const DEFAULT_OPEN_DURATION: &str = "1m";
+
+fn parse_duration(key: &str, value: &Value) -> Result<Duration> {
+    let Some(value) = value.as_str() else {
+        return Err(anyhow::anyhow!(
+            "Invalid {}: {}. It should be a duration like \"30s\"",
+            key,
+            value
+        ));
+    };
+    let duration = duration_str::parse(value)
+        .map_err(|error| anyhow::anyhow!("Invalid {}: {} - {}", key, value, error))?;
+    let duration = Duration::from_std(duration)?;
+    if duration <= Duration::zero() {
+        return Err(anyhow::anyhow!(
+            "Invalid {}: {}. It should be greater than 0",
+            key,
+            value
+        ));
+    }
+    Ok(duration)
+}
+
+fn get_duration(
+    object: &serde_json::Map<String, Value>,
+    key: &str,
+    default: &str,
+) -> Result<Duration> {
+    match object.get(key) {
+        None | Some(Value::Null) => parse_duration(key, &Value::from(default)),
+        Some(value) => parse_duration(key, value),
+    }
+}
+
+fn get_count(object: &serde_json::Map<String, Value>, key: &str, default: u32) -> Result<u32> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(default),
+        Some(value) => match value.as_u64() {
+            Some(count) if count > 0 && count <= u32::MAX as u64 => Ok(count as u32),
+            _ => Err(anyhow::anyhow!(
+                "Invalid {}: {}. It should be a number greater than 0",
+                key,
+                value
+            )),
+        },
+    }
+}
+
+/**
+ * RetryPolicy
+ * metadata.retry of a scaling component (e.g. { max_attempts: 3, backoff: 1s, max_backoff: 30s })
+ * - max_attempts: the number of attempts including the first one
+ * - backoff: the wait before the first retry. It doubles on every retry up to max_backoff.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    // The wait before the retry after the attempt (1-based) with the jitter. It is between the half and the whole of the exponential backoff.
+    pub fn get_backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(30);
+        let backoff = self
+            .backoff
+            .num_milliseconds()
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff.num_milliseconds());
+        let half = backoff / 2;
+        let jitter = rand::thread_rng().gen_range(0..=half);
+        Duration::milliseconds(half + jitter)
+    }
+}
+
+/**
+ * CircuitBreakerPolicy
+ * metadata.circuit_breaker of a scaling component (e.g. { failure_threshold: 5, open_duration: 5m })
+ * - failure_threshold: the number of consecutive failed applies that opens the circuit
+ * - open_duration: how long the applies are rejected before a trial apply is allowed
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircuitBreakerPolicy {
+    pub failure_threshold: u32,
+    pub open_duration: Duration,
+}
+
+/**
+ * ApplyPolicy
+ * The timeout, retry and circuit breaker settings of a scaling component
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ApplyPolicy {
+    // The timeout of each attempt
+    pub timeout: Option<Duration>,
+    pub retry: Option<RetryPolicy>,
+    pub circuit_breaker: Option<CircuitBreakerPolicy>,
+}
+
+impl ApplyPolicy {
+    // Get the apply policy from the metadata of a scaling component. None if nothing is set.
+    pub fn from_metadata(metadata: &HashMap<String, Value>) -> Result<Option<Self>> {
+        let timeout = match metadata.get(TIMEOUT_KEY) {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(parse_duration(TIMEOUT_KEY, value)?),
+        };
+        let retry = match metadata.get(RETRY_KEY) {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(retry)) => Some(RetryPolicy {
+                max_attempts: get_count(retry, "max_attempts", 3)?,
+                backoff: get_duration(retry, "backoff", DEFAULT_RETRY_BACKOFF)?,
+                max_backoff: get_duration(retry, "max_backoff", DEFAULT_RETRY_MAX_BACKOFF)?,
+            }),
+            Some(value) => {
+                return Err(anyhow::anyhow!(
+                "Invalid retry: {}. It should be an object like {{ max_attempts: 3, backoff: 1s }}",
+                value
+            ))
+            }
+        };
+        let circuit_breaker = match metadata.get(CIRCUIT_BREAKER_KEY) {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(circuit_breaker)) => Some(CircuitBreakerPolicy {
+                failure_threshold: get_count(circuit_breaker, "failure_threshold", 5)?,
+                open_duration: get_duration(
+                    circuit_breaker,
+                    "open_duration",
+                    DEFAULT_OPEN_DURATION,
+                )?,
+            }),
+            Some(value) => {
+                return Err(anyhow::anyhow!(
+                    "Invalid circuit_breaker: {}. It should be an object like {{ failure_threshold: 5, open_duration: 5m }}",
+                    value
+                ))
+            }
+        };
+        if timeout.is_none() && retry.is_none() && circuit_breaker.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(ApplyPolicy {
+            timeout,
+            retry,
+            circuit_breaker,
+        }))
+    }
+
+    pub fn get_max_attempts(&self) -> u32 {
+        self.retry.map(|retry| retry.max_attempts).unwrap_or(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_policy_from_metadata() {
+        let metadata: HashMap<String, Value> = serde_json::from_value(json!({
+            "timeout": "10s",
+            "retry": { "max_attempts": 4, "backoff": "2s" },
+            "circuit_breaker": { "failure_threshold": 3, "open_duration": "5m" },
+        }))
+        .unwrap();
+        let policy = ApplyPolicy::from_metadata(&metadata).unwrap().unwrap();
+        assert_eq!(policy.timeout, Some(Duration::seconds(10)));
+        assert_eq!(
+            policy.retry,
+            Some(RetryPolicy {
+                max_attempts: 4,
+                backoff: Duration::seconds(2),
+                max_backoff: Duration::seconds(30),
+            })
+        );
+        assert_eq!(
+            policy.circuit_breaker,
+            Some(CircuitBreakerPolicy {
+                failure_threshold: 3,
+                open_duration: Duration::minutes(5),
+            })
+        );
+
+        assert!(ApplyPolicy::from_metadata(&HashMap::new())
+            .unwrap()
+            .is_none());
+        let metadata = HashMap::from([("timeout".to_string(), json!("ten seconds"))]);
+        assert!(ApplyPolicy::from_metadata(&metadata).is_err());
+        let metadata = HashMap::from([("retry".to_string(), json!({ "max_attempts": 0 }))]);
+        assert!(ApplyPolicy::from_metadata(&metadata).is_err());
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let retry = RetryPolicy {
+            max_attempts: 5,
+            backoff: Duration::seconds(1),
+            max_backoff: Duration::seconds(4),
+        };
+        for (attempt, max_backoff) in [(1, 1000), (2, 2000), (3, 4000), (4, 4000)] {
+            let backoff = retry.get_backoff(attempt).num_milliseconds();
+            assert!(backoff >= max_backoff / 2 && backoff <= max_backoff);
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use ts_rs::TS;
+
+// The maximum number of the missed occurrences that are caught up (catch_up: all)
+const MAX_CATCH_UP_OCCURRENCES: usize = 100;
+
+/**
+ * CatchUpPolicy
+ * What to do with the occurrences of a cron expression that were missed while the planner was down or busy
+ * - skip: the missed occurrences are not fired (default)
+ * - latest: only the latest missed occurrence is fired
+ * - all: every missed occurrence is fired one by one from the oldest
+ */
+#[derive(TS)]
+#[ts(export, export_to = "../web-app/src/types/bindings/catch-up-policy.ts")]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    #[default]
+    Skip,
+    Latest,
+    All,
+}
+
+/**
+ * CronSchedule
+ * The cron expression of a plan item with its timezone
+ * - cron_expression: a standard 5-field crontab expression (minute hour day month weekday),
+ *   a 6 or 7-field expression with seconds (and years), or a macro (e.g. @hourly, @daily)
+ * - timezone: an IANA timezone name (e.g. "Asia/Seoul"). UTC if it is not set.
+ */
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    schedule: cron::Schedule,
+    timezone: Tz,
+}
+
+impl CronSchedule {
+    pub fn parse(cron_expression: &str, timezone: Option<&str>) -> Result<Self> {
+        let normalized = normalize_cron_expression(cron_expression)?;
+        let schedule = cron::Schedule::from_str(normalized.as_str()).map_err(|error| {
+            anyhow::anyhow!(
+                "Failed to parse cron expression: {} ({})",
+                cron_expression,
+                error
+            )
+        })?;
+        let timezone = match timezone {
+            Some(timezone) => Tz::from_str(timezone)
+                .map_err(|_| anyhow::anyhow!("Invalid timezone: {}", timezone))?,
+            None => Tz::UTC,
+        };
+        Ok(CronSchedule { schedule, timezone })
+    }
+
+    // The next fire time after the given time
+    pub fn next_after(&self, after: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.upcoming_after(after, 1).into_iter().next()
+    }
+
+    /**
+    Get the occurrence to fire at now
+    - The occurrences missed since last_fired are fired first by the catch_up policy
+    - Otherwise, the next occurrence within window_ms from now is fired if it has not been fired yet
+     */
+    pub fn get_due_occurrence(
+        &self,
+        now: &DateTime<Utc>,
+        window_ms: i64,
+        last_fired: Option<DateTime<Utc>>,
+        catch_up: CatchUpPolicy,
+    ) -> Option<DateTime<Utc>> {
+        if let Some(last_fired) = last_fired {
+            let missed = self
+                .schedule
+                .after(&last_fired.with_timezone(&self.timezone))
+                .map(|datetime| datetime.with_timezone(&Utc))
+                .take_while(|datetime| datetime <= now);
+            let missed = match catch_up {
+                CatchUpPolicy::Skip => None,
+                CatchUpPolicy::Latest => missed.last(),
+                CatchUpPolicy::All => {
+                    // Only the latest occurrences are caught up if too many were missed
+                    let missed = missed.collect::<Vec<DateTime<Utc>>>();
+                    missed
+                        .get(missed.len().saturating_sub(MAX_CATCH_UP_OCCURRENCES))
+                        .copied()
+                }
+            };
+            if missed.is_some() {
+                return missed;
+            }
+        }
+        let next = self.next_after(now)?;
+        if next - *now > Duration::milliseconds(window_ms) {
+            return None;
+        }
+        // The occurrence has already been fired
+        if last_fired
+            .map(|last_fired| last_fired >= next)
+            .unwrap_or(false)
+        {
+            return None;
+        }
+        Some(next)
+    }
+
+    // The latest fire time in (from, to]
+    pub fn latest_between(
+        &self,
+        from: &DateTime<Utc>,
+        to: &DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&from.with_timezone(&self.timezone))
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .take_while(|datetime| datetime <= to)
+            .last()
+    }
+
+    // The next fire times after the given time (at most "count")
+    pub fn upcoming_after(&self, after: &DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.timezone))
+            .take(count)
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .collect()
+    }
+}
+
+// Convert a standard 5-field crontab expression to the 6-field expression with seconds
+fn normalize_cron_expression(cron_expression: &str) -> Result<String> {
+    let cron_expression = cron_expression.trim();
+    let fields = cron_expression.split_whitespace().collect::<Vec<&str>>();
+    if cron_expression.starts_with('@') || fields.len() != 5 {
+        return Ok(cron_expression.to_string());
+    }
+    let day_of_week = convert_day_of_week(fields[4])?;
+    Ok(format!(
+        "0 {} {} {} {} {}",
+        fields[0], fields[1], fields[2], fields[3], day_of_week
+    ))
+}
+
+/**
+Convert the day of week field of crontab (0-7, Sunday is 0 or 7) to the field of the cron crate (1-7, Sunday is 1)
+- The names (e.g. MON-FRI) and "*" are kept as they are
+ */
+fn convert_day_of_week(field: &str) -> Result<String> {
+    let to_ordinal = |value: &str| -> Result<u32> {
+        let day = value
+            .parse::<u32>()
+            .map_err(|_| anyhow::anyhow!("Invalid day of week: {}", value))?;
+        match day {
+            0 | 7 => Ok(1),
+            1..=6 => Ok(day + 1),
+            _ => Err(anyhow::anyhow!("Invalid day of week: {}", value)),
+        }
+    };
+
+    let mut converted = Vec::new();
+    for element in field.split(',') {
+        let (range, step) = match element.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (element, None),
+        };
+        let is_numeric = range
+            .chars()
+            .all(|character| character.is_ascii_digit() || character == '-');
+        if range == "*" || range == "?" || !is_numeric {
+            converted.push(element.to_string());
+            continue;
+        }
+        let step_suffix = step.map(|step| format!("/{}", step)).unwrap_or_default();
+        let Some((start, end)) = range.split_once('-') else {
+            converted.push(format!("{}{}", to_ordinal(range)?, step_suffix));
+            continue;
+        };
+        // 0-7 has Sunday at both ends, so it is the same as 0-6 (the full week with the step)
+        if end == "7" && start == "0" {
+            converted.push(format!("1-7{}", step_suffix));
+            continue;
+        }
+        // A range that ends with Sunday (7) is split because Sunday is the first day in the cron crate
+        if end == "7" {
+            let start_ordinal = to_ordinal(start)?;
+            converted.push(format!("{}-7{}", start_ordinal, step_suffix));
+            let step_value = step.and_then(|step| step.parse::<u32>().ok()).unwrap_or(1);
+            let start_day = start_ordinal - 1;
+            if (7 - start_day) % step_value == 0 {
+                converted.push("1".to_string());
+            }
+            continue;
+        }
+        converted.push(format!(
+            "{}-{}{}",
+            to_ordinal(start)?,
+            to_ordinal(end)?,
+            step_suffix
+        ));
+    }
+    Ok(converted.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_normalize_cron_expression() {
+        assert_eq!(
+            normalize_cron_expression("30 9 * * 1-5").unwrap(),
+            "0 30 9 * * 2-6"
+        );
+        assert_eq!(
+            normalize_cron_expression("0 0 * * 0,6").unwrap(),
+            "0 0 0 * * 1,7"
+        );
+        assert_eq!(
+            normalize_cron_expression("0 0 * * 5-7").unwrap(),
+            "0 0 0 * * 6-7,1"
+        );
+        // The full week
+        assert_eq!(
+            normalize_cron_expression("0 0 * * 0-7").unwrap(),
+            "0 0 0 * * 1-7"
+        );
+        assert_eq!(
+            normalize_cron_expression("0 0 * * 0-6").unwrap(),
+            "0 0 0 * * 1-7"
+        );
+        assert_eq!(
+            normalize_cron_expression("0 0 * * 0-7/2").unwrap(),
+            "0 0 0 * * 1-7/2"
+        );
+        assert_eq!(
+            normalize_cron_expression("0 0 * * MON-FRI").unwrap(),
+            "0 0 0 * * MON-FRI"
+        );
+        assert_eq!(
+            normalize_cron_expression("*/2 * * * * * *").unwrap(),
+            "*/2 * * * * * *"
+        );
+        assert_eq!(normalize_cron_expression("@hourly").unwrap(), "@hourly");
+        assert!(normalize_cron_expression("0 0 * * 8").is_err());
+    }
+
+    #[test]
+    fn test_cron_schedule_with_timezone() {
+        // 09:00 in Seoul is 00:00 in UTC
+        let schedule = CronSchedule::parse("0 9 * * *", Some("Asia/Seoul")).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 3, 11, 1, 0, 0).unwrap();
+        let upcoming = schedule.upcoming_after(&after, 2);
+        assert_eq!(
+            upcoming,
+            vec![
+                Utc.with_ymd_and_hms(2024, 3, 12, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 3, 13, 0, 0, 0).unwrap(),
+            ]
+        );
+
+        // Weekdays only. 2024-03-16 is Saturday.
+        let schedule = CronSchedule::parse("0 9 * * 1-5", Some("Asia/Seoul")).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 3, 15, 1, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_after(&after),
+            Some(Utc.with_ymd_and_hms(2024, 3, 18, 0, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_cron_schedule_macros() {
+        let schedule = CronSchedule::parse("@daily", None).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 3, 11, 1, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_after(&after),
+            Some(Utc.with_ymd_and_hms(2024, 3, 12, 0, 0, 0).unwrap())
+        );
+        let schedule = CronSchedule::parse("@hourly", Some("Asia/Seoul")).unwrap();
+        assert_eq!(
+            schedule.next_after(&after),
+            Some(Utc.with_ymd_and_hms(2024, 3, 11, 2, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_due_occurrence() {
+        let schedule = CronSchedule::parse("@hourly", None).unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 3, 12, 3, 30, 0).unwrap();
+        let last_fired = Some(Utc.with_ymd_and_hms(2024, 3, 12, 0, 0, 0).unwrap());
+
+        // 01:00, 02:00 and 03:00 were missed
+        assert_eq!(
+            schedule.get_due_occurrence(&now, 1000, last_fired, CatchUpPolicy::Skip),
+            None
+        );
+        assert_eq!(
+            schedule.get_due_occurrence(&now, 1000, last_fired, CatchUpPolicy::Latest),
+            Some(Utc.with_ymd_and_hms(2024, 3, 12, 3, 0, 0).unwrap())
+        );
+        assert_eq!(
+            schedule.get_due_occurrence(&now, 1000, last_fired, CatchUpPolicy::All),
+            Some(Utc.with_ymd_and_hms(2024, 3, 12, 1, 0, 0).unwrap())
+        );
+
+        // The next occurrence within the window is fired only once
+        let now = Utc.with_ymd_and_hms(2024, 3, 12, 3, 59, 59).unwrap();
+        let next = Utc.with_ymd_and_hms(2024, 3, 12, 4, 0, 0).unwrap();
+        assert_eq!(
+            schedule.get_due_occurrence(&now, 1000, None, CatchUpPolicy::Skip),
+            Some(next)
+        );
+        assert_eq!(
+            schedule.get_due_occurrence(&now, 1000, Some(next), CatchUpPolicy::All),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cron_schedule_invalid() {
+        assert!(CronSchedule::parse("invalid", None).is_err());
+        assert!(CronSchedule::parse("0 9 * * *", Some("Mars/Olympus")).is_err());
+    }
+}
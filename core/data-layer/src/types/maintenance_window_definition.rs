@@ -0,0 +1,219 @@
+use super::{cron_schedule::CronSchedule, object_kind::ObjectKind, validate_id_regex};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_valid::Validate;
+use ts_rs::TS;
+
+fn default_kind() -> ObjectKind {
+    ObjectKind::MaintenanceWindow
+}
+// A maintenance window is enabled unless it is disabled explicitly so that a forgotten flag does not allow scaling
+fn default_enabled() -> bool {
+    true
+}
+
+/**
+ * MaintenanceWindowDefinition
+ * A period when the scaling plans do not apply the scaling components automatically (e.g. database migrations, deploy freezes)
+ * - start_time, end_time: a one-off window (RFC 3339)
+ * - cron_expression, duration: a recurring window that starts at every occurrence of the cron expression and lasts for the duration (e.g. "2h")
+ * - timezone: the IANA timezone of the cron_expression (UTC by default)
+ * - plan_ids, component_ids: the plans and the scaling components in the window. Every plan and component if both are empty.
+ *   If both are set, only the components in component_ids of the plans in plan_ids are in the window.
+ * - The manual runs (POST /api/run-plan) are not suppressed
+ */
+#[derive(TS)]
+#[ts(
+    export,
+    export_to = "../web-app/src/types/bindings/maintenance-window-definition.ts"
+)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, PartialEq)]
+pub struct MaintenanceWindowDefinition {
+    #[serde(default = "default_kind")]
+    pub kind: ObjectKind,
+    #[serde(default)]
+    pub db_id: String,
+    #[validate(custom(validate_id_regex))]
+    #[validate(min_length = 2)]
+    pub id: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+    #[serde(default)]
+    pub cron_expression: Option<String>,
+    #[serde(default)]
+    pub duration: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub plan_ids: Vec<String>,
+    #[serde(default)]
+    pub component_ids: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn parse_time(key: &str, value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .map_err(|error| anyhow::anyhow!("Invalid {}: {} ({})", key, value, error))
+}
+
+impl MaintenanceWindowDefinition {
+    // Check that the window has either start_time and end_time or cron_expression and duration
+    pub fn validate_schedule(&self) -> Result<()> {
+        match (
+            self.start_time.as_deref(),
+            self.end_time.as_deref(),
+            self.cron_expression.as_deref(),
+            self.duration.as_deref(),
+        ) {
+            (Some(start_time), Some(end_time), None, None) => {
+                if parse_time("start_time", start_time)? >= parse_time("end_time", end_time)? {
+                    return Err(anyhow::anyhow!(
+                        "The end_time of the maintenance window {} should be after the start_time",
+                        self.id
+                    ));
+                }
+                Ok(())
+            }
+            (None, None, Some(cron_expression), Some(duration)) => {
+                CronSchedule::parse(cron_expression, self.timezone.as_deref())?;
+                self.parse_duration(duration)?;
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!(
+                "The maintenance window {} should have either start_time and end_time or cron_expression and duration",
+                self.id
+            )),
+        }
+    }
+
+    fn parse_duration(&self, duration: &str) -> Result<Duration> {
+        let parsed = duration_str::parse_chrono(duration)
+            .map_err(|error| anyhow::anyhow!("Invalid duration: {} ({})", duration, error))?;
+        if parsed <= Duration::zero() {
+            return Err(anyhow::anyhow!(
+                "Invalid duration: {}. It should be greater than 0",
+                duration
+            ));
+        }
+        Ok(parsed)
+    }
+
+    // When the window that is open at the time ends. None if the window is closed at the time.
+    pub fn get_active_until(&self, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+        self.validate_schedule()?;
+        if let (Some(start_time), Some(end_time)) = (&self.start_time, &self.end_time) {
+            let start_time = parse_time("start_time", start_time)?;
+            let end_time = parse_time("end_time", end_time)?;
+            if start_time <= now && now < end_time {
+                return Ok(Some(end_time));
+            }
+            return Ok(None);
+        }
+        let (Some(cron_expression), Some(duration)) = (&self.cron_expression, &self.duration)
+        else {
+            return Ok(None);
+        };
+        let schedule = CronSchedule::parse(cron_expression, self.timezone.as_deref())?;
+        let duration = self.parse_duration(duration)?;
+        // The latest occurrence that started less than the duration ago
+        Ok(schedule
+            .latest_between(&(now - duration), &now)
+            .map(|started_at| started_at + duration))
+    }
+
+    // Whether the scaling component of the plan is in the window
+    pub fn selects(&self, plan_id: &str, component_id: &str) -> bool {
+        (self.plan_ids.is_empty() || self.plan_ids.iter().any(|id| id == plan_id))
+            && (self.component_ids.is_empty()
+                || self.component_ids.iter().any(|id| id == component_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_maintenance_window(yaml: &str) -> MaintenanceWindowDefinition {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_absolute_maintenance_window() {
+        let window = get_maintenance_window(
+            r#"
+kind: MaintenanceWindow
+id: db_migration
+start_time: "2024-03-25T01:00:00Z"
+end_time: "2024-03-25T03:00:00Z"
+plan_ids: [plan_1]
+"#,
+        );
+        assert!(window.enabled);
+        let end_time = parse_time("end_time", "2024-03-25T03:00:00Z").unwrap();
+        let now = parse_time("now", "2024-03-25T02:00:00Z").unwrap();
+        assert_eq!(window.get_active_until(now).unwrap(), Some(end_time));
+        assert_eq!(window.get_active_until(end_time).unwrap(), None);
+        assert!(window.selects("plan_1", "component_1"));
+        assert!(!window.selects("plan_2", "component_1"));
+    }
+
+    #[test]
+    fn test_cron_maintenance_window() {
+        // Every Saturday 02:00-04:00 in Seoul
+        let window = get_maintenance_window(
+            r#"
+kind: MaintenanceWindow
+id: deploy_freeze
+cron_expression: "0 2 * * SAT"
+duration: 2h
+timezone: Asia/Seoul
+component_ids: [component_1]
+"#,
+        );
+        // 2024-03-23 is a Saturday (02:00 in Seoul is 17:00 UTC on Friday)
+        let now = parse_time("now", "2024-03-22T18:30:00Z").unwrap();
+        assert_eq!(
+            window.get_active_until(now).unwrap(),
+            Some(parse_time("end_time", "2024-03-22T19:00:00Z").unwrap())
+        );
+        let now = parse_time("now", "2024-03-22T19:00:00Z").unwrap();
+        assert_eq!(window.get_active_until(now).unwrap(), None);
+        assert!(window.selects("plan_1", "component_1"));
+        assert!(!window.selects("plan_1", "component_2"));
+    }
+
+    #[test]
+    fn test_validate_schedule() {
+        let window = get_maintenance_window(
+            r#"
+id: invalid
+start_time: "2024-03-25T01:00:00Z"
+cron_expression: "0 2 * * *"
+"#,
+        );
+        assert!(window.validate_schedule().is_err());
+        let window = get_maintenance_window(
+            r#"
+id: invalid
+start_time: "2024-03-25T03:00:00Z"
+end_time: "2024-03-25T01:00:00Z"
+"#,
+        );
+        assert!(window.validate_schedule().is_err());
+        let window = get_maintenance_window(
+            r#"
+id: invalid
+cron_expression: "0 2 * * *"
+duration: two hours
+"#,
+        );
+        assert!(window.validate_schedule().is_err());
+    }
+}
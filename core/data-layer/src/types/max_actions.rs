@@ -0,0 +1,105 @@
+use anyhow::Result;
+use chrono::Duration;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// metadata.max_actions of a scaling plan or a scaling component (e.g. "6 per 1h")
+pub const MAX_ACTIONS_KEY: &str = "max_actions";
+
+/**
+ * MaxActions
+ * The safety budget of the scaling actions in a sliding window (e.g. "6 per 1h")
+ * - count: the maximum number of actions in the period
+ * - period: the length of the sliding window
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaxActions {
+    pub count: usize,
+    pub period: Duration,
+}
+
+impl MaxActions {
+    // Parse "<count> per <duration>" (e.g. "6 per 1h", "10 per 30m")
+    pub fn parse(value: &str) -> Result<Self> {
+        let re_max_actions = regex::Regex::new(r"^\s*(\d+)\s+per\s+(.+?)\s*$").unwrap();
+        let Some(captures) = re_max_actions.captures(value) else {
+            return Err(anyhow::anyhow!(
+                "Invalid max_actions: {}. It should be \"<count> per <duration>\" (e.g. \"6 per 1h\")",
+                value
+            ));
+        };
+        let count = captures[1].parse::<usize>()?;
+        if count == 0 {
+            return Err(anyhow::anyhow!(
+                "Invalid max_actions: {}. The count should be greater than 0",
+                value
+            ));
+        }
+        let period = duration_str::parse(&captures[2])
+            .map_err(|error| anyhow::anyhow!("Invalid max_actions: {} - {}", value, error))?;
+        let period = Duration::from_std(period)?;
+        if period <= Duration::zero() {
+            return Err(anyhow::anyhow!(
+                "Invalid max_actions: {}. The period should be greater than 0",
+                value
+            ));
+        }
+        Ok(MaxActions { count, period })
+    }
+
+    // Get max_actions from the metadata of a scaling plan or a scaling component. None if it is not set.
+    pub fn from_metadata(metadata: &HashMap<String, Value>) -> Result<Option<Self>> {
+        match metadata.get(MAX_ACTIONS_KEY) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) => Ok(Some(MaxActions::parse(value)?)),
+            Some(value) => Err(anyhow::anyhow!(
+                "Invalid max_actions: {}. It should be a string like \"6 per 1h\"",
+                value
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for MaxActions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let seconds = self.period.num_seconds();
+        if seconds % 3600 == 0 {
+            write!(f, "{} per {}h", self.count, seconds / 3600)
+        } else if seconds % 60 == 0 {
+            write!(f, "{} per {}m", self.count, seconds / 60)
+        } else {
+            write!(f, "{} per {}s", self.count, seconds)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_max_actions() {
+        let max_actions = MaxActions::parse("6 per 1h").unwrap();
+        assert_eq!(max_actions.count, 6);
+        assert_eq!(max_actions.period, Duration::hours(1));
+        assert_eq!(max_actions.to_string(), "6 per 1h");
+        assert_eq!(
+            MaxActions::parse(" 10 per 30m ").unwrap().to_string(),
+            "10 per 30m"
+        );
+
+        assert!(MaxActions::parse("6").is_err());
+        assert!(MaxActions::parse("0 per 1h").is_err());
+        assert!(MaxActions::parse("6 per forever").is_err());
+
+        let metadata = HashMap::from([(MAX_ACTIONS_KEY.to_string(), json!("2 per 90s"))]);
+        let max_actions = MaxActions::from_metadata(&metadata).unwrap().unwrap();
+        assert_eq!(max_actions.to_string(), "2 per 90s");
+        assert!(MaxActions::from_metadata(&HashMap::new())
+            .unwrap()
+            .is_none());
+        let metadata = HashMap::from([(MAX_ACTIONS_KEY.to_string(), json!(6))]);
+        assert!(MaxActions::from_metadata(&metadata).is_err());
+    }
+}
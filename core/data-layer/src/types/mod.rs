@@ -1,9 +1,20 @@
+pub mod action;
+pub mod apply_policy;
+pub mod cron_schedule;
+pub mod maintenance_window_definition;
+pub mod max_actions;
 pub mod metric;
 pub mod metric_definition;
 pub mod metrics_data_item;
 pub mod object_kind;
+pub mod pending_action;
+pub mod plan_expression_stats;
 pub mod plan_item_definition;
 pub mod plan_log_definition;
+pub mod plan_pause;
+pub mod planner_request;
+pub mod planner_state;
+pub mod scaling_behavior;
 pub mod scaling_component;
 pub mod scaling_component_definition;
 pub mod scaling_plan_definition;
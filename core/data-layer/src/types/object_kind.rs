@@ -3,11 +3,12 @@ use ts_rs::TS;
 
 #[derive(TS)]
 #[ts(export, export_to = "../web-app/src/types/bindings/object-kind.ts")]
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum ObjectKind {
     Metric,
     ScalingPlan,
     ScalingComponent,
+    MaintenanceWindow,
 }
 
 impl std::fmt::Display for ObjectKind {
@@ -16,6 +17,7 @@ impl std::fmt::Display for ObjectKind {
             ObjectKind::Metric => write!(f, "Metric"),
             ObjectKind::ScalingPlan => write!(f, "ScalingPlan"),
             ObjectKind::ScalingComponent => write!(f, "ScalingComponent"),
+            ObjectKind::MaintenanceWindow => write!(f, "MaintenanceWindow"),
         }
     }
 }
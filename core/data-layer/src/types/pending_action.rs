@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/**
+ * PendingActionStatus
+ * - Pending: waiting for a human to approve or reject it
+ * - Approved: approved and applied to the scaling components
+ * - Rejected: rejected, the scaling components were not applied
+ * - Expired: neither approved nor rejected before expires_at
+ */
+#[derive(TS)]
+#[ts(
+    export,
+    export_to = "../web-app/src/types/bindings/pending-action-status.ts"
+)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PendingActionStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Expired,
+}
+
+impl std::fmt::Display for PendingActionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PendingActionStatus::Pending => write!(f, "PENDING"),
+            PendingActionStatus::Approved => write!(f, "APPROVED"),
+            PendingActionStatus::Rejected => write!(f, "REJECTED"),
+            PendingActionStatus::Expired => write!(f, "EXPIRED"),
+        }
+    }
+}
+
+/**
+ * PendingActionDefinition
+ * A fired plan item with require_approval that waits for a human approval instead of being applied
+ * - plan_item_json: the plan item that fired
+ * - scaling_components_json: the resolved params of the scaling components (JSON array) that are applied when it is approved
+ * - status: "PENDING", "APPROVED", "REJECTED" or "EXPIRED"
+ * - created_at, expires_at, updated_at: RFC 3339
+ */
+#[derive(TS)]
+#[ts(
+    export,
+    export_to = "../web-app/src/types/bindings/pending-action-definition.ts"
+)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PendingActionDefinition {
+    pub id: String,
+    pub plan_db_id: String,
+    pub plan_id: String,
+    pub plan_item_id: String,
+    pub plan_item_json: String,
+    pub scaling_components_json: String,
+    pub status: String,
+    pub created_at: String,
+    pub expires_at: String,
+    pub updated_at: String,
+}
@@ -0,0 +1,58 @@
+/**
+ * PlanExpressionStats
+ * The stats that get() in the plan expressions supports
+ * - The planner computes them and the definition validator accepts only them
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanExpressionStats {
+    Latest,
+    Average,
+    Sum,
+    Count,
+    Minimum,
+    Maximum,
+    LinearSlope,
+    MovingAverageSlope,
+    ForecastLinear,
+    ForecastHoltWinters,
+}
+
+impl PlanExpressionStats {
+    pub const ALL: [PlanExpressionStats; 10] = [
+        PlanExpressionStats::Latest,
+        PlanExpressionStats::Average,
+        PlanExpressionStats::Sum,
+        PlanExpressionStats::Count,
+        PlanExpressionStats::Minimum,
+        PlanExpressionStats::Maximum,
+        PlanExpressionStats::LinearSlope,
+        PlanExpressionStats::MovingAverageSlope,
+        PlanExpressionStats::ForecastLinear,
+        PlanExpressionStats::ForecastHoltWinters,
+    ];
+
+    // The stats of the name in get() (e.g. "avg"). The name is case-insensitive.
+    pub fn from_name(name: &str) -> Option<PlanExpressionStats> {
+        let name = name.to_lowercase();
+        PlanExpressionStats::ALL
+            .into_iter()
+            .find(|stats| stats.to_string() == name)
+    }
+}
+
+impl std::fmt::Display for PlanExpressionStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanExpressionStats::Latest => write!(f, "latest"),
+            PlanExpressionStats::Average => write!(f, "avg"),
+            PlanExpressionStats::Sum => write!(f, "sum"),
+            PlanExpressionStats::Count => write!(f, "count"),
+            PlanExpressionStats::Minimum => write!(f, "min"),
+            PlanExpressionStats::Maximum => write!(f, "max"),
+            PlanExpressionStats::LinearSlope => write!(f, "linear_slope"),
+            PlanExpressionStats::MovingAverageSlope => write!(f, "moving_average_slope"),
+            PlanExpressionStats::ForecastLinear => write!(f, "forecast_linear"),
+            PlanExpressionStats::ForecastHoltWinters => write!(f, "forecast_holt_winters"),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use super::validate_id_regex;
+use super::{cron_schedule::CatchUpPolicy, scaling_behavior::ScalingBehavior, validate_id_regex};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_valid::Validate;
@@ -10,12 +10,34 @@ fn default_cool_down() -> Option<u64> {
     None
 }
 
+/**
+ * ApplyMode
+ * How the scaling components of a plan item are applied
+ * - independent: every scaling component is applied even if another one fails (default)
+ * - transactional: the scaling components are applied in the declared order and the execution stops at the first failure.
+ *   The scaling components that were applied before the failure are reverted to the values captured before the execution.
+ *   Only the kinds in TRANSACTIONAL_COMPONENT_KINDS can capture their values.
+ */
+#[derive(TS)]
+#[ts(export, export_to = "../web-app/src/types/bindings/apply-mode.ts")]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyMode {
+    #[default]
+    Independent,
+    Transactional,
+}
+
+// The kinds of the scaling components that can capture their current values to be reverted in the transactional apply mode
+pub const TRANSACTIONAL_COMPONENT_KINDS: [&str; 3] =
+    ["kubernetes-deployment", "aws-ec2-autoscaling", "wa-logger"];
+
 #[derive(TS)]
 #[ts(
     export,
     export_to = "../web-app/src/types/bindings/plan-item-definition.ts"
 )]
-#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, Default)]
 pub struct PlanItemDefinition {
     #[validate(custom(validate_id_regex))]
     #[validate(min_length = 2)]
@@ -34,4 +56,29 @@ pub struct PlanItemDefinition {
     pub scaling_components: Vec<Value>,
     #[ts(type = "any")]
     pub ui: Option<HashMap<String, Value>>,
+    // Overrides the behavior of the plan for each direction
+    #[serde(default)]
+    pub behavior: Option<ScalingBehavior>,
+    // The expression has to be true for the duration before the plan item is executed (e.g. "30s", "2m")
+    #[serde(default, rename = "for")]
+    pub for_duration: Option<String>,
+    // The IANA timezone of the cron_expression (e.g. "Asia/Seoul"). Overrides the timezone in the plan metadata.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    // What to do with the occurrences of the cron_expression that were missed (skip by default)
+    #[serde(default)]
+    pub catch_up: Option<CatchUpPolicy>,
+    // The scaling components are applied only after a human approves the pending action (false by default)
+    #[serde(default)]
+    pub require_approval: Option<bool>,
+    // The plan item runs only when no other plan item matched for "fallback_after" consecutive ticks (false by default)
+    // The expression and the cron_expression are optional for a fallback plan item
+    #[serde(default)]
+    pub fallback: Option<bool>,
+    // The number of consecutive ticks with no matching plan item before the fallback plan item runs (1 by default)
+    #[serde(default)]
+    pub fallback_after: Option<u32>,
+    // How the scaling components are applied (independent by default)
+    #[serde(default)]
+    pub apply_mode: Option<ApplyMode>,
 }
@@ -2,6 +2,47 @@ use serde::{Deserialize, Serialize};
 use serde_valid::Validate;
 use ts_rs::TS;
 
+/**
+ * PlanLogStatus
+ * - Success: the scaling components were applied
+ * - Fail: the plan item or one of its scaling components failed
+ * - Shadow: the plan runs in shadow mode and would have applied the scaling components
+ * - Suppressed: a stabilization window (behavior) suppressed the scaling components
+ * - Pending: the expression became true but not yet for the "for" duration of the plan item
+ * - Skipped: a scaling component of the plan item was skipped because it is cooling down
+ * - RateLimited: a scaling component of the plan item was not applied because the scaling plan or the scaling component reached max_actions
+ * - PendingApproval: the plan item requires approval and its scaling components wait as a pending action
+ */
+#[derive(TS)]
+#[ts(export, export_to = "../web-app/src/types/bindings/plan-log-status.ts")]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PlanLogStatus {
+    Success,
+    Fail,
+    Shadow,
+    Suppressed,
+    Pending,
+    Skipped,
+    RateLimited,
+    PendingApproval,
+}
+
+impl std::fmt::Display for PlanLogStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanLogStatus::Success => write!(f, "SUCCESS"),
+            PlanLogStatus::Fail => write!(f, "FAIL"),
+            PlanLogStatus::Shadow => write!(f, "SHADOW"),
+            PlanLogStatus::Suppressed => write!(f, "SUPPRESSED"),
+            PlanLogStatus::Pending => write!(f, "PENDING"),
+            PlanLogStatus::Skipped => write!(f, "SKIPPED"),
+            PlanLogStatus::RateLimited => write!(f, "RATE_LIMITED"),
+            PlanLogStatus::PendingApproval => write!(f, "PENDING_APPROVAL"),
+        }
+    }
+}
+
 #[derive(TS)]
 #[ts(
     export,
@@ -17,6 +58,15 @@ pub struct PlanLogDefinition {
     pub metric_values_json: String,
     pub metadata_values_json: String,
     pub fail_message: Option<String>,
+    // e.g. "SUCCESS", "FAIL", "SHADOW", "SUPPRESSED", "PENDING", "SKIPPED", "RATE_LIMITED", "PENDING_APPROVAL" (None for the logs created before the status was introduced)
+    #[serde(default)]
+    pub status: Option<String>,
+    // The evaluated variables of the plan in the tick ("$key" => value)
+    #[serde(default)]
+    pub variables_json: Option<String>,
+    // The parameter overrides of the scaling components in a manual run (component_id => { key => value })
+    #[serde(default)]
+    pub overrides_json: Option<String>,
 }
 
 impl PlanLogDefinition {
@@ -27,6 +77,7 @@ impl PlanLogDefinition {
         metric_values_json: String,
         metadata_values_json: String,
         fail_message: Option<String>,
+        status: PlanLogStatus,
     ) -> Self {
         Self {
             id: uuid::Uuid::new_v4().to_string(),
@@ -36,6 +87,9 @@ impl PlanLogDefinition {
             metric_values_json,
             metadata_values_json,
             fail_message,
+            status: Some(status.to_string()),
+            variables_json: None,
+            overrides_json: None,
         }
     }
 }
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/**
+ * PlanPauseDefinition
+ * A plan whose evaluation is suspended at runtime (POST /api/plans/{id}/pause)
+ * - plan_id: the id of the plan (not the db_id) so that the pause is kept when the definitions are reloaded
+ * - paused_until: the plan resumes by itself after it. None until it is resumed.
+ * - paused_at, paused_until, updated_at: RFC 3339
+ */
+#[derive(TS)]
+#[ts(
+    export,
+    export_to = "../web-app/src/types/bindings/plan-pause-definition.ts"
+)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlanPauseDefinition {
+    pub plan_id: String,
+    pub paused_at: String,
+    pub paused_until: Option<String>,
+    pub updated_at: String,
+}
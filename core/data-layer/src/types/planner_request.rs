@@ -0,0 +1,68 @@
+use crate::ScalingPlanDefinition;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/**
+ * PlannerRequest
+ * A request from the API server to the scaling planners that run in the main application.
+ * The API server can't depend on the planner, so the requests go through the DataLayer.
+ */
+#[derive(Debug)]
+pub enum PlannerRequest {
+    Backtest(BacktestRequest),
+    EvaluateExpression(ExpressionEvaluateRequest),
+    // Approve a pending action by id and apply it to the scaling components
+    ApprovePendingAction(String),
+    // Get the circuit breaker states of the scaling components (component_id => state)
+    GetCircuitBreakers,
+}
+
+/**
+ * BacktestRequest
+ * - definition: the scaling plan to replay
+ * - from, to: the time range in milliseconds (Unix timestamp)
+ * - interval: the interval of the virtual clock in milliseconds (default: the interval of the plan)
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BacktestRequest {
+    pub definition: ScalingPlanDefinition,
+    pub from: u64,
+    pub to: u64,
+    #[serde(default)]
+    pub interval: Option<u64>,
+}
+
+/**
+ * ExpressionEvaluateRequest
+ * - expression: the JavaScript expression to evaluate with the current in-memory metrics data
+ * - variables: the variables of a scaling plan that the expression uses (e.g. $threshold)
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExpressionEvaluateRequest {
+    pub expression: String,
+    #[serde(default)]
+    pub variables: HashMap<String, Value>,
+}
+
+/**
+ * PlannerUnavailableError
+ * The error of the requests that need the running scaling planners (e.g. approval, circuit breakers)
+ * when they don't run in this instance. Only the leader runs them when the leader election is enabled.
+ */
+#[derive(Debug)]
+pub struct PlannerUnavailableError;
+
+impl std::fmt::Display for PlannerUnavailableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The scaling planners are not running in this instance. Send the request to the leader."
+        )
+    }
+}
+
+impl std::error::Error for PlannerUnavailableError {}
+
+// The sender of the response of a PlannerRequest
+pub type PlannerResponder = tokio::sync::oneshot::Sender<anyhow::Result<serde_json::Value>>;
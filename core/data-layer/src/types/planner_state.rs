@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/**
+ * PlannerState
+ * The runtime state of a ScalingPlanner that is restored when the planner is recreated (e.g. restart, definition change)
+ * - last_plan_item_id: the last applied plan item
+ * - component_actions: the last action of each scaling component for the cool downs (component_id => action)
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct PlannerState {
+    #[serde(default)]
+    pub last_plan_item_id: String,
+    #[serde(default)]
+    pub component_actions: HashMap<String, ComponentAction>,
+}
+
+/**
+ * ComponentAction
+ * The last action that was applied to a scaling component
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ComponentAction {
+    pub plan_item_id: String,
+    pub applied_at: DateTime<Utc>,
+    pub cool_down_until: DateTime<Utc>,
+}
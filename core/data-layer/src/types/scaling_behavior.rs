@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/**
+ * ScalingBehavior
+ * The direction-aware behavior of a ScalingPlan or a PlanItem (like the behavior of Kubernetes HPA)
+ * - scale_up: the rules when a numeric param of a scaling component increases
+ * - scale_down: the rules when a numeric param of a scaling component decreases
+ */
+#[derive(TS)]
+#[ts(
+    export,
+    export_to = "../web-app/src/types/bindings/scaling-behavior.ts"
+)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ScalingBehavior {
+    #[serde(default)]
+    pub scale_up: Option<ScalingRules>,
+    #[serde(default)]
+    pub scale_down: Option<ScalingRules>,
+}
+
+/**
+ * ScalingRules
+ * - stabilization_window_seconds: the recommendations in the last N seconds are considered before scaling.
+ *   For scaling up, the lowest recommendation is used. For scaling down, the highest recommendation is used.
+ */
+#[derive(TS)]
+#[ts(export, export_to = "../web-app/src/types/bindings/scaling-rules.ts")]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ScalingRules {
+    #[serde(default)]
+    pub stabilization_window_seconds: u64,
+}
+
+impl ScalingBehavior {
+    // The rules of the plan item override the rules of the plan for each direction
+    pub fn merge(
+        item_behavior: Option<&ScalingBehavior>,
+        plan_behavior: Option<&ScalingBehavior>,
+    ) -> ScalingBehavior {
+        let scale_up = item_behavior
+            .and_then(|behavior| behavior.scale_up.clone())
+            .or_else(|| plan_behavior.and_then(|behavior| behavior.scale_up.clone()));
+        let scale_down = item_behavior
+            .and_then(|behavior| behavior.scale_down.clone())
+            .or_else(|| plan_behavior.and_then(|behavior| behavior.scale_down.clone()));
+        ScalingBehavior {
+            scale_up,
+            scale_down,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_behavior() {
+        let plan_behavior = ScalingBehavior {
+            scale_up: Some(ScalingRules {
+                stabilization_window_seconds: 10,
+            }),
+            scale_down: Some(ScalingRules {
+                stabilization_window_seconds: 300,
+            }),
+        };
+        let item_behavior = ScalingBehavior {
+            scale_up: None,
+            scale_down: Some(ScalingRules {
+                stabilization_window_seconds: 60,
+            }),
+        };
+        let merged = ScalingBehavior::merge(Some(&item_behavior), Some(&plan_behavior));
+        assert_eq!(merged.scale_up.unwrap().stabilization_window_seconds, 10);
+        assert_eq!(merged.scale_down.unwrap().stabilization_window_seconds, 60);
+
+        let merged = ScalingBehavior::merge(None, None);
+        assert_eq!(merged, ScalingBehavior::default());
+    }
+}
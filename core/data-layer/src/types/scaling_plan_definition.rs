@@ -1,10 +1,19 @@
-use super::{object_kind::ObjectKind, plan_item_definition::PlanItemDefinition, validate_id_regex};
+use super::{
+    object_kind::ObjectKind, plan_item_definition::PlanItemDefinition,
+    scaling_behavior::ScalingBehavior, validate_id_regex,
+};
 use serde::{Deserialize, Serialize};
 use serde_valid::Validate;
 use std::collections::HashMap;
 use ts_rs::TS;
 
 pub const DEFAULT_PLAN_INTERVAL: u16 = 1000;
+// metadata.mode: shadow - evaluate the plan without applying the scaling components
+pub const PLAN_MODE_SHADOW: &str = "shadow";
+// metadata.execution_mode: first_match (default) - only the first matching plan item runs in an interval
+pub const EXECUTION_MODE_FIRST_MATCH: &str = "first_match";
+// metadata.execution_mode: all_matching - every matching plan item runs in priority order in an interval
+pub const EXECUTION_MODE_ALL_MATCHING: &str = "all_matching";
 
 fn default_kind() -> ObjectKind {
     ObjectKind::ScalingPlan
@@ -39,6 +48,9 @@ pub struct ScalingPlanDefinition {
     pub plans: Vec<PlanItemDefinition>,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    // Stabilization windows for scaling up and down
+    #[serde(default)]
+    pub behavior: Option<ScalingBehavior>,
 }
 
 impl Default for ScalingPlanDefinition {
@@ -51,6 +63,7 @@ impl Default for ScalingPlanDefinition {
             variables: HashMap::new(),
             plans: vec![],
             enabled: true,
+            behavior: None,
         }
     }
 }
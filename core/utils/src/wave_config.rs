@@ -18,6 +18,14 @@ const DEFAULT_WEB_UI: bool = true;
 const DEFAULT_WEB_UI_HOST: &str = "0.0.0.0";
 const DEFAULT_WEB_UI_PORT: u16 = 3025;
 const DEFAULT_RESET_DEFINITIONS_ON_STARTUP: bool = false;
+const DEFAULT_SHADOW_MODE: bool = false;
+const DEFAULT_EXPRESSION_TIME_LIMIT_MS: u64 = 1000;
+const DEFAULT_EXPRESSION_MEMORY_LIMIT_MB: u64 = 64;
+const DEFAULT_PENDING_ACTION_TTL: &str = "1h";
+const DEFAULT_LEADER_ELECTION_ENABLED: bool = false;
+const DEFAULT_LEADER_ELECTION_LEASE_NAME: &str = "wave-autoscale-leader";
+const DEFAULT_LEADER_ELECTION_LEASE_DURATION_SEC: u64 = 15;
+const DEFAULT_LEADER_ELECTION_RENEW_INTERVAL_SEC: u64 = 5;
 const DEFAULT_WEBHOOKS: Option<Vec<Webhooks>> = None;
 const DEFAULT_WEBHOOKS_URL: Option<String> = None;
 const DEFAULT_WEBHOOKS_HEADERS: Option<HashMap<String, String>> = None;
@@ -61,6 +69,27 @@ fn default_web_ui_port() -> u16 {
 fn default_reset_definitions_on_startup() -> bool {
     DEFAULT_RESET_DEFINITIONS_ON_STARTUP
 }
+fn default_shadow_mode() -> bool {
+    DEFAULT_SHADOW_MODE
+}
+fn default_expression_time_limit_ms() -> u64 {
+    DEFAULT_EXPRESSION_TIME_LIMIT_MS
+}
+fn default_expression_memory_limit_mb() -> u64 {
+    DEFAULT_EXPRESSION_MEMORY_LIMIT_MB
+}
+fn default_pending_action_ttl() -> String {
+    DEFAULT_PENDING_ACTION_TTL.to_string()
+}
+fn default_leader_election_lease_name() -> String {
+    DEFAULT_LEADER_ELECTION_LEASE_NAME.to_string()
+}
+fn default_leader_election_lease_duration_sec() -> u64 {
+    DEFAULT_LEADER_ELECTION_LEASE_DURATION_SEC
+}
+fn default_leader_election_renew_interval_sec() -> u64 {
+    DEFAULT_LEADER_ELECTION_RENEW_INTERVAL_SEC
+}
 fn default_webhooks() -> Option<Vec<Webhooks>> {
     DEFAULT_WEBHOOKS
 }
@@ -99,6 +128,57 @@ pub enum WebhookType {
     // SlackOauth, // TODO: To be developed.
 }
 
+#[derive(Debug, PartialEq, Deserialize, Clone, Serialize, Default)]
+pub enum LeaderElectionBackend {
+    // A lease row in the database of db_url
+    #[default]
+    #[serde(alias = "Database", alias = "database")]
+    Database,
+    // A Lease object (coordination.k8s.io/v1) in the Kubernetes cluster
+    #[serde(alias = "Kubernetes", alias = "kubernetes")]
+    Kubernetes,
+}
+
+/**
+ * LeaderElectionConfig
+ * Only the leader runs the scaling plans and the metric collectors. The followers serve the API.
+ * - identity: the unique name of this instance (default: HOSTNAME and a random suffix)
+ * - lease_duration_sec: a follower takes over the lease when the leader has not renewed it for this duration
+ * - renew_interval_sec: how often the leader renews the lease and the followers try to acquire it
+ * - kubernetes_namespace: the namespace of the Lease object (default: POD_NAMESPACE or "default")
+ */
+#[derive(Debug, PartialEq, Deserialize, Clone, Serialize)]
+pub struct LeaderElectionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub backend: LeaderElectionBackend,
+    #[serde(default)]
+    pub identity: Option<String>,
+    #[serde(default = "default_leader_election_lease_name")]
+    pub lease_name: String,
+    #[serde(default = "default_leader_election_lease_duration_sec")]
+    pub lease_duration_sec: u64,
+    #[serde(default = "default_leader_election_renew_interval_sec")]
+    pub renew_interval_sec: u64,
+    #[serde(default)]
+    pub kubernetes_namespace: Option<String>,
+}
+
+impl Default for LeaderElectionConfig {
+    fn default() -> Self {
+        LeaderElectionConfig {
+            enabled: DEFAULT_LEADER_ELECTION_ENABLED,
+            backend: LeaderElectionBackend::default(),
+            identity: None,
+            lease_name: DEFAULT_LEADER_ELECTION_LEASE_NAME.to_string(),
+            lease_duration_sec: DEFAULT_LEADER_ELECTION_LEASE_DURATION_SEC,
+            renew_interval_sec: DEFAULT_LEADER_ELECTION_RENEW_INTERVAL_SEC,
+            kubernetes_namespace: None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Deserialize, Clone, Serialize)]
 pub struct WaveConfig {
     // Wave Autoscale
@@ -124,6 +204,29 @@ pub struct WaveConfig {
     #[serde(default = "default_reset_definitions_on_startup")]
     pub reset_definitions_on_startup: bool,
 
+    //
+    // Scaling Planner
+    //
+    // Run all plans in shadow mode. Plans are evaluated but the scaling components are not applied.
+    #[serde(default = "default_shadow_mode")]
+    pub shadow_mode: bool,
+    // The CPU time limit of an evaluation of the JavaScript expressions (milliseconds). 0 means no limit.
+    #[serde(default = "default_expression_time_limit_ms")]
+    pub expression_time_limit_ms: u64,
+    // The memory limit of the JavaScript runtime of a scaling plan (megabytes). 0 means no limit.
+    #[serde(default = "default_expression_memory_limit_mb")]
+    pub expression_memory_limit_mb: u64,
+    // How long the pending actions of the plan items with require_approval wait for the approval (e.g. 30m, 1h)
+    #[serde(default = "default_pending_action_ttl")]
+    pub pending_action_ttl: String,
+
+    //
+    // High Availability
+    //
+    // Run multiple instances with the same db_url. Only the leader runs the scaling plans and the metric collectors.
+    #[serde(default)]
+    pub leader_election: LeaderElectionConfig,
+
     //
     // Metrics
     //
@@ -177,6 +280,11 @@ impl Default for WaveConfig {
             watch_definition_duration: DEFAULT_WATCH_DEFINITION_DURATION,
             plan_logs_retention: DEFAULT_PLAN_LOGS_RETENTION.to_string(),
             reset_definitions_on_startup: DEFAULT_RESET_DEFINITIONS_ON_STARTUP,
+            shadow_mode: DEFAULT_SHADOW_MODE,
+            expression_time_limit_ms: DEFAULT_EXPRESSION_TIME_LIMIT_MS,
+            expression_memory_limit_mb: DEFAULT_EXPRESSION_MEMORY_LIMIT_MB,
+            pending_action_ttl: DEFAULT_PENDING_ACTION_TTL.to_string(),
+            leader_election: LeaderElectionConfig::default(),
             host: DEFAULT_API_HOST.to_string(),
             port: DEFAULT_API_PORT,
             web_ui: DEFAULT_WEB_UI,
@@ -263,5 +371,23 @@ mod tests {
         assert_eq!(wave_config.web_ui_host, DEFAULT_WEB_UI_HOST);
         assert_eq!(wave_config.web_ui_port, DEFAULT_WEB_UI_PORT);
         assert_eq!(wave_config.webhooks, DEFAULT_WEBHOOKS);
+        assert_eq!(wave_config.shadow_mode, DEFAULT_SHADOW_MODE);
+        assert_eq!(
+            wave_config.expression_time_limit_ms,
+            DEFAULT_EXPRESSION_TIME_LIMIT_MS
+        );
+        assert_eq!(
+            wave_config.expression_memory_limit_mb,
+            DEFAULT_EXPRESSION_MEMORY_LIMIT_MB
+        );
+        assert_eq!(wave_config.pending_action_ttl, DEFAULT_PENDING_ACTION_TTL);
+        assert_eq!(
+            wave_config.leader_election.enabled,
+            DEFAULT_LEADER_ELECTION_ENABLED
+        );
+        assert_eq!(
+            wave_config.leader_election.lease_duration_sec,
+            DEFAULT_LEADER_ELECTION_LEASE_DURATION_SEC
+        );
     }
 }
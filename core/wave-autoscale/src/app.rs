@@ -1,23 +1,36 @@
 use crate::{
     metric_updater::{MetricUpdater, SharedMetricUpdater},
     scaling_component::{ScalingComponentManager, SharedScalingComponentManager},
-    scaling_planner::scaling_planner_manager::{
-        ScalingPlannerManager, SharedScalingPlannerManager,
+    scaling_planner::{
+        backtest::backtest,
+        expression_evaluator::evaluate_expression,
+        js_sandbox::JsSandboxLimits,
+        pending_actions::{approve_pending_action, parse_pending_action_ttl},
+        scaling_planner_manager::{ScalingPlannerManager, SharedScalingPlannerManager},
     },
 };
-use data_layer::data_layer::DataLayer;
-use std::sync::Arc;
+use data_layer::{
+    data_layer::DataLayer,
+    types::planner_request::{PlannerRequest, PlannerUnavailableError},
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use tokio::time::sleep;
 use tracing::{debug, error, info};
 use utils::wave_config::WaveConfig;
 
 pub struct App {
-    _wave_config: WaveConfig,
+    wave_config: WaveConfig,
     shared_data_layer: Arc<DataLayer>,
     shared_metric_updater: SharedMetricUpdater,
     shared_scaling_component_manager: SharedScalingComponentManager,
     shared_scaling_planner_manager: SharedScalingPlannerManager,
     plan_logs_remover_handle: Option<tokio::task::JoinHandle<()>>,
+    planner_request_handle: Option<tokio::task::JoinHandle<()>>,
+    // Whether the scaling components are loaded and the scaling plans run in this instance (false in the followers)
+    planners_running: Arc<AtomicBool>,
 }
 
 impl App {
@@ -28,22 +41,36 @@ impl App {
         // Create ScalingComponentManager
         let shared_scaling_component_manager = ScalingComponentManager::new_shared();
 
+        // The TTL of the pending actions of the plan items with require_approval
+        let pending_action_ttl = match parse_pending_action_ttl(&wave_config.pending_action_ttl) {
+            Ok(pending_action_ttl) => pending_action_ttl,
+            Err(error) => {
+                error!("{}. The default TTL (1h) is used.", error);
+                chrono::Duration::hours(1)
+            }
+        };
+
         // Create ScalingPlanManager
         let shared_scaling_planner_manager = ScalingPlannerManager::new_shared(
             shared_data_layer.clone(),
             shared_metric_updater.clone(),
             shared_scaling_component_manager.clone(),
             wave_config.webhooks.clone(),
+            wave_config.shadow_mode,
+            JsSandboxLimits::from(&wave_config),
+            pending_action_ttl,
         );
 
         // Create App
         App {
-            _wave_config: wave_config,
+            wave_config,
             shared_data_layer,
             shared_metric_updater,
             shared_scaling_component_manager,
             shared_scaling_planner_manager,
             plan_logs_remover_handle: None,
+            planner_request_handle: None,
+            planners_running: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -94,6 +121,7 @@ impl App {
                     return;
                 }
             }
+            self.planners_running.store(true, Ordering::SeqCst);
         }
 
         // Scaling Planner Manager
@@ -115,6 +143,26 @@ impl App {
             let number_of_plans = plan_definitions.len();
             info!("[app] {} plan definitions", number_of_plans);
 
+            // The maintenance windows are loaded with the plans. A change of them restarts the plans by the definitions watcher.
+            let maintenance_windows = self
+                .shared_data_layer
+                .get_enabled_maintenance_windows()
+                .await;
+            if maintenance_windows.is_err() {
+                let error = maintenance_windows.err().unwrap();
+                error!("Error getting maintenance window definitions: {}", error);
+                return;
+            }
+            manager_writer.set_maintenance_windows(maintenance_windows.unwrap());
+
+            // Add the definitions even without plans to drop the states of the removed plans
+            let scaling_plan_result = manager_writer.add_definitions(plan_definitions);
+            if scaling_plan_result.is_err() {
+                let error = scaling_plan_result.err().unwrap();
+                error!("Error adding scaling plan definitions: {}", error);
+                return;
+            }
+
             if number_of_plans != 0 {
                 // Run Metric Updater
                 {
@@ -123,18 +171,31 @@ impl App {
                     updater_writer.run().await;
                 }
 
-                let scaling_plan_result = manager_writer.add_definitions(plan_definitions);
-                if scaling_plan_result.is_err() {
-                    let error = scaling_plan_result.err().unwrap();
-                    error!("Error adding scaling plan definitions: {}", error);
-                    return;
-                }
                 manager_writer.run();
                 info!("[app] ScalingPlans started: {} plans", number_of_plans);
             }
         }
     }
 
+    // Stop the scaling plans and the metric updater (e.g. when this instance is no longer the leader)
+    pub async fn stop(&mut self) {
+        self.planners_running.store(false, Ordering::SeqCst);
+        {
+            let mut manager_writer = self.shared_scaling_planner_manager.write().await;
+            manager_writer.stop();
+            manager_writer.remove_all();
+        }
+        {
+            let mut updater_writer = self.shared_metric_updater.write().await;
+            updater_writer.stop();
+        }
+        self.shared_scaling_component_manager
+            .write()
+            .await
+            .remove_all();
+        info!("[app] ScalingPlans stopped");
+    }
+
     // Run the cron job to remove the old plan logs
     pub fn run_remove_plan_logs_cron_job(&mut self, duration_string: String) {
         self.stop_remove_plan_logs_cron_job();
@@ -178,6 +239,76 @@ impl App {
         }
     }
 
+    // Handle the requests to the scaling planners from the API server (e.g. backtest, expression evaluation, approval)
+    pub async fn run_planner_request_handler(&mut self) {
+        let Some(mut receiver) = self.shared_data_layer.take_planner_request_receiver().await else {
+            error!("[app] The planner request handler is already running");
+            return;
+        };
+        let data_layer = self.shared_data_layer.clone();
+        let scaling_component_manager = self.shared_scaling_component_manager.clone();
+        let scaling_planner_manager = self.shared_scaling_planner_manager.clone();
+        let js_limits = JsSandboxLimits::from(&self.wave_config);
+        let planners_running = self.planners_running.clone();
+        let handle = tokio::spawn(async move {
+            while let Some((request, responder)) = receiver.recv().await {
+                // The followers have no scaling components and no metrics data. Only the backtest reads the database.
+                if !planners_running.load(Ordering::SeqCst)
+                    && !matches!(request, PlannerRequest::Backtest(_))
+                {
+                    let _ = responder.send(Err(PlannerUnavailableError.into()));
+                    continue;
+                }
+                let response = match request {
+                    PlannerRequest::Backtest(backtest_request) => {
+                        debug!(
+                            "[app] Backtest: {} ({} - {})",
+                            backtest_request.definition.id,
+                            backtest_request.from,
+                            backtest_request.to
+                        );
+                        // A backtest can run long, so it runs on its own task and the other requests (e.g. approvals) do not wait for it
+                        let data_layer = data_layer.clone();
+                        tokio::spawn(async move {
+                            let response = backtest(&data_layer, backtest_request, js_limits)
+                                .await
+                                .and_then(|timeline| Ok(serde_json::to_value(timeline)?));
+                            let _ = responder.send(response);
+                        });
+                        continue;
+                    }
+                    PlannerRequest::EvaluateExpression(evaluate_request) => {
+                        debug!("[app] Evaluate expression: {}", evaluate_request.expression);
+                        evaluate_expression(evaluate_request, js_limits)
+                            .await
+                            .and_then(|result| Ok(serde_json::to_value(result)?))
+                    }
+                    PlannerRequest::ApprovePendingAction(id) => {
+                        debug!("[app] Approve pending action: {}", id);
+                        approve_pending_action(
+                            &data_layer,
+                            &scaling_component_manager,
+                            &scaling_planner_manager,
+                            &id,
+                            js_limits,
+                        )
+                        .await
+                        .and_then(|results| Ok(serde_json::to_value(results)?))
+                    }
+                    PlannerRequest::GetCircuitBreakers => {
+                        let circuit_breakers = scaling_component_manager
+                            .read()
+                            .await
+                            .get_circuit_breakers();
+                        Ok(serde_json::json!(circuit_breakers))
+                    }
+                };
+                let _ = responder.send(response);
+            }
+        });
+        self.planner_request_handle = Some(handle);
+    }
+
     // For unit testing
     #[allow(dead_code)]
     pub fn get_data_layer(&self) -> Arc<DataLayer> {
@@ -0,0 +1,44 @@
+use super::LeaderLease;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Duration;
+use data_layer::data_layer::DataLayer;
+use std::sync::Arc;
+
+// A lease row in the database that all instances share through db_url
+pub struct DatabaseLease {
+    data_layer: Arc<DataLayer>,
+    name: String,
+    identity: String,
+    lease_duration: Duration,
+}
+
+impl DatabaseLease {
+    pub fn new(
+        data_layer: Arc<DataLayer>,
+        name: String,
+        identity: String,
+        lease_duration: Duration,
+    ) -> Self {
+        DatabaseLease {
+            data_layer,
+            name,
+            identity,
+            lease_duration,
+        }
+    }
+}
+
+#[async_trait]
+impl LeaderLease for DatabaseLease {
+    async fn try_acquire(&self) -> Result<bool> {
+        self.data_layer
+            .try_acquire_leader_lease(&self.name, &self.identity, self.lease_duration)
+            .await
+    }
+    async fn release(&self) -> Result<()> {
+        self.data_layer
+            .release_leader_lease(&self.name, &self.identity)
+            .await
+    }
+}
@@ -0,0 +1,161 @@
+use super::LeaderLease;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use k8s_openapi::api::coordination::v1::{Lease, LeaseSpec};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{MicroTime, ObjectMeta};
+use kube::{api::PostParams, Api, Client};
+use tokio::sync::OnceCell;
+
+// The status code of a conflict. Another instance created or updated the Lease in the meantime.
+const CONFLICT: u16 = 409;
+
+// A Lease object (coordination.k8s.io/v1) in the Kubernetes cluster where the instances run
+pub struct KubernetesLease {
+    client: OnceCell<Client>,
+    namespace: String,
+    name: String,
+    identity: String,
+    lease_duration: Duration,
+}
+
+impl KubernetesLease {
+    pub fn new(
+        namespace: String,
+        name: String,
+        identity: String,
+        lease_duration: Duration,
+    ) -> Self {
+        KubernetesLease {
+            client: OnceCell::new(),
+            namespace,
+            name,
+            identity,
+            lease_duration,
+        }
+    }
+
+    async fn get_api(&self) -> Result<Api<Lease>> {
+        // Infer the runtime environment (in-cluster or kubeconfig)
+        let client = self
+            .client
+            .get_or_try_init(|| async { Client::try_default().await })
+            .await?;
+        Ok(Api::namespaced(client.clone(), &self.namespace))
+    }
+
+    fn get_spec(
+        &self,
+        acquire_time: DateTime<Utc>,
+        renew_time: DateTime<Utc>,
+        lease_transitions: i32,
+    ) -> LeaseSpec {
+        LeaseSpec {
+            holder_identity: Some(self.identity.clone()),
+            lease_duration_seconds: Some(self.lease_duration.num_seconds() as i32),
+            acquire_time: Some(MicroTime(acquire_time)),
+            renew_time: Some(MicroTime(renew_time)),
+            lease_transitions: Some(lease_transitions),
+        }
+    }
+}
+
+// Whether the lease has not been renewed in its duration (or has no holder)
+fn is_expired(spec: &LeaseSpec, now: DateTime<Utc>) -> bool {
+    if spec.holder_identity.is_none() {
+        return true;
+    }
+    match (spec.renew_time.as_ref(), spec.lease_duration_seconds) {
+        (Some(renew_time), Some(lease_duration_seconds)) => {
+            renew_time.0 + Duration::seconds(lease_duration_seconds as i64) <= now
+        }
+        _ => true,
+    }
+}
+
+#[async_trait]
+impl LeaderLease for KubernetesLease {
+    async fn try_acquire(&self) -> Result<bool> {
+        let api = self.get_api().await?;
+        let now = Utc::now();
+        let Some(mut lease) = api.get_opt(&self.name).await? else {
+            let lease = Lease {
+                metadata: ObjectMeta {
+                    name: Some(self.name.clone()),
+                    namespace: Some(self.namespace.clone()),
+                    ..Default::default()
+                },
+                spec: Some(self.get_spec(now, now, 0)),
+            };
+            return match api.create(&PostParams::default(), &lease).await {
+                Ok(_) => Ok(true),
+                Err(kube::Error::Api(response)) if response.code == CONFLICT => Ok(false),
+                Err(error) => Err(error.into()),
+            };
+        };
+
+        let spec = lease.spec.clone().unwrap_or_default();
+        let is_holder = spec.holder_identity.as_deref() == Some(self.identity.as_str());
+        if !is_holder && !is_expired(&spec, now) {
+            return Ok(false);
+        }
+        let (acquire_time, lease_transitions) = if is_holder {
+            (
+                spec.acquire_time
+                    .map(|acquire_time| acquire_time.0)
+                    .unwrap_or(now),
+                spec.lease_transitions.unwrap_or(0),
+            )
+        } else {
+            (now, spec.lease_transitions.unwrap_or(0) + 1)
+        };
+        lease.spec = Some(self.get_spec(acquire_time, now, lease_transitions));
+        // The resourceVersion of the read Lease makes the replace fail if another instance updated it first
+        match api
+            .replace(&self.name, &PostParams::default(), &lease)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(kube::Error::Api(response)) if response.code == CONFLICT => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn release(&self) -> Result<()> {
+        let api = self.get_api().await?;
+        let Some(mut lease) = api.get_opt(&self.name).await? else {
+            return Ok(());
+        };
+        let Some(spec) = lease.spec.as_mut() else {
+            return Ok(());
+        };
+        if spec.holder_identity.as_deref() != Some(self.identity.as_str()) {
+            return Ok(());
+        }
+        spec.holder_identity = None;
+        spec.renew_time = None;
+        api.replace(&self.name, &PostParams::default(), &lease)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_expired() {
+        let now = Utc::now();
+        let lease = KubernetesLease::new(
+            "default".to_string(),
+            "wave-autoscale-leader".to_string(),
+            "instance_1".to_string(),
+            Duration::seconds(15),
+        );
+        let spec = lease.get_spec(now, now, 0);
+        assert!(!is_expired(&spec, now + Duration::seconds(10)));
+        assert!(is_expired(&spec, now + Duration::seconds(15)));
+        assert!(is_expired(&LeaseSpec::default(), now));
+    }
+}
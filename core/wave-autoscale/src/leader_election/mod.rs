@@ -0,0 +1,252 @@
+pub mod database_lease;
+pub mod kubernetes_lease;
+
+use self::{database_lease::DatabaseLease, kubernetes_lease::KubernetesLease};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use data_layer::data_layer::DataLayer;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+use utils::wave_config::{LeaderElectionBackend, LeaderElectionConfig};
+
+// A lease that only one instance can hold at a time
+#[async_trait]
+pub trait LeaderLease: Send + Sync {
+    // Acquire or renew the lease. Returns whether this instance holds it for lease_duration from now.
+    async fn try_acquire(&self) -> Result<bool>;
+    async fn release(&self) -> Result<()>;
+}
+
+pub fn create_leader_lease(
+    config: &LeaderElectionConfig,
+    identity: &str,
+    shared_data_layer: Arc<DataLayer>,
+) -> Box<dyn LeaderLease> {
+    let lease_duration = Duration::seconds(config.lease_duration_sec as i64);
+    match config.backend {
+        LeaderElectionBackend::Database => Box::new(DatabaseLease::new(
+            shared_data_layer,
+            config.lease_name.clone(),
+            identity.to_string(),
+            lease_duration,
+        )),
+        LeaderElectionBackend::Kubernetes => {
+            let namespace = config
+                .kubernetes_namespace
+                .clone()
+                .or_else(|| std::env::var("POD_NAMESPACE").ok())
+                .unwrap_or_else(|| "default".to_string());
+            Box::new(KubernetesLease::new(
+                namespace,
+                config.lease_name.clone(),
+                identity.to_string(),
+                lease_duration,
+            ))
+        }
+    }
+}
+
+// The identity of this instance in the lease (e.g. the pod name with a random suffix)
+pub fn get_identity(config: &LeaderElectionConfig) -> String {
+    if let Some(identity) = config.identity.as_ref() {
+        return identity.clone();
+    }
+    let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "wave-autoscale".to_string());
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    format!("{}-{}", hostname, &suffix[..8])
+}
+
+/**
+ * LeaderState
+ * Whether this instance is the leader, judged by the last successful renewal
+ * - The leader steps down when it can't renew the lease before the next renewal would be too late
+ *   so that it stops before another instance can acquire the expired lease.
+ */
+#[derive(Debug)]
+pub struct LeaderState {
+    lease_duration: Duration,
+    renew_interval: Duration,
+    renewed_at: Option<DateTime<Utc>>,
+}
+
+impl LeaderState {
+    pub fn new(lease_duration: Duration, renew_interval: Duration) -> Self {
+        LeaderState {
+            lease_duration,
+            renew_interval,
+            renewed_at: None,
+        }
+    }
+
+    // Update the state with the result of try_acquire and return whether this instance is the leader
+    // - now: when try_acquire was called. The lease is valid from then, not from when the call returned.
+    pub fn update(&mut self, acquired: Result<bool>, now: DateTime<Utc>) -> bool {
+        match acquired {
+            Ok(true) => {
+                self.renewed_at = Some(now);
+                true
+            }
+            Ok(false) => {
+                self.renewed_at = None;
+                false
+            }
+            Err(error) => {
+                let Some(renewed_at) = self.renewed_at else {
+                    return false;
+                };
+                if renewed_at + self.lease_duration <= now + self.renew_interval {
+                    error!(
+                        "[leader-election] Failed to renew the lease in time: {}",
+                        error
+                    );
+                    self.renewed_at = None;
+                    false
+                } else {
+                    warn!("[leader-election] Failed to renew the lease: {}", error);
+                    true
+                }
+            }
+        }
+    }
+}
+
+// Acquire or renew the lease within the timeout. A hung call fails so that the leader can step down in time.
+async fn try_acquire_with_timeout(
+    lease: &dyn LeaderLease,
+    timeout: std::time::Duration,
+) -> Result<bool> {
+    match tokio::time::timeout(timeout, lease.try_acquire()).await {
+        Ok(acquired) => acquired,
+        Err(_) => Err(anyhow::anyhow!(
+            "Timed out acquiring the lease after {:?}",
+            timeout
+        )),
+    }
+}
+
+/**
+ * Run the leader election in the background
+ * - The leader renews the lease every renew_interval_sec and the followers try to acquire it
+ * - Returns a receiver of whether this instance is the leader
+ */
+pub fn run_leader_election(
+    config: &LeaderElectionConfig,
+    shared_data_layer: Arc<DataLayer>,
+) -> watch::Receiver<bool> {
+    let identity = get_identity(config);
+    let lease = create_leader_lease(config, &identity, shared_data_layer);
+    let lease_duration = Duration::seconds(config.lease_duration_sec as i64);
+    // The lease should be renewed before it expires
+    let renew_interval_sec = config
+        .renew_interval_sec
+        .clamp(1, config.lease_duration_sec.max(2) - 1);
+    let renew_interval = Duration::seconds(renew_interval_sec as i64);
+    info!(
+        "[leader-election] {} joins the election ({:?}, lease: {})",
+        identity, config.backend, config.lease_name
+    );
+
+    let (sender, receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        let mut leader_state = LeaderState::new(lease_duration, renew_interval);
+        loop {
+            let now = Utc::now();
+            let acquired = try_acquire_with_timeout(
+                lease.as_ref(),
+                std::time::Duration::from_secs(renew_interval_sec),
+            )
+            .await;
+            let is_leader = leader_state.update(acquired, now);
+            if is_leader != *sender.borrow() {
+                if is_leader {
+                    info!("[leader-election] {} is the leader", identity);
+                } else {
+                    info!("[leader-election] {} is a follower", identity);
+                }
+                if sender.send(is_leader).is_err() {
+                    // Nobody follows the election anymore
+                    if is_leader {
+                        let _ = lease.release().await;
+                    }
+                    return;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(renew_interval_sec)).await;
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leader_state() {
+        let mut leader_state = LeaderState::new(Duration::seconds(15), Duration::seconds(5));
+        let now = Utc::now();
+        assert!(!leader_state.update(Err(anyhow::anyhow!("error")), now));
+        assert!(leader_state.update(Ok(true), now));
+
+        // The leader keeps the leadership while the lease is valid for the next renewal
+        assert!(leader_state.update(Err(anyhow::anyhow!("error")), now + Duration::seconds(5)));
+        assert!(!leader_state.update(Err(anyhow::anyhow!("error")), now + Duration::seconds(10)));
+
+        // Another instance holds the lease
+        assert!(leader_state.update(Ok(true), now + Duration::seconds(15)));
+        assert!(!leader_state.update(Ok(false), now + Duration::seconds(20)));
+    }
+
+    #[test]
+    fn test_leader_state_with_slow_renewal() {
+        let mut leader_state = LeaderState::new(Duration::seconds(15), Duration::seconds(5));
+        // The renewal was called at now and returned a few seconds later
+        let now = Utc::now();
+        assert!(leader_state.update(Ok(true), now));
+
+        // The lease expires at now + 15, so the leader steps down before the renewal at now + 10 would be too late
+        assert!(leader_state.update(Err(anyhow::anyhow!("error")), now + Duration::seconds(5)));
+        assert!(!leader_state.update(Err(anyhow::anyhow!("error")), now + Duration::seconds(10)));
+    }
+
+    struct HungLease;
+
+    #[async_trait]
+    impl LeaderLease for HungLease {
+        async fn try_acquire(&self) -> Result<bool> {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok(true)
+        }
+        async fn release(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_try_acquire_with_timeout() {
+        let acquired =
+            try_acquire_with_timeout(&HungLease, std::time::Duration::from_millis(100)).await;
+        assert!(acquired.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_leader_election() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        let data_layer = Arc::new(data_layer);
+        let config = LeaderElectionConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let mut leader = run_leader_election(&config, data_layer.clone());
+        leader.changed().await.unwrap();
+        assert!(*leader.borrow());
+
+        // The follower does not get the lease while the leader renews it
+        let mut follower = run_leader_election(&config, data_layer.clone());
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+        assert!(!*follower.borrow_and_update());
+    }
+}
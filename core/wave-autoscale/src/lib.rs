@@ -1,4 +1,5 @@
 pub mod app;
+pub mod leader_election;
 pub mod metric_collector_manager;
 pub mod metric_updater;
 pub mod scaling_component;
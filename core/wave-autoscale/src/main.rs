@@ -2,6 +2,7 @@
  * Wave Autoscale
  */
 mod app;
+mod leader_election;
 mod metric_collector_manager;
 mod metric_updater;
 mod scaling_component;
@@ -14,11 +15,24 @@ use data_layer::data_layer::DataLayer;
 use metric_collector_manager::MetricsCollectorManager;
 use std::sync::Arc;
 use tokio::sync::watch;
-use tracing::{debug, error};
+use tracing::{debug, error, info};
 use utils::{config_path::find_file_in_wa, wave_config::WaveConfig};
 
 const LOCAL_DEFINITION_FILE_NAME: &str = "wave-definition.yaml";
 
+// Wait until the leadership of this instance changes. It never returns if the leader election is disabled.
+async fn leader_changed(leader_receiver: &mut Option<watch::Receiver<bool>>) -> bool {
+    let Some(receiver) = leader_receiver.as_mut() else {
+        return std::future::pending().await;
+    };
+    if receiver.changed().await.is_err() {
+        // The leader election stopped
+        *leader_receiver = None;
+        return false;
+    }
+    *receiver.borrow()
+}
+
 #[tokio::main]
 async fn main() {
     //
@@ -88,6 +102,9 @@ async fn main() {
     // Run the main application(controller)
     let mut app = app::App::new(wave_config.clone(), shared_data_layer.clone()).await;
 
+    // Handle the requests to the scaling planners from the API server
+    app.run_planner_request_handler().await;
+
     //
     // Run some jobs (Plan Logs Remover, Reset definitions on startup, Watch the definition file, and the main application(controller))
     //
@@ -102,6 +119,7 @@ async fn main() {
         let _ = shared_data_layer.delete_all_metrics().await;
         let _ = shared_data_layer.delete_all_scaling_components().await;
         let _ = shared_data_layer.delete_all_plans().await;
+        let _ = shared_data_layer.delete_all_maintenance_windows().await;
     }
 
     // Sync the definition file if it exists
@@ -124,9 +142,47 @@ async fn main() {
         None
     };
 
+    // Leader election
+    // Only the leader runs the scaling plans and the metric collectors. The followers serve the API.
+    let mut leader_receiver: Option<watch::Receiver<bool>> = if wave_config.leader_election.enabled
+    {
+        Some(leader_election::run_leader_election(
+            &wave_config.leader_election,
+            shared_data_layer.clone(),
+        ))
+    } else {
+        None
+    };
+    let mut is_leader = leader_receiver.is_none();
+
     // Run the main application(controller) in a loop
     // If watch_duration is 0, run the main application(controller) only once
-    while watch_receiver.is_some() && watch_receiver.as_mut().unwrap().changed().await.is_ok() {
+    while let Some(receiver) = watch_receiver.as_mut() {
+        tokio::select! {
+            result = receiver.changed() => {
+                if result.is_err() {
+                    break;
+                }
+                if !is_leader {
+                    continue;
+                }
+            }
+            leader = leader_changed(&mut leader_receiver) => {
+                if leader == is_leader {
+                    continue;
+                }
+                is_leader = leader;
+                if !is_leader {
+                    // Stop the metric collectors and the scaling plans
+                    info!("Stopping the scaling plans. This instance is a follower.");
+                    metric_collector_manager.run(&vec![]).await;
+                    app.stop().await;
+                    continue;
+                }
+                info!("Starting the scaling plans. This instance is the leader.");
+            }
+        }
+
         // Update metric collectors
         // TODO: MetricCollectorManager could be moved into the app(controller)
         let shared_data_layer = shared_data_layer.clone();
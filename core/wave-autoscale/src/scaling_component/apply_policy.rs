@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use data_layer::types::apply_policy::CircuitBreakerPolicy;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CircuitState {
+    // The applies are allowed
+    Closed,
+    // The applies are rejected until open_duration has passed
+    Open,
+    // A trial apply is in progress. It closes the circuit on success and opens it again on failure.
+    // Another trial apply is allowed after open_duration if the trial apply never finished (e.g. the planner was stopped).
+    HalfOpen,
+}
+
+/**
+ * CircuitBreaker
+ * The state of the circuit breaker of a scaling component. It is kept when the definitions are reloaded.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitBreaker {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub opened_at: Option<DateTime<Utc>>,
+    // When the trial apply started
+    pub half_open_since: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        CircuitBreaker {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            half_open_since: None,
+            last_error: None,
+        }
+    }
+}
+
+impl CircuitBreaker {
+    // Whether an apply is allowed now. An open circuit becomes half-open after open_duration to allow one trial apply.
+    pub fn try_acquire(&mut self, policy: &CircuitBreakerPolicy, now: DateTime<Utc>) -> bool {
+        match self.state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => {
+                let half_open_since = self.half_open_since.unwrap_or(now);
+                if half_open_since + policy.open_duration <= now {
+                    self.half_open_since = Some(now);
+                    true
+                } else {
+                    false
+                }
+            }
+            CircuitState::Open => {
+                let opened_at = self.opened_at.unwrap_or(now);
+                if opened_at + policy.open_duration <= now {
+                    self.state = CircuitState::HalfOpen;
+                    self.half_open_since = Some(now);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    // The time when the open circuit allows a trial apply
+    pub fn get_retry_at(&self, policy: &CircuitBreakerPolicy) -> Option<DateTime<Utc>> {
+        match self.state {
+            CircuitState::Open => self
+                .opened_at
+                .map(|opened_at| opened_at + policy.open_duration),
+            _ => None,
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.state = CircuitState::Closed;
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.half_open_since = None;
+        self.last_error = None;
+    }
+
+    pub fn record_failure(
+        &mut self,
+        policy: &CircuitBreakerPolicy,
+        error: String,
+        now: DateTime<Utc>,
+    ) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.last_error = Some(error);
+        if self.state == CircuitState::HalfOpen
+            || self.consecutive_failures >= policy.failure_threshold
+        {
+            self.state = CircuitState::Open;
+            self.opened_at = Some(now);
+            self.half_open_since = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let policy = CircuitBreakerPolicy {
+            failure_threshold: 2,
+            open_duration: Duration::minutes(1),
+        };
+        let mut circuit_breaker = CircuitBreaker::default();
+        let now = Utc::now();
+
+        assert!(circuit_breaker.try_acquire(&policy, now));
+        circuit_breaker.record_failure(&policy, "error".to_string(), now);
+        assert_eq!(circuit_breaker.state, CircuitState::Closed);
+        circuit_breaker.record_failure(&policy, "error".to_string(), now);
+        assert_eq!(circuit_breaker.state, CircuitState::Open);
+        assert!(!circuit_breaker.try_acquire(&policy, now + Duration::seconds(30)));
+
+        // A failed trial apply opens the circuit again
+        assert!(circuit_breaker.try_acquire(&policy, now + Duration::minutes(1)));
+        assert_eq!(circuit_breaker.state, CircuitState::HalfOpen);
+        assert!(!circuit_breaker.try_acquire(&policy, now + Duration::minutes(1)));
+        circuit_breaker.record_failure(&policy, "error".to_string(), now + Duration::minutes(1));
+        assert_eq!(circuit_breaker.state, CircuitState::Open);
+
+        // A trial apply that never finished does not keep the circuit half-open forever
+        assert!(circuit_breaker.try_acquire(&policy, now + Duration::minutes(2)));
+        assert!(!circuit_breaker.try_acquire(&policy, now + Duration::seconds(150)));
+        assert!(circuit_breaker.try_acquire(&policy, now + Duration::minutes(3)));
+        assert_eq!(circuit_breaker.state, CircuitState::HalfOpen);
+
+        // A successful trial apply closes the circuit
+        circuit_breaker.record_success();
+        assert_eq!(circuit_breaker.state, CircuitState::Closed);
+        assert_eq!(circuit_breaker.consecutive_failures, 0);
+    }
+}
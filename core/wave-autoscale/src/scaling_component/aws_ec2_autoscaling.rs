@@ -139,6 +139,30 @@ impl ScalingComponent for EC2AutoScalingComponent {
         return_params.insert("desired".to_string(), Value::from(desired_value));
         Ok(return_params)
     }
+
+    async fn get_current_params(
+        &self,
+        _params: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>> {
+        let metadata = self.definition.metadata.clone();
+        let Some(Value::String(asg_name)) = metadata.get("asg_name") else {
+            return Err(anyhow::anyhow!("Invalid metadata"));
+        };
+        let config = get_aws_config_with_metadata(&metadata)
+            .await
+            .map_err(|error| anyhow::anyhow!(error))?;
+        let client = Client::new(&config);
+        // desired, min and max
+        let current_state_array = EC2ComponentTargetValue::iter()
+            .map(|value| format!("${}", value))
+            .collect::<Vec<String>>();
+        let current_state_map =
+            get_current_state_map(current_state_array, client, asg_name.clone()).await?;
+        Ok(current_state_map
+            .into_iter()
+            .map(|(key, value)| (key.trim_start_matches('$').to_string(), Value::from(value)))
+            .collect())
+    }
 }
 
 async fn get_current_state_map(
@@ -198,6 +198,40 @@ impl ScalingComponent for K8sDeploymentScalingComponent {
         return_params.insert("replicas".to_string(), Value::from(replicas_value));
         Ok(return_params)
     }
+
+    async fn get_current_params(
+        &self,
+        _params: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>> {
+        let metadata = self.definition.metadata.clone();
+        let (Some(Value::String(namespace)), Some(Value::String(name))) =
+            (metadata.get("namespace"), metadata.get("name"))
+        else {
+            return Err(anyhow::anyhow!("Invalid metadata"));
+        };
+        let api_server_endpoint = metadata
+            .get("api_server_endpoint")
+            .map(|api_server_endpoint| api_server_endpoint.to_string());
+        let ca_cert = metadata.get("ca_cert").map(|ca_cert| ca_cert.to_string());
+        let client = self
+            .get_client(api_server_endpoint, ca_cert, Some(namespace.to_string()))
+            .await?;
+        let replicas_key = format!("${}", K8sComponentTargetValue::Replicas);
+        let current_state_map = get_current_state_map(
+            vec![replicas_key.clone()],
+            client,
+            namespace.to_string(),
+            name.to_string(),
+        )
+        .await?;
+        let Some(replicas) = current_state_map.get(&replicas_key) else {
+            return Err(anyhow::anyhow!("Failed to get the current replicas"));
+        };
+        Ok(HashMap::from([(
+            K8sComponentTargetValue::Replicas.to_string(),
+            Value::from(*replicas),
+        )]))
+    }
 }
 
 async fn get_current_state_map(
@@ -1,5 +1,6 @@
 pub mod amazon_dynamodb_table;
 pub mod amazon_emr_ec2;
+pub mod apply_policy;
 pub mod aws_ec2_autoscaling;
 pub mod aws_ecs_service_scaling;
 pub mod aws_lambda_function;
@@ -15,6 +16,7 @@ pub mod k8s_json_patch;
 pub mod netfunnel_segment;
 pub mod wa_logger;
 
+use self::apply_policy::CircuitBreaker;
 use self::{
     amazon_dynamodb_table::DynamoDbTableScalingComponent,
     amazon_emr_ec2::EMREC2AutoScalingComponent, aws_ec2_autoscaling::EC2AutoScalingComponent,
@@ -28,11 +30,20 @@ use self::{
     k8s_deployment::K8sDeploymentScalingComponent, k8s_json_patch::K8sPatchScalingComponent,
     netfunnel_segment::NetfunnelSegmentScalingComponent, wa_logger::WALoggerComponent,
 };
+use crate::scaling_planner::rate_limit::ActionHistory;
 use anyhow::Result;
 use async_trait::async_trait;
-use data_layer::ScalingComponentDefinition;
-use std::{collections::HashMap, sync::Arc};
+use chrono::{DateTime, Duration, Utc};
+use data_layer::{
+    types::{apply_policy::ApplyPolicy, max_actions::MaxActions},
+    ScalingComponentDefinition,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 use tokio::sync::RwLock;
+use tracing::warn;
 
 // ScalingComponent can be used in multiple threads. So it needs to be Send + Sync.
 #[async_trait]
@@ -44,6 +55,17 @@ pub trait ScalingComponent: Send + Sync {
     ) -> Result<HashMap<String, serde_json::Value>>;
     fn get_scaling_component_kind(&self) -> &str;
     fn get_id(&self) -> &str;
+    // The current values of the params (e.g. the current replicas) to revert an apply with them.
+    // The transactional plan items can only use the scaling components that support it (TRANSACTIONAL_COMPONENT_KINDS).
+    async fn get_current_params(
+        &self,
+        _params: &HashMap<String, serde_json::Value>,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        Err(anyhow::anyhow!(
+            "{} does not support getting the current params",
+            self.get_scaling_component_kind()
+        ))
+    }
 }
 
 //
@@ -53,13 +75,26 @@ pub type SharedScalingComponentManager = Arc<RwLock<ScalingComponentManager>>;
 
 #[derive(Default)]
 pub struct ScalingComponentManager {
-    scaling_components: HashMap<String, Box<dyn ScalingComponent>>,
+    scaling_components: HashMap<String, Arc<dyn ScalingComponent>>,
+    // component_id => metadata.max_actions of the scaling component definition
+    max_actions: HashMap<String, MaxActions>,
+    // component_id => the recent actions. It is shared by all scaling planners and kept when the definitions are reloaded.
+    action_histories: Mutex<HashMap<String, ActionHistory>>,
+    // component_id => metadata.timeout, retry and circuit_breaker of the scaling component definition
+    apply_policies: HashMap<String, ApplyPolicy>,
+    // component_id => the state of the circuit breaker. It is kept when the definitions are reloaded.
+    // Shared with the ScalingComponentAppliers that apply without the lock of the manager
+    circuit_breakers: Arc<Mutex<HashMap<String, CircuitBreaker>>>,
 }
 
 impl ScalingComponentManager {
     pub fn new() -> Self {
         ScalingComponentManager {
             scaling_components: HashMap::new(),
+            max_actions: HashMap::new(),
+            action_histories: Mutex::new(HashMap::new()),
+            apply_policies: HashMap::new(),
+            circuit_breakers: Arc::new(Mutex::new(HashMap::new())),
         }
     }
     pub fn new_shared() -> SharedScalingComponentManager {
@@ -135,8 +170,18 @@ impl ScalingComponentManager {
         &mut self,
         scaling_component_definition: ScalingComponentDefinition,
     ) -> Result<()> {
+        let max_actions = MaxActions::from_metadata(&scaling_component_definition.metadata)?;
+        let apply_policy = ApplyPolicy::from_metadata(&scaling_component_definition.metadata)?;
         let scaling_component = self.create_scaling_component(&scaling_component_definition)?;
         self.add_scaling_component(scaling_component);
+        if let Some(max_actions) = max_actions {
+            self.max_actions
+                .insert(scaling_component_definition.id.clone(), max_actions);
+        }
+        if let Some(apply_policy) = apply_policy {
+            self.apply_policies
+                .insert(scaling_component_definition.id, apply_policy);
+        }
         Ok(())
     }
 
@@ -151,35 +196,266 @@ impl ScalingComponentManager {
     }
 
     pub fn add_scaling_component(&mut self, scaling_component: Box<dyn ScalingComponent>) {
-        self.scaling_components
-            .insert(scaling_component.get_id().to_string(), scaling_component);
-    }
-
-    pub fn get_scaling_components(&self) -> &HashMap<String, Box<dyn ScalingComponent>> {
-        &self.scaling_components
+        self.scaling_components.insert(
+            scaling_component.get_id().to_string(),
+            Arc::from(scaling_component),
+        );
     }
 
     pub fn remove_all(&mut self) {
         self.scaling_components.clear();
+        self.max_actions.clear();
+        self.apply_policies.clear();
     }
 
-    pub fn get_scaling_component(&self, id: &str) -> Option<&Box<dyn ScalingComponent>> {
+    pub fn get_scaling_component(&self, id: &str) -> Option<&Arc<dyn ScalingComponent>> {
         self.scaling_components.get(id)
     }
 
+    // The time until the next action to the scaling component is allowed by its max_actions. None if it is allowed now.
+    pub fn get_rate_limit(&self, id: &str, now: DateTime<Utc>) -> Option<(MaxActions, Duration)> {
+        let max_actions = self.max_actions.get(id)?;
+        let action_histories = self.action_histories.lock().ok()?;
+        let wait_time = action_histories.get(id)?.get_wait_time(max_actions, now)?;
+        Some((*max_actions, wait_time))
+    }
+
+    // Save an action to the scaling component for its max_actions
+    pub fn record_action(&self, id: &str, now: DateTime<Utc>) {
+        let Some(max_actions) = self.max_actions.get(id) else {
+            return;
+        };
+        if let Ok(mut action_histories) = self.action_histories.lock() {
+            action_histories
+                .entry(id.to_string())
+                .or_default()
+                .record(max_actions, now);
+        }
+    }
+
+    // Save the actions to the scaling components only if all of them are allowed by their max_actions. Returns whether they were saved.
+    pub fn try_record_actions(&self, ids: &[&str], now: DateTime<Utc>) -> bool {
+        let Ok(mut action_histories) = self.action_histories.lock() else {
+            return false;
+        };
+        let allowed =
+            ids.iter().all(
+                |id| match (self.max_actions.get(*id), action_histories.get(*id)) {
+                    (Some(max_actions), Some(action_history)) => {
+                        action_history.get_wait_time(max_actions, now).is_none()
+                    }
+                    _ => true,
+                },
+            );
+        if !allowed {
+            return false;
+        }
+        for id in ids {
+            if let Some(max_actions) = self.max_actions.get(*id) {
+                action_histories
+                    .entry(id.to_string())
+                    .or_default()
+                    .record(max_actions, now);
+            }
+        }
+        true
+    }
+
+    // Remove a saved action to the scaling component that was not done
+    pub fn remove_action(&self, id: &str, timestamp: DateTime<Utc>) {
+        if let Ok(mut action_histories) = self.action_histories.lock() {
+            if let Some(action_history) = action_histories.get_mut(id) {
+                action_history.remove(timestamp);
+            }
+        }
+    }
+
+    // What is needed to apply the params to the scaling component without the lock of the manager
+    pub fn get_applier(&self, id: &str) -> Result<ScalingComponentApplier> {
+        let Some(scaling_component) = self.scaling_components.get(id) else {
+            return Err(anyhow::anyhow!("Unknown scaling component kind"));
+        };
+        Ok(ScalingComponentApplier {
+            id: id.to_string(),
+            scaling_component: scaling_component.clone(),
+            apply_policy: self.apply_policies.get(id).copied(),
+            circuit_breakers: self.circuit_breakers.clone(),
+        })
+    }
+
+    // Apply the params to the scaling component with its apply policy (ScalingComponentApplier::apply)
+    // For testing. The scaling planners apply with the appliers so that the lock of the manager is not held.
+    #[allow(dead_code)]
     pub async fn apply_to(
         &self,
         id: &str,
         params: HashMap<String, serde_json::Value>,
         context: rquickjs::AsyncContext,
     ) -> Result<HashMap<String, serde_json::Value>> {
-        match self.scaling_components.get(id) {
-            Some(scaling_component) => scaling_component.apply(params, context).await,
-            None => Err(anyhow::anyhow!("Unknown scaling component kind")),
+        self.get_applier(id)?.apply(params, context).await
+    }
+
+    // The circuit breakers of the scaling components that have metadata.circuit_breaker (component_id => state)
+    pub fn get_circuit_breakers(&self) -> HashMap<String, serde_json::Value> {
+        let Ok(circuit_breakers) = self.circuit_breakers.lock() else {
+            return HashMap::new();
+        };
+        self.apply_policies
+            .iter()
+            .filter_map(|(id, apply_policy)| {
+                let circuit_breaker_policy = apply_policy.circuit_breaker.as_ref()?;
+                let circuit_breaker = circuit_breakers.get(id).cloned().unwrap_or_default();
+                Some((
+                    id.clone(),
+                    serde_json::json!({
+                        "state": circuit_breaker.state,
+                        "consecutive_failures": circuit_breaker.consecutive_failures,
+                        "failure_threshold": circuit_breaker_policy.failure_threshold,
+                        "opened_at": circuit_breaker.opened_at,
+                        "retry_at": circuit_breaker.get_retry_at(circuit_breaker_policy),
+                        "last_error": circuit_breaker.last_error,
+                    }),
+                ))
+            })
+            .collect()
+    }
+}
+
+/**
+ * ScalingComponentApplier
+ * A scaling component with its apply policy and the shared circuit breakers
+ * - It is taken from the ScalingComponentManager so that the lock of the manager is not held while applying.
+ *   The retries and the backoff can take long, and a waiting writer (e.g. a definitions reload) would block the other planners meanwhile.
+ */
+pub struct ScalingComponentApplier {
+    id: String,
+    scaling_component: Arc<dyn ScalingComponent>,
+    apply_policy: Option<ApplyPolicy>,
+    circuit_breakers: Arc<Mutex<HashMap<String, CircuitBreaker>>>,
+}
+
+impl ScalingComponentApplier {
+    pub async fn get_current_params(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        let mut current_params = self.scaling_component.get_current_params(params).await?;
+        current_params.insert(
+            "component_id".to_string(),
+            serde_json::Value::from(self.id.as_str()),
+        );
+        Ok(current_params)
+    }
+
+    /**
+     * Apply the params to the scaling component with its apply policy
+     * - timeout: each attempt fails if it takes longer than the timeout
+     * - retry: the failed attempts are retried with the exponential backoff and jitter
+     * - circuit_breaker: the applies are rejected without calling the scaling component while the circuit is open
+     */
+    pub async fn apply(
+        &self,
+        params: HashMap<String, serde_json::Value>,
+        context: rquickjs::AsyncContext,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        let id = self.id.as_str();
+        let scaling_component = &self.scaling_component;
+        let Some(apply_policy) = self.apply_policy.as_ref() else {
+            return scaling_component.apply(params, context).await;
+        };
+
+        if let Some(circuit_breaker_policy) = apply_policy.circuit_breaker.as_ref() {
+            let mut circuit_breakers = self
+                .circuit_breakers
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Failed to lock the circuit breakers"))?;
+            let circuit_breaker = circuit_breakers.entry(id.to_string()).or_default();
+            if !circuit_breaker.try_acquire(circuit_breaker_policy, Utc::now()) {
+                return Err(match circuit_breaker.get_retry_at(circuit_breaker_policy) {
+                    Some(retry_at) => anyhow::anyhow!(
+                        "The circuit breaker of {} is open until {}",
+                        id,
+                        retry_at.to_rfc3339()
+                    ),
+                    None => anyhow::anyhow!(
+                        "The circuit breaker of {} is half-open. A trial apply is in progress.",
+                        id
+                    ),
+                });
+            }
+        }
+
+        let max_attempts = apply_policy.get_max_attempts();
+        let mut attempt = 1;
+        let result = loop {
+            let apply = scaling_component.apply(params.clone(), context.clone());
+            let result = match apply_policy.timeout {
+                Some(timeout) => {
+                    let std_timeout = timeout.to_std().unwrap_or_default();
+                    match tokio::time::timeout(std_timeout, apply).await {
+                        Ok(result) => result,
+                        Err(_) => Err(anyhow::anyhow!(
+                            "Timed out after {}ms",
+                            timeout.num_milliseconds()
+                        )),
+                    }
+                }
+                None => apply.await,
+            };
+            let Err(error) = result else {
+                break result;
+            };
+            let Some(retry) = apply_policy.retry.filter(|_| attempt < max_attempts) else {
+                break Err(if max_attempts > 1 {
+                    anyhow::anyhow!("Failed after {} attempts. {}", attempt, error)
+                } else {
+                    error
+                });
+            };
+            let backoff = retry.get_backoff(attempt);
+            warn!(
+                "[ScalingComponentManager] Failed to apply {} (attempt {}/{}). Retry in {}ms. {}",
+                id,
+                attempt,
+                max_attempts,
+                backoff.num_milliseconds(),
+                error
+            );
+            tokio::time::sleep(backoff.to_std().unwrap_or_default()).await;
+            attempt += 1;
+        };
+
+        if let Some(circuit_breaker_policy) = apply_policy.circuit_breaker.as_ref() {
+            if let Ok(mut circuit_breakers) = self.circuit_breakers.lock() {
+                let circuit_breaker = circuit_breakers.entry(id.to_string()).or_default();
+                match result.as_ref() {
+                    Ok(_) => circuit_breaker.record_success(),
+                    Err(error) => circuit_breaker.record_failure(
+                        circuit_breaker_policy,
+                        error.to_string(),
+                        Utc::now(),
+                    ),
+                }
+            }
         }
+        result
     }
 }
 
+// Apply the params with the shared manager. The read lock is released before the apply.
+pub async fn apply_to_shared(
+    shared_scaling_component_manager: &SharedScalingComponentManager,
+    id: &str,
+    params: HashMap<String, serde_json::Value>,
+    context: rquickjs::AsyncContext,
+) -> Result<HashMap<String, serde_json::Value>> {
+    let applier = shared_scaling_component_manager
+        .read()
+        .await
+        .get_applier(id)?;
+    applier.apply(params, context).await
+}
+
 pub fn filter_current_state_in_expression(
     expression: &str,
     current_state_key_array: Vec<String>,
@@ -250,6 +526,157 @@ mod test {
             .unwrap()
     }
 
+    // A scaling component that fails the first "failures" applies and sleeps for "delay_ms" in each apply
+    struct FlakyComponent {
+        id: String,
+        failures: Mutex<u32>,
+        delay_ms: u64,
+        applies: Arc<Mutex<u32>>,
+    }
+
+    #[async_trait]
+    impl ScalingComponent for FlakyComponent {
+        async fn apply(
+            &self,
+            params: HashMap<String, serde_json::Value>,
+            _context: rquickjs::AsyncContext,
+        ) -> Result<HashMap<String, serde_json::Value>> {
+            *self.applies.lock().unwrap() += 1;
+            tokio::time::sleep(std::time::Duration::from_millis(self.delay_ms)).await;
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(anyhow::anyhow!("Service unavailable"));
+            }
+            Ok(params)
+        }
+        fn get_scaling_component_kind(&self) -> &str {
+            "flaky"
+        }
+        fn get_id(&self) -> &str {
+            &self.id
+        }
+    }
+
+    fn add_flaky_component(
+        scaling_component_manager: &mut ScalingComponentManager,
+        id: &str,
+        failures: u32,
+        delay_ms: u64,
+        metadata: serde_json::Value,
+    ) -> Arc<Mutex<u32>> {
+        let applies = Arc::new(Mutex::new(0));
+        scaling_component_manager.add_scaling_component(Box::new(FlakyComponent {
+            id: id.to_string(),
+            failures: Mutex::new(failures),
+            delay_ms,
+            applies: applies.clone(),
+        }));
+        let metadata = serde_json::from_value(metadata).unwrap();
+        let apply_policy = ApplyPolicy::from_metadata(&metadata).unwrap().unwrap();
+        scaling_component_manager
+            .apply_policies
+            .insert(id.to_string(), apply_policy);
+        applies
+    }
+
+    #[tokio::test]
+    async fn test_apply_to_with_apply_policy() {
+        let mut scaling_component_manager = ScalingComponentManager::new();
+        // Succeeds on the third attempt
+        let retried = add_flaky_component(
+            &mut scaling_component_manager,
+            "retried",
+            2,
+            0,
+            serde_json::json!({ "retry": { "max_attempts": 3, "backoff": "10ms" } }),
+        );
+        // Every attempt times out
+        let timed_out = add_flaky_component(
+            &mut scaling_component_manager,
+            "timed_out",
+            0,
+            1000,
+            serde_json::json!({ "timeout": "50ms", "retry": { "max_attempts": 2, "backoff": "10ms" } }),
+        );
+        // Opens the circuit after 2 failed applies
+        let broken = add_flaky_component(
+            &mut scaling_component_manager,
+            "broken",
+            u32::MAX,
+            0,
+            serde_json::json!({ "circuit_breaker": { "failure_threshold": 2, "open_duration": "1h" } }),
+        );
+
+        let result = scaling_component_manager
+            .apply_to("retried", HashMap::new(), get_rquickjs_context().await)
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(*retried.lock().unwrap(), 3);
+
+        let result = scaling_component_manager
+            .apply_to("timed_out", HashMap::new(), get_rquickjs_context().await)
+            .await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Failed after 2 attempts. Timed out after 50ms"
+        );
+        assert_eq!(*timed_out.lock().unwrap(), 2);
+
+        for _ in 0..3 {
+            let result = scaling_component_manager
+                .apply_to("broken", HashMap::new(), get_rquickjs_context().await)
+                .await;
+            assert!(result.is_err());
+        }
+        // The third apply is rejected by the open circuit
+        assert_eq!(*broken.lock().unwrap(), 2);
+        let circuit_breakers = scaling_component_manager.get_circuit_breakers();
+        assert_eq!(circuit_breakers.len(), 1);
+        assert_eq!(circuit_breakers["broken"]["state"], "OPEN");
+        assert_eq!(circuit_breakers["broken"]["consecutive_failures"], 2);
+        assert_eq!(
+            circuit_breakers["broken"]["last_error"],
+            "Service unavailable"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_to_shared_releases_the_lock() {
+        let shared_scaling_component_manager = ScalingComponentManager::new_shared();
+        add_flaky_component(
+            &mut *shared_scaling_component_manager.write().await,
+            "slow",
+            1,
+            200,
+            serde_json::json!({ "retry": { "max_attempts": 2, "backoff": "200ms" } }),
+        );
+
+        let apply = tokio::spawn({
+            let shared_scaling_component_manager = shared_scaling_component_manager.clone();
+            async move {
+                apply_to_shared(
+                    &shared_scaling_component_manager,
+                    "slow",
+                    HashMap::new(),
+                    get_rquickjs_context().await,
+                )
+                .await
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // A writer (e.g. a definitions reload) is not blocked by the retries of the apply
+        let write = tokio::time::timeout(
+            std::time::Duration::from_millis(100),
+            shared_scaling_component_manager.write(),
+        )
+        .await;
+        assert!(write.is_ok());
+        drop(write);
+        assert!(apply.await.unwrap().is_ok());
+    }
+
     #[test]
     fn test_filter_current_state_in_expression() {
         let expression = "$test1 + 2 + $test2";
@@ -3,11 +3,13 @@ use anyhow::{Ok, Result};
 use async_trait::async_trait;
 use data_layer::ScalingComponentDefinition;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Mutex};
 use tracing::info;
 
 pub struct WALoggerComponent {
     definition: ScalingComponentDefinition,
+    // The last applied params are the current params of the logger
+    last_params: Mutex<Option<HashMap<String, Value>>>,
 }
 
 impl WALoggerComponent {
@@ -16,7 +18,10 @@ impl WALoggerComponent {
 
     // Functions
     pub fn new(definition: ScalingComponentDefinition) -> Self {
-        WALoggerComponent { definition }
+        WALoggerComponent {
+            definition,
+            last_params: Mutex::new(None),
+        }
     }
 }
 
@@ -34,8 +39,21 @@ impl ScalingComponent for WALoggerComponent {
         _context: rquickjs::AsyncContext,
     ) -> Result<HashMap<String, Value>> {
         info!("[wa-logger] params: {:?}", params);
+        if let std::result::Result::Ok(mut last_params) = self.last_params.lock() {
+            *last_params = Some(params.clone());
+        }
         Ok(params)
     }
+    async fn get_current_params(
+        &self,
+        _params: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>> {
+        let last_params = self
+            .last_params
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Failed to get the last params"))?;
+        Ok(last_params.clone().unwrap_or_default())
+    }
 }
 
 #[cfg(test)]
@@ -73,4 +91,26 @@ mod test {
             .await;
         assert!(scaling_component.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_get_current_params() {
+        let scaling_component = WALoggerComponent::new(ScalingComponentDefinition {
+            id: String::from("scaling_id"),
+            component_kind: String::from("wa-logger"),
+            ..Default::default()
+        });
+        let params = HashMap::from([("replicas".to_string(), serde_json::json!(3))]);
+        assert!(scaling_component
+            .get_current_params(&params)
+            .await
+            .unwrap()
+            .is_empty());
+        let _ = scaling_component
+            .apply(params.clone(), get_rquickjs_context().await)
+            .await;
+        assert_eq!(
+            scaling_component.get_current_params(&params).await.unwrap(),
+            params
+        );
+    }
 }
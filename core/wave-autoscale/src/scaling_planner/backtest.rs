@@ -0,0 +1,817 @@
+use super::js_functions::{get_metric_value, GetArgs, GetValueCache};
+use super::{
+    cool_down::ComponentCoolDowns,
+    evaluate_plan_item,
+    fallback::{is_fallback, sort_plan_items, FallbackTracker},
+    get_plan_interval, get_plan_item_cool_down, get_plan_timezone, is_all_matching_mode,
+    js_sandbox::{JsSandbox, JsSandboxLimits},
+    plan_item_state::{parse_for_duration, PlanItemState, PlanItemStateTracker},
+    rate_limit::PlanRateLimit,
+    run_plan_item_stages, set_plan_variables,
+    stabilization::Stabilizer,
+    PlanItemEvaluation, PlanItemStages, Stage, StageOutcome,
+};
+use crate::scaling_component::ScalingComponentManager;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use data_layer::{
+    data_layer::DataLayer,
+    types::{max_actions::MaxActions, planner_request::BacktestRequest},
+    ScalingPlanDefinition,
+};
+use rquickjs::async_with;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::sync::RwLock;
+use tracing::{debug, error};
+
+// The metrics data before "from" is also loaded so that get() with period_sec has the data at the beginning.
+// It is the minimum. A longer period_sec in the plan extends it (see get_lookback_sec).
+const BACKTEST_LOOKBACK_SEC: u64 = 60 * 60;
+// The metrics data is loaded window by window so that a long time range is not loaded into memory at once
+const BACKTEST_WINDOW_SEC: u64 = 60 * 60;
+// To prevent a request from running too long (e.g. a month with 1 second interval)
+const MAX_BACKTEST_TICKS: u64 = 100_000;
+
+/**
+ * BacktestTimelineItem
+ * A plan item that would have been fired at the timestamp
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestTimelineItem {
+    // Unix timestamp in milliseconds
+    pub timestamp: u64,
+    pub plan_item_id: String,
+    pub expression_values: Vec<HashMap<String, Option<f64>>>,
+    pub scaling_components: Vec<HashMap<String, Value>>,
+}
+
+/**
+Replay the stored metrics data through a scaling plan with a virtual clock
+- The virtual clock moves from "from" to "to" by the interval
+- get() in the expressions uses the metrics data in the database instead of the in-memory metrics data
+- The metrics data is loaded for a window (BACKTEST_WINDOW_SEC with the lookback) at a time as the virtual clock moves
+- The lookback is the longest period_sec in the plan. The backtest fails if get() needs a longer period_sec (e.g. from a variable)
+- The execution mode, the "for" durations, the fallback plan items and the stages of the scaling planner
+  (the maintenance windows in the database, the cool downs, the stabilization windows and max_actions of the plan) are simulated with the virtual clock
+- max_actions of the scaling components is not simulated because the request has only the plan definition
+- Nothing is applied to the scaling components
+- The backtest fails if an evaluation hits the limits of the sandbox (js_limits)
+ */
+pub async fn backtest(
+    data_layer: &DataLayer,
+    request: BacktestRequest,
+    js_limits: JsSandboxLimits,
+) -> Result<Vec<BacktestTimelineItem>> {
+    let BacktestRequest {
+        definition,
+        from,
+        to,
+        interval,
+    } = request;
+    if from >= to {
+        return Err(anyhow::anyhow!("\"from\" should be before \"to\""));
+    }
+    let interval = interval.unwrap_or(get_plan_interval(&definition) as u64);
+    if interval == 0 {
+        return Err(anyhow::anyhow!("\"interval\" should be greater than 0"));
+    }
+    if (to - from) / interval > MAX_BACKTEST_TICKS {
+        return Err(anyhow::anyhow!(
+            "Too many ticks to backtest. The time range divided by the interval should be less than {}",
+            MAX_BACKTEST_TICKS
+        ));
+    }
+
+    let lookback_sec = get_lookback_sec(&definition);
+    // The longest period_sec of get() that was beyond the lookback (0 if none)
+    let exceeded_period_sec = Arc::new(AtomicU64::new(0));
+
+    // The metrics data of the current window and the end of the window
+    let mut metrics_data_map = Arc::new(HashMap::new());
+    let mut loaded_to: Option<u64> = None;
+    let virtual_now = Arc::new(AtomicU64::new(from));
+
+    let sandbox = JsSandbox::new(js_limits).await?;
+
+    let all_matching = is_all_matching_mode(&definition);
+    let plan_timezone = get_plan_timezone(&definition);
+    let plan_rate_limit = PlanRateLimit {
+        max_actions: MaxActions::from_metadata(&definition.metadata)?,
+        ..Default::default()
+    };
+    let plan_items = sort_plan_items(&definition.plans);
+    let maintenance_windows = data_layer.get_enabled_maintenance_windows().await?;
+
+    let stabilizer = RwLock::new(Stabilizer::new());
+    let component_cool_downs = RwLock::new(ComponentCoolDowns::new());
+    // No scaling component is loaded, so only max_actions of the plan is applied
+    let scaling_component_manager = ScalingComponentManager::new_shared();
+    let stages = PlanItemStages {
+        plan_id: &definition.id,
+        plan_behavior: definition.behavior.as_ref(),
+        maintenance_windows: &maintenance_windows,
+        component_cool_downs: &component_cool_downs,
+        stabilizer: &stabilizer,
+        plan_rate_limit: &plan_rate_limit,
+        scaling_component_manager: &scaling_component_manager,
+    };
+    let mut plan_item_states = PlanItemStateTracker::new();
+    let mut timeline: Vec<BacktestTimelineItem> = Vec::new();
+    let mut fallback_tracker = FallbackTracker::new();
+    // plan_item_id => the last fired occurrence of the cron expression
+    let mut cron_last_fired: HashMap<String, DateTime<Utc>> = HashMap::new();
+    let mut now = from;
+    while now <= to {
+        virtual_now.store(now, Ordering::SeqCst);
+        // Load the metrics data of the next window when the virtual clock passes the loaded one
+        if loaded_to.map_or(true, |loaded_to| now > loaded_to) {
+            let window_to = (now + BACKTEST_WINDOW_SEC * 1000).min(to);
+            metrics_data_map = Arc::new(
+                data_layer
+                    .get_metrics_data_map_from_db(
+                        to_system_time(now) - Duration::from_secs(lookback_sec),
+                        to_system_time(window_to),
+                    )
+                    .await?,
+            );
+            loaded_to = Some(window_to);
+        }
+        // A fresh context for every tick like the scaling planner so that the globals (e.g. "var $x") do not leak between ticks
+        let context = sandbox.new_context().await?;
+
+        // Set the get function that reads the loaded metrics data at the virtual clock
+        // The values are cached in a tick of the virtual clock
+        {
+            let metrics_data_map = metrics_data_map.clone();
+            let virtual_now = virtual_now.clone();
+            let exceeded_period_sec = exceeded_period_sec.clone();
+            let get_value_cache = GetValueCache::new();
+            async_with!(context => |ctx| {
+                let _ = ctx.globals().set(
+                    "get",
+                    rquickjs::prelude::Func::new("get", move |args: rquickjs::Object<'_>| {
+                        let args = GetArgs::from_js_object(&args)?;
+                        // The metrics data before the lookback is not loaded, so the value would be computed from a part of the period
+                        if args.period_sec > lookback_sec {
+                            exceeded_period_sec.fetch_max(args.period_sec, Ordering::SeqCst);
+                            return Err(rquickjs::Error::new_loading("period_sec is longer than the lookback of the backtest"));
+                        }
+                        let now = to_system_time(virtual_now.load(Ordering::SeqCst));
+                        get_value_cache.get_or_compute(&args, || get_metric_value(&metrics_data_map, &args, now))
+                    }),
+                );
+            })
+            .await;
+        }
+
+        sandbox.start_evaluation();
+        let evaluated_variables = set_plan_variables(&context, &definition.variables).await;
+        if let Some(limit_error) =
+            sandbox.finish_evaluation(evaluated_variables.fail_message.as_deref())
+        {
+            return Err(anyhow::anyhow!(
+                "Failed to evaluate the variables at {}. {}",
+                now,
+                limit_error
+            ));
+        }
+
+        let mut matched = false;
+        let mut evaluated_plan_item_ids = HashSet::new();
+        for plan_item in plan_items.iter() {
+            if is_fallback(plan_item) {
+                let cool_down = get_plan_item_cool_down(plan_item, &definition.metadata);
+                if matched || !fallback_tracker.is_due(plan_item, cool_down, to_datetime(now)) {
+                    continue;
+                }
+            }
+            sandbox.start_evaluation();
+            let evaluation = evaluate_plan_item(
+                plan_item,
+                &context,
+                to_datetime(now),
+                interval as i64,
+                plan_timezone.as_deref(),
+                cron_last_fired.get(&plan_item.id).copied(),
+            )
+            .await;
+            evaluated_plan_item_ids.insert(plan_item.id.as_str());
+            let (expression_values, cron_occurrence) = match evaluation {
+                PlanItemEvaluation::Matched(expression_values, cron_occurrence) => {
+                    (expression_values, cron_occurrence)
+                }
+                PlanItemEvaluation::Failed(fail_message) => {
+                    if let Some(limit_error) = sandbox.finish_evaluation(Some(&fail_message)) {
+                        return Err(anyhow::anyhow!(
+                            "Failed to evaluate the plan item {} at {}. {}",
+                            plan_item.id,
+                            now,
+                            limit_error
+                        ));
+                    }
+                    debug!("[backtest] {} - {}", plan_item.id, fail_message);
+                    plan_item_states.reset(&plan_item.id);
+                    continue;
+                }
+                PlanItemEvaluation::NotMatched => {
+                    plan_item_states.reset(&plan_item.id);
+                    continue;
+                }
+            };
+            if !is_fallback(plan_item) {
+                matched = true;
+            }
+
+            // The plan item is fired only after the expression has been true for the "for" duration
+            if let Some(for_duration) = plan_item.for_duration.as_ref() {
+                // The plan item with an invalid "for" duration is skipped like in the scaling planner
+                let for_duration = match parse_for_duration(for_duration) {
+                    Ok(for_duration) => for_duration,
+                    Err(error) => {
+                        error!("[backtest] {} - {}", plan_item.id, error);
+                        continue;
+                    }
+                };
+                let state =
+                    plan_item_states.observe_true(&plan_item.id, for_duration, to_datetime(now));
+                if let PlanItemState::Pending { .. } = state {
+                    continue;
+                }
+            }
+            if is_fallback(plan_item) {
+                fallback_tracker.record_fired(&plan_item.id, to_datetime(now));
+            }
+
+            let (stage_outcome, stage_logs) = run_plan_item_stages(
+                plan_item,
+                &stages,
+                &sandbox,
+                context.clone(),
+                to_datetime(now),
+            )
+            .await;
+            for stage_log in stage_logs.iter() {
+                debug!("[backtest] {} - {}", plan_item.id, stage_log.message);
+            }
+            let scaling_components = match stage_outcome {
+                StageOutcome::Apply(scaling_components) => scaling_components,
+                // Nothing is fired if every scaling component is held back by a stage
+                StageOutcome::Held(stage) => {
+                    // The cron occurrence is consumed in a maintenance window like in the scaling planner
+                    if let (Stage::MaintenanceWindow, Some(cron_occurrence)) =
+                        (stage, cron_occurrence)
+                    {
+                        cron_last_fired.insert(plan_item.id.clone(), cron_occurrence);
+                    }
+                    if all_matching {
+                        continue;
+                    }
+                    break;
+                }
+                StageOutcome::Failed(limit_error) => {
+                    return Err(anyhow::anyhow!(
+                        "Failed to evaluate the params of the plan item {} at {}. {}",
+                        plan_item.id,
+                        now,
+                        limit_error
+                    ));
+                }
+            };
+
+            {
+                let mut stabilizer = stabilizer.write().await;
+                for params in scaling_components.iter() {
+                    stabilizer.record_applied(params);
+                }
+            }
+            let cool_down = get_plan_item_cool_down(plan_item, &definition.metadata);
+            {
+                let mut component_cool_downs = component_cool_downs.write().await;
+                for params in scaling_components.iter() {
+                    if let Some(component_id) = params.get("component_id").and_then(Value::as_str) {
+                        component_cool_downs.record_applied(
+                            component_id,
+                            &plan_item.id,
+                            cool_down,
+                            to_datetime(now),
+                        );
+                    }
+                }
+            }
+
+            if let Some(cron_occurrence) = cron_occurrence {
+                cron_last_fired.insert(plan_item.id.clone(), cron_occurrence);
+            }
+            timeline.push(BacktestTimelineItem {
+                timestamp: now,
+                plan_item_id: plan_item.id.clone(),
+                expression_values,
+                scaling_components,
+            });
+
+            plan_rate_limit.record(to_datetime(now)).await;
+            if all_matching {
+                continue;
+            }
+            // Only one plan item is fired per interval
+            break;
+        }
+        let period_sec = exceeded_period_sec.load(Ordering::SeqCst);
+        if period_sec > 0 {
+            return Err(anyhow::anyhow!(
+                "period_sec of get() ({}) is longer than the metrics data loaded for the backtest ({} seconds). Use a number for period_sec in the plan so that the backtest loads enough data",
+                period_sec,
+                lookback_sec
+            ));
+        }
+        fallback_tracker.end_tick(matched);
+        // The "for" durations of the plan items that were not evaluated start again like in the scaling planner
+        plan_item_states.reset_unevaluated(&evaluated_plan_item_ids);
+        now += interval;
+    }
+    Ok(timeline)
+}
+
+// The seconds of the metrics data to load before a window. It is the longest period_sec of get() in the variables and the plan items
+// (e.g. get({ metric_id: 'cpu', stats: 'forecast_holt_winters', period_sec: 7200, season_period_sec: 3600 })).
+fn get_lookback_sec(definition: &ScalingPlanDefinition) -> u64 {
+    let re_period_sec = regex::Regex::new(r"\bperiod_sec\s*:\s*(\d+)").unwrap();
+    let sources = [
+        serde_json::to_string(&definition.variables).unwrap_or_default(),
+        serde_json::to_string(&definition.plans).unwrap_or_default(),
+    ];
+    sources
+        .iter()
+        .flat_map(|source| re_period_sec.captures_iter(source))
+        .filter_map(|captures| captures[1].parse::<u64>().ok())
+        .fold(BACKTEST_LOOKBACK_SEC, u64::max)
+}
+
+fn to_system_time(timestamp_ms: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_millis(timestamp_ms)
+}
+
+fn to_datetime(timestamp_ms: u64) -> DateTime<Utc> {
+    let naive = NaiveDateTime::from_timestamp_millis(timestamp_ms as i64).unwrap_or_default();
+    DateTime::<Utc>::from_utc(naive, Utc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_layer::types::{
+        cron_schedule::CatchUpPolicy, plan_item_definition::PlanItemDefinition,
+    };
+    use serde_json::json;
+
+    const METRIC_ID: &str = "backtest_metric";
+
+    fn now_ms() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+
+    async fn get_data_layer() -> DataLayer {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        data_layer
+    }
+
+    // Save a cpu metric of 10 and return when it was saved
+    async fn add_cpu_metric(data_layer: &DataLayer, metric_id: &str) -> u64 {
+        let _ = data_layer
+            .add_metrics_data_into_db(
+                "vector",
+                metric_id,
+                json!([{"name": "cpu", "value": 10.0}]).to_string().as_str(),
+            )
+            .await;
+        now_ms()
+    }
+
+    // A plan item that scales component1 to 1 replica
+    fn get_plan_item(id: &str, expression: Option<&str>) -> PlanItemDefinition {
+        PlanItemDefinition {
+            id: id.to_string(),
+            expression: expression.map(str::to_string),
+            priority: 1,
+            scaling_components: vec![json!({"component_id": "component1", "replicas": 1})],
+            ..Default::default()
+        }
+    }
+
+    fn get_definition(plans: Vec<PlanItemDefinition>) -> ScalingPlanDefinition {
+        ScalingPlanDefinition {
+            id: "backtest_plan".to_string(),
+            plans,
+            ..Default::default()
+        }
+    }
+
+    async fn run_backtest(
+        data_layer: &DataLayer,
+        definition: ScalingPlanDefinition,
+        from: u64,
+        to: u64,
+        interval: u64,
+    ) -> Result<Vec<BacktestTimelineItem>> {
+        backtest(
+            data_layer,
+            BacktestRequest {
+                definition,
+                from,
+                to,
+                interval: Some(interval),
+            },
+            JsSandboxLimits::default(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_backtest() {
+        let data_layer = get_data_layer().await;
+        let inserted_at = add_cpu_metric(&data_layer, METRIC_ID).await;
+        let expression = format!("get({{ metric_id: '{}', name: 'cpu' }}) > 5", METRIC_ID);
+        let definition = ScalingPlanDefinition {
+            variables: HashMap::from([("replicas".to_string(), json!(2))]),
+            metadata: HashMap::from([("cool_down".to_string(), json!(2))]),
+            ..get_definition(vec![PlanItemDefinition {
+                scaling_components: vec![json!({
+                    "component_id": "component1",
+                    "replicas": "$replicas + 1",
+                })],
+                ..get_plan_item("scale_out", Some(&expression))
+            }])
+        };
+
+        // The virtual clock: -3s, -2s, -1s (no data yet), 0s (fired), +1s (cool down), +2s (fired)
+        let timeline = run_backtest(
+            &data_layer,
+            definition,
+            inserted_at - 3000,
+            inserted_at + 2000,
+            1000,
+        )
+        .await
+        .unwrap();
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[0].timestamp, inserted_at);
+        assert_eq!(timeline[1].timestamp, inserted_at + 2000);
+        assert_eq!(timeline[0].plan_item_id, "scale_out");
+        assert_eq!(
+            timeline[0].scaling_components[0].get("replicas"),
+            Some(&json!(3.0))
+        );
+        assert!(!timeline[0].expression_values.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backtest_windows() {
+        let data_layer = get_data_layer().await;
+        let metric_id = "backtest_windows_metric";
+        let inserted_at = add_cpu_metric(&data_layer, metric_id).await;
+        let expression = format!("get({{ metric_id: '{}', name: 'cpu' }}) > 5", metric_id);
+        let definition = get_definition(vec![get_plan_item("scale_out", Some(&expression))]);
+
+        // The virtual clock moves by a half window, so the metrics data at the last tick is in the third window
+        let window_ms = BACKTEST_WINDOW_SEC * 1000;
+        let timeline = run_backtest(
+            &data_layer,
+            definition,
+            inserted_at - 2 * window_ms,
+            inserted_at,
+            window_ms / 2,
+        )
+        .await
+        .unwrap();
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline[0].timestamp, inserted_at);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_lookback() {
+        let data_layer = get_data_layer().await;
+        let inserted_at = add_cpu_metric(&data_layer, METRIC_ID).await;
+        let get_plan =
+            |expression: &str, variables: HashMap<String, Value>| ScalingPlanDefinition {
+                variables,
+                ..get_definition(vec![get_plan_item("scale_out", Some(expression))])
+            };
+        // The metrics data is 1.5 hours before the tick, which is beyond the default lookback
+        let at = inserted_at + 90 * 60 * 1000;
+
+        // The lookback is extended to period_sec
+        let definition = get_plan(
+            &format!(
+                "get({{ metric_id: '{}', name: 'cpu', stats: 'max', period_sec: 7200 }}) > 5",
+                METRIC_ID
+            ),
+            HashMap::new(),
+        );
+        assert_eq!(get_lookback_sec(&definition), 7200);
+        let timeline = run_backtest(&data_layer, definition, at, at + 1000, 1000)
+            .await
+            .unwrap();
+        assert_eq!(timeline.len(), 2);
+
+        // period_sec from a variable is not known before the evaluation
+        let definition = get_plan(
+            &format!(
+                "get({{ metric_id: '{}', name: 'cpu', stats: 'max', period_sec: $period_sec }}) > 5",
+                METRIC_ID
+            ),
+            HashMap::from([("period_sec".to_string(), json!(7200))]),
+        );
+        assert_eq!(get_lookback_sec(&definition), BACKTEST_LOOKBACK_SEC);
+        let result = run_backtest(&data_layer, definition, at, at + 1000, 1000).await;
+        assert!(result.unwrap_err().to_string().contains("period_sec"));
+    }
+
+    #[tokio::test]
+    async fn test_backtest_for_duration() {
+        let data_layer = get_data_layer().await;
+        let definition = get_definition(vec![PlanItemDefinition {
+            for_duration: Some("2s".to_string()),
+            ..get_plan_item("always", Some("true"))
+        }]);
+
+        // The virtual clock: 0s, 1s (pending), 2s, 3s, 4s, 5s (fired)
+        let from = now_ms() - 10_000;
+        let timeline = run_backtest(&data_layer, definition, from, from + 5000, 1000)
+            .await
+            .unwrap();
+        assert_eq!(timeline.len(), 4);
+        assert_eq!(timeline[0].timestamp, from + 2000);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_fresh_context() {
+        let data_layer = get_data_layer().await;
+        // The global is set only in the context of the tick
+        let definition = get_definition(vec![get_plan_item(
+            "first_tick",
+            Some("var fired = typeof ticked === 'undefined'; var ticked = true; fired"),
+        )]);
+
+        // The virtual clock: 0s, 1s, 2s. The global of a tick does not leak into the next tick.
+        let from = now_ms() - 10_000;
+        let timeline = run_backtest(&data_layer, definition, from, from + 2000, 1000)
+            .await
+            .unwrap();
+        assert_eq!(timeline.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_invalid_for_duration() {
+        let data_layer = get_data_layer().await;
+        let definition = get_definition(vec![
+            PlanItemDefinition {
+                priority: 2,
+                for_duration: Some("a while".to_string()),
+                ..get_plan_item("invalid", Some("true"))
+            },
+            get_plan_item("always", Some("true")),
+        ]);
+
+        // The plan item with the invalid "for" duration is skipped in every tick
+        let from = now_ms() - 10_000;
+        let timeline = run_backtest(&data_layer, definition, from, from + 2000, 1000)
+            .await
+            .unwrap();
+        assert_eq!(timeline.len(), 3);
+        assert!(timeline.iter().all(|item| item.plan_item_id == "always"));
+    }
+
+    #[tokio::test]
+    async fn test_backtest_all_matching() {
+        let data_layer = get_data_layer().await;
+        let get_plan_item = |id: &str, priority: i16, cool_down: u64| PlanItemDefinition {
+            cool_down: Some(cool_down),
+            priority,
+            scaling_components: vec![json!({"component_id": id, "replicas": 1})],
+            ..get_plan_item(id, Some("true"))
+        };
+        let definition = ScalingPlanDefinition {
+            metadata: HashMap::from([("execution_mode".to_string(), json!("all_matching"))]),
+            ..get_definition(vec![get_plan_item("web", 2, 2), get_plan_item("waf", 1, 3)])
+        };
+
+        // The virtual clock: 0s (web, waf), 1s, 2s (web), 3s (waf), 4s (web)
+        let from = now_ms() - 10_000;
+        let timeline = run_backtest(&data_layer, definition, from, from + 4000, 1000)
+            .await
+            .unwrap();
+        let fired = timeline
+            .iter()
+            .map(|item| ((item.timestamp - from) / 1000, item.plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fired,
+            vec![(0, "web"), (0, "waf"), (2, "web"), (3, "waf"), (4, "web")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backtest_fallback() {
+        let data_layer = get_data_layer().await;
+        let get_plan_item =
+            |id: &str, expression: Option<&str>, fallback: bool| PlanItemDefinition {
+                cool_down: Some(2),
+                scaling_components: vec![json!({"component_id": id, "replicas": 1})],
+                fallback: Some(fallback),
+                fallback_after: Some(2),
+                ..get_plan_item(id, expression)
+            };
+        let definition = get_definition(vec![
+            get_plan_item("baseline", None, true),
+            get_plan_item("never", Some("false"), false),
+        ]);
+
+        // The virtual clock: 1s (2 ticks with no match), 3s (the cool down of 2s), 5s
+        let from = now_ms() - 10_000;
+        let timeline = run_backtest(&data_layer, definition, from, from + 5000, 1000)
+            .await
+            .unwrap();
+        let fired = timeline
+            .iter()
+            .map(|item| ((item.timestamp - from) / 1000, item.plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fired,
+            vec![(1, "baseline"), (3, "baseline"), (5, "baseline")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backtest_cron_timezone() {
+        let data_layer = get_data_layer().await;
+        let definition = ScalingPlanDefinition {
+            metadata: HashMap::from([("timezone".to_string(), json!("Asia/Seoul"))]),
+            ..get_definition(vec![PlanItemDefinition {
+                cron_expression: Some("0 9 * * *".to_string()),
+                ..get_plan_item("morning", None)
+            }])
+        };
+
+        // 09:00 in Seoul is 00:00 in UTC. The cron plan item is fired within the interval before the time.
+        let midnight_utc = 1_710_201_600_000; // 2024-03-12T00:00:00Z
+        let timeline = run_backtest(
+            &data_layer,
+            definition,
+            midnight_utc - 3000,
+            midnight_utc + 3000,
+            1000,
+        )
+        .await
+        .unwrap();
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline[0].timestamp, midnight_utc - 1000);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_cron_catch_up() {
+        let data_layer = get_data_layer().await;
+        let get_definition = |catch_up: CatchUpPolicy| ScalingPlanDefinition {
+            // The cool down (1.5 hours) blocks the occurrence at 01:00
+            metadata: HashMap::from([("cool_down".to_string(), json!(5400))]),
+            ..get_definition(vec![PlanItemDefinition {
+                cron_expression: Some("0 * * * *".to_string()),
+                catch_up: Some(catch_up),
+                ..get_plan_item("hourly", None)
+            }])
+        };
+        let midnight_utc = 1_710_201_600_000; // 2024-03-12T00:00:00Z
+        let minute = 60_000;
+        let backtest_catch_up = |catch_up: CatchUpPolicy| {
+            run_backtest(
+                &data_layer,
+                get_definition(catch_up),
+                midnight_utc - 2 * minute,
+                midnight_utc + 95 * minute,
+                minute,
+            )
+        };
+
+        let timeline = backtest_catch_up(CatchUpPolicy::Skip).await.unwrap();
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline[0].timestamp, midnight_utc - minute);
+
+        // The missed occurrence at 01:00 is fired when the cool down ends (01:29)
+        let timeline = backtest_catch_up(CatchUpPolicy::Latest).await.unwrap();
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[1].timestamp, midnight_utc + 89 * minute);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_max_actions() {
+        let data_layer = get_data_layer().await;
+        let definition = ScalingPlanDefinition {
+            metadata: HashMap::from([("max_actions".to_string(), json!("2 per 5s"))]),
+            ..get_definition(vec![get_plan_item("always", Some("true"))])
+        };
+
+        // The virtual clock: 0s, 1s (fired), 2s, 3s, 4s (rate limited), 5s, 6s (fired)
+        let from = now_ms() - 10_000;
+        let timeline = run_backtest(&data_layer, definition, from, from + 6000, 1000)
+            .await
+            .unwrap();
+        let timestamps = timeline
+            .iter()
+            .map(|item| item.timestamp - from)
+            .collect::<Vec<u64>>();
+        assert_eq!(timestamps, vec![0, 1000, 5000, 6000]);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_maintenance_window() {
+        let data_layer = get_data_layer().await;
+        // A window that opens every minute for 2 minutes is always open
+        data_layer
+            .add_maintenance_windows(vec![(
+                serde_json::from_value(json!({
+                    "id": "always",
+                    "cron_expression": "* * * * *",
+                    "duration": "2m",
+                    "component_ids": ["component1"],
+                }))
+                .unwrap(),
+                "".to_string(),
+            )])
+            .await
+            .unwrap();
+        let definition = get_definition(vec![PlanItemDefinition {
+            scaling_components: vec![
+                json!({"component_id": "component1", "replicas": 1}),
+                json!({"component_id": "component2", "replicas": 1}),
+            ],
+            ..get_plan_item("always", Some("true"))
+        }]);
+
+        // The scaling component in the maintenance window is never fired
+        let from = now_ms() - 10_000;
+        let timeline = run_backtest(&data_layer, definition, from, from + 2000, 1000)
+            .await
+            .unwrap();
+        assert_eq!(timeline.len(), 3);
+        for item in timeline.iter() {
+            assert_eq!(item.scaling_components.len(), 1);
+            assert_eq!(
+                item.scaling_components[0].get("component_id"),
+                Some(&json!("component2"))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backtest_invalid_range() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        let result = backtest(
+            &data_layer,
+            BacktestRequest {
+                definition: ScalingPlanDefinition::default(),
+                from: 2000,
+                to: 1000,
+                interval: None,
+            },
+            JsSandboxLimits::default(),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backtest_time_limit() {
+        let data_layer = get_data_layer().await;
+        let definition =
+            get_definition(vec![get_plan_item("infinite_loop", Some("while(true) {}"))]);
+        let result = backtest(
+            &data_layer,
+            BacktestRequest {
+                definition,
+                from: 1000,
+                to: 2000,
+                interval: Some(1000),
+            },
+            JsSandboxLimits {
+                time_limit_ms: 100,
+                memory_limit_mb: 0,
+            },
+        )
+        .await;
+        let error = result.err().unwrap().to_string();
+        assert!(error.contains("infinite_loop"));
+        assert!(error.contains("CPU time limit (100 ms)"));
+    }
+}
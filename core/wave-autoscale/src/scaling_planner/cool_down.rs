@@ -0,0 +1,92 @@
+use chrono::{DateTime, Duration, Utc};
+use data_layer::types::planner_state::ComponentAction;
+use std::collections::HashMap;
+
+/**
+ * ComponentCoolDowns
+ * Tracks the last action and the cool down of each scaling component
+ * so that a scaling component in cool down does not block the other scaling components.
+ */
+#[derive(Debug, Default)]
+pub struct ComponentCoolDowns {
+    // component_id => the last action
+    actions: HashMap<String, ComponentAction>,
+}
+
+impl ComponentCoolDowns {
+    pub fn new() -> Self {
+        ComponentCoolDowns::default()
+    }
+
+    // Restore the last actions (e.g. from the PlannerState in the DataLayer)
+    pub fn from_actions(actions: HashMap<String, ComponentAction>) -> Self {
+        ComponentCoolDowns { actions }
+    }
+
+    pub fn get_actions(&self) -> &HashMap<String, ComponentAction> {
+        &self.actions
+    }
+
+    // Save the action that was applied to the scaling component. cool_down is in seconds.
+    pub fn record_applied(
+        &mut self,
+        component_id: &str,
+        plan_item_id: &str,
+        cool_down: u64,
+        now: DateTime<Utc>,
+    ) {
+        self.actions.insert(
+            component_id.to_string(),
+            ComponentAction {
+                plan_item_id: plan_item_id.to_string(),
+                applied_at: now,
+                cool_down_until: now + Duration::seconds(cool_down as i64),
+            },
+        );
+    }
+
+    // The time left until the cool down of the scaling component ends. None if it is not cooling down.
+    pub fn get_time_left(&self, component_id: &str, now: DateTime<Utc>) -> Option<Duration> {
+        let action = self.actions.get(component_id)?;
+        let time_left = action.cool_down_until - now;
+        if time_left.num_milliseconds() > 0 {
+            Some(time_left)
+        } else {
+            None
+        }
+    }
+
+    // For testing
+    #[allow(dead_code)]
+    pub fn get_last_action(&self, component_id: &str) -> Option<&ComponentAction> {
+        self.actions.get(component_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_cool_downs() {
+        let mut cool_downs = ComponentCoolDowns::new();
+        let now = Utc::now();
+        cool_downs.record_applied("component_a", "plan_1", 60, now);
+
+        // Only the scaling component that was applied is cooling down
+        let time_left = cool_downs
+            .get_time_left("component_a", now + Duration::seconds(10))
+            .unwrap();
+        assert_eq!(time_left.num_seconds(), 50);
+        assert!(cool_downs
+            .get_time_left("component_b", now + Duration::seconds(10))
+            .is_none());
+        assert!(cool_downs
+            .get_time_left("component_a", now + Duration::seconds(60))
+            .is_none());
+
+        let action = cool_downs.get_last_action("component_a").unwrap();
+        assert_eq!(action.plan_item_id, "plan_1");
+        assert_eq!(action.applied_at, now);
+    }
+}
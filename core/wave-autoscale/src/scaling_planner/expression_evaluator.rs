@@ -0,0 +1,205 @@
+use super::{
+    expression_get_value,
+    js_functions::{get_in_js_with_cache, GetValueCache},
+    js_sandbox::{JsSandbox, JsSandboxLimits},
+    set_plan_variables,
+};
+use anyhow::Result;
+use data_layer::types::planner_request::ExpressionEvaluateRequest;
+use rquickjs::{async_with, CatchResultExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use tracing::debug;
+
+/**
+ * ExpressionEvaluateResult
+ * - result: the value of the expression (None if it failed)
+ * - variables: the evaluated variables ("$key" => value)
+ * - expression_values: the values of get() and the variables in the expression (the same as the plan logs)
+ * - error: the error of the variables or the expression
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpressionEvaluateResult {
+    pub result: Option<Value>,
+    pub variables: BTreeMap<String, Value>,
+    pub expression_values: Vec<HashMap<String, Option<f64>>>,
+    pub error: Option<String>,
+}
+
+/**
+Evaluate an expression with the current in-memory metrics data (METRICS_DATA)
+- get() is the same function as the one in the scaling planners
+- The variables are evaluated before the expression
+- The evaluations have the same limits as the scaling planners (js_limits)
+ */
+pub async fn evaluate_expression(
+    request: ExpressionEvaluateRequest,
+    js_limits: JsSandboxLimits,
+) -> Result<ExpressionEvaluateResult> {
+    let ExpressionEvaluateRequest {
+        expression,
+        variables,
+    } = request;
+    if expression.trim().is_empty() {
+        return Err(anyhow::anyhow!("\"expression\" is empty"));
+    }
+
+    let sandbox = JsSandbox::new(js_limits).await?;
+    let context = sandbox.new_context().await?;
+    async_with!(context => |ctx| {
+        let _ = ctx.globals().set(
+            "get",
+            rquickjs::prelude::Func::new("get", get_in_js_with_cache(GetValueCache::new())),
+        );
+    })
+    .await;
+
+    // Evaluate "variables" first because the expression can use them
+    sandbox.start_evaluation();
+    let evaluated_variables = set_plan_variables(&context, &variables).await;
+    let limit_error = sandbox.finish_evaluation(evaluated_variables.fail_message.as_deref());
+    if let Some(fail_message) = limit_error.or(evaluated_variables.fail_message) {
+        return Ok(ExpressionEvaluateResult {
+            result: None,
+            variables: evaluated_variables.values,
+            expression_values: Vec::new(),
+            error: Some(format!(
+                "Failed to evaluate the variables. {}",
+                fail_message
+            )),
+        });
+    }
+
+    sandbox.start_evaluation();
+    let source = expression.clone();
+    let evaluation = async_with!(context => |ctx| {
+        let value = ctx
+            .eval::<rquickjs::Value, _>(source)
+            .catch(ctx)
+            .map_err(|error| error.to_string())?;
+        // undefined (e.g. a statement) has no JSON
+        let Some(json) = ctx.json_stringify(value).map_err(|error| error.to_string())? else {
+            return Ok(Value::Null);
+        };
+        let json = json.to_string().map_err(|error| error.to_string())?;
+        serde_json::from_str::<Value>(&json).map_err(|error| error.to_string())
+    })
+    .await;
+    let fail_message = evaluation.as_ref().err().map(String::as_str);
+    let error = sandbox.finish_evaluation(fail_message).or_else(|| {
+        fail_message.map(|fail_message| format!("Failed to evaluate expression\n{}", fail_message))
+    });
+    debug!("[ExpressionEvaluator] {} => {:?}", expression, evaluation);
+
+    sandbox.start_evaluation();
+    let expression_values = async_with!(context => |ctx| {
+        expression_get_value(expression, ctx).await
+    })
+    .await;
+    Ok(ExpressionEvaluateResult {
+        result: evaluation.ok(),
+        variables: evaluated_variables.values,
+        expression_values,
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_layer::data_layer::DataLayer;
+    use serde_json::json;
+
+    fn get_request(
+        expression: &str,
+        variables: HashMap<String, Value>,
+    ) -> ExpressionEvaluateRequest {
+        ExpressionEvaluateRequest {
+            expression: expression.to_string(),
+            variables,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_expression() {
+        let data_layer = DataLayer::new("", 500_000, false).await;
+        data_layer.sync("").await;
+        let metric_id = format!("metric_{}", uuid::Uuid::new_v4().simple());
+        let metrics = json!([{"name": "cpu", "tags": {}, "value": 70.0}]).to_string();
+        data_layer
+            .add_metrics_data("vector", &metric_id, &metrics)
+            .await
+            .unwrap();
+
+        let get_call = format!("get({{ metric_id: '{}', name: 'cpu' }})", metric_id);
+        let result = evaluate_expression(
+            get_request(
+                &format!("{} > $threshold", get_call),
+                HashMap::from([("threshold".to_string(), json!("50 + 10"))]),
+            ),
+            JsSandboxLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.result, Some(json!(true)));
+        assert_eq!(
+            result.variables,
+            BTreeMap::from([("$threshold".to_string(), json!(60))])
+        );
+        assert!(result.error.is_none());
+        assert_eq!(result.expression_values.len(), 2);
+        assert_eq!(
+            result.expression_values[0].get(&get_call),
+            Some(&Some(70.0))
+        );
+        assert_eq!(
+            result.expression_values[1].get("$threshold"),
+            Some(&Some(60.0))
+        );
+
+        // The error of the expression is returned with the values
+        let result = evaluate_expression(
+            get_request(&format!("{} > unknown_variable", get_call), HashMap::new()),
+            JsSandboxLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.result.is_none());
+        assert!(result.error.unwrap().contains("unknown_variable"));
+        assert_eq!(
+            result.expression_values[0].get(&get_call),
+            Some(&Some(70.0))
+        );
+
+        // The error of the variables
+        let result = evaluate_expression(
+            get_request(
+                "$threshold > 1",
+                HashMap::from([("threshold".to_string(), json!("(1"))]),
+            ),
+            JsSandboxLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result
+            .error
+            .unwrap()
+            .starts_with("Failed to evaluate the variables."));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_expression_time_limit() {
+        let result = evaluate_expression(
+            get_request("while(true) {}", HashMap::new()),
+            JsSandboxLimits {
+                time_limit_ms: 100,
+                memory_limit_mb: 0,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(result.result.is_none());
+        assert!(result.error.unwrap().contains("CPU time limit (100 ms)"));
+    }
+}
@@ -0,0 +1,137 @@
+use chrono::{DateTime, Duration, Utc};
+use data_layer::types::plan_item_definition::PlanItemDefinition;
+use std::collections::HashMap;
+
+// A fallback plan item runs after 1 tick with no matching plan item by default
+const DEFAULT_FALLBACK_AFTER: u32 = 1;
+
+// Whether the plan item runs only when no other plan item matches
+pub fn is_fallback(plan_item: &PlanItemDefinition) -> bool {
+    plan_item.fallback.unwrap_or(false)
+}
+
+// Sort the plan items by priority (the highest first). The fallback plan items come after the other plan items.
+pub fn sort_plan_items(plan_items: &[PlanItemDefinition]) -> Vec<PlanItemDefinition> {
+    let mut plan_items = plan_items.to_vec();
+    plan_items.sort_by(|a, b| {
+        is_fallback(a)
+            .cmp(&is_fallback(b))
+            .then(a.priority.cmp(&b.priority).reverse())
+    });
+    plan_items
+}
+
+/**
+ * FallbackTracker
+ * Tracks the consecutive ticks with no matching plan item and when each fallback plan item fired
+ * - A fallback plan item is due after "fallback_after" consecutive ticks with no matching plan item (including the current tick)
+ * - A fallback plan item does not fire again until its cool down has passed
+ */
+#[derive(Debug, Default)]
+pub struct FallbackTracker {
+    // The number of the consecutive ticks with no matching plan item before the current tick
+    no_match_ticks: u32,
+    // Whether a fallback plan item fired in the current tick
+    fired_in_tick: bool,
+    // plan_item_id => when the fallback plan item fired
+    last_fired: HashMap<String, DateTime<Utc>>,
+}
+
+impl FallbackTracker {
+    pub fn new() -> Self {
+        FallbackTracker::default()
+    }
+
+    // Whether the fallback plan item can fire in the current tick that has no matching plan item. cool_down is in seconds.
+    pub fn is_due(
+        &self,
+        plan_item: &PlanItemDefinition,
+        cool_down: u64,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let fallback_after = plan_item.fallback_after.unwrap_or(DEFAULT_FALLBACK_AFTER);
+        if self.no_match_ticks + 1 < fallback_after {
+            return false;
+        }
+        match self.last_fired.get(&plan_item.id) {
+            Some(last_fired) => *last_fired + Duration::seconds(cool_down as i64) <= now,
+            None => true,
+        }
+    }
+
+    pub fn record_fired(&mut self, plan_item_id: &str, now: DateTime<Utc>) {
+        self.last_fired.insert(plan_item_id.to_string(), now);
+        self.fired_in_tick = true;
+    }
+
+    // Count the tick. A tick with a matching plan item or a fired fallback plan item starts the count again.
+    pub fn end_tick(&mut self, matched: bool) {
+        if matched || self.fired_in_tick {
+            self.no_match_ticks = 0;
+        } else {
+            self.no_match_ticks = self.no_match_ticks.saturating_add(1);
+        }
+        self.fired_in_tick = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn get_plan_item(id: &str, priority: i16, fallback: bool) -> PlanItemDefinition {
+        serde_json::from_value(json!({
+            "id": id,
+            "priority": priority,
+            "scaling_components": [],
+            "ui": null,
+            "fallback": fallback,
+            "fallback_after": 3,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sort_plan_items() {
+        let plan_items = vec![
+            get_plan_item("fallback", 10, true),
+            get_plan_item("low", 1, false),
+            get_plan_item("high", 5, false),
+        ];
+        let ids = sort_plan_items(&plan_items)
+            .into_iter()
+            .map(|plan_item| plan_item.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["high", "low", "fallback"]);
+    }
+
+    #[test]
+    fn test_fallback_tracker() {
+        let plan_item = get_plan_item("fallback", 0, true);
+        let mut tracker = FallbackTracker::new();
+        let now = Utc::now();
+
+        // 3 consecutive ticks with no match
+        assert!(!tracker.is_due(&plan_item, 60, now));
+        tracker.end_tick(false);
+        assert!(!tracker.is_due(&plan_item, 60, now));
+        tracker.end_tick(false);
+        assert!(tracker.is_due(&plan_item, 60, now));
+
+        // A match starts the count again
+        tracker.end_tick(true);
+        assert!(!tracker.is_due(&plan_item, 60, now));
+        tracker.end_tick(false);
+        tracker.end_tick(false);
+        assert!(tracker.is_due(&plan_item, 60, now));
+        tracker.record_fired(&plan_item.id, now);
+        tracker.end_tick(false);
+
+        // The cool down of the fallback plan item
+        tracker.end_tick(false);
+        tracker.end_tick(false);
+        assert!(!tracker.is_due(&plan_item, 60, now + Duration::seconds(30)));
+        assert!(tracker.is_due(&plan_item, 60, now + Duration::seconds(60)));
+    }
+}
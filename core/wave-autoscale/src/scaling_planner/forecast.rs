@@ -0,0 +1,158 @@
+use anyhow::Result;
+
+// The smoothing factors of Holt-Winters for the level, the trend and the season
+const HOLT_WINTERS_ALPHA: f64 = 0.5;
+const HOLT_WINTERS_BETA: f64 = 0.3;
+const HOLT_WINTERS_GAMMA: f64 = 0.3;
+
+/**
+Forecast the value at x with the simple linear regression of the points
+- points: (x, y). x is the seconds from the current time (negative for the past)
+- Returns intercept + slope * x
+ */
+pub fn forecast_linear(points: &[(f64, f64)], x: f64) -> Result<f64> {
+    if points.len() < 2 {
+        return Err(anyhow::anyhow!(
+            "At least 2 values are needed for the forecast"
+        ));
+    }
+    let n = points.len() as f64;
+    let x_mean = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let y_mean = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - x_mean).powi(2)).sum();
+    if variance == 0.0 {
+        return Err(anyhow::anyhow!(
+            "The values should be collected at 2 different times at least for the forecast"
+        ));
+    }
+    let slope = covariance / variance;
+    Ok(y_mean + slope * (x - x_mean))
+}
+
+/**
+Forecast the value with Holt-Winters (additive triple exponential smoothing)
+- values: the values at a regular interval (the oldest first)
+- steps: how many intervals after the last value to forecast
+- season_length: the number of values in a season. Without it, only the level and the trend are smoothed (Holt's linear method).
+  At least 2 seasons of values are needed to initialize the season.
+ */
+pub fn forecast_holt_winters(
+    values: &[f64],
+    steps: f64,
+    season_length: Option<usize>,
+) -> Result<f64> {
+    let Some(season_length) = season_length else {
+        if values.len() < 2 {
+            return Err(anyhow::anyhow!(
+                "At least 2 values are needed for the forecast"
+            ));
+        }
+        let mut level = values[0];
+        let mut trend = values[1] - values[0];
+        for value in values.iter().skip(1) {
+            let last_level = level;
+            level = HOLT_WINTERS_ALPHA * value + (1.0 - HOLT_WINTERS_ALPHA) * (level + trend);
+            trend = HOLT_WINTERS_BETA * (level - last_level) + (1.0 - HOLT_WINTERS_BETA) * trend;
+        }
+        return Ok(level + steps * trend);
+    };
+    if season_length < 2 {
+        return Err(anyhow::anyhow!(
+            "A season should have 2 values at least. season_length: {}",
+            season_length
+        ));
+    }
+    if values.len() < 2 * season_length {
+        return Err(anyhow::anyhow!(
+            "At least 2 seasons of values are needed for the forecast. values: {}, season_length: {}",
+            values.len(),
+            season_length
+        ));
+    }
+
+    // Initialize with the first 2 seasons
+    // - trend: the difference of the averages of the seasons per value
+    // - season: the difference from the trend line through the average of the first season
+    let first_mean = values[..season_length].iter().sum::<f64>() / season_length as f64;
+    let second_mean =
+        values[season_length..2 * season_length].iter().sum::<f64>() / season_length as f64;
+    let mut trend = (second_mean - first_mean) / season_length as f64;
+    let center = (season_length - 1) as f64 / 2.0;
+    let mut seasonals = values[..season_length]
+        .iter()
+        .enumerate()
+        .map(|(index, value)| value - (first_mean + (index as f64 - center) * trend))
+        .collect::<Vec<f64>>();
+    // The level at the last value of the first season
+    let mut level = first_mean + center * trend;
+
+    for (index, value) in values.iter().enumerate().skip(season_length) {
+        let season_index = index % season_length;
+        let last_level = level;
+        level = HOLT_WINTERS_ALPHA * (value - seasonals[season_index])
+            + (1.0 - HOLT_WINTERS_ALPHA) * (level + trend);
+        trend = HOLT_WINTERS_BETA * (level - last_level) + (1.0 - HOLT_WINTERS_BETA) * trend;
+        seasonals[season_index] = HOLT_WINTERS_GAMMA * (value - level)
+            + (1.0 - HOLT_WINTERS_GAMMA) * seasonals[season_index];
+    }
+
+    // The season of the forecast is the one of the nearest interval
+    let season_index = (values.len() - 1 + steps.round() as usize) % season_length;
+    Ok(level + steps * trend + seasonals[season_index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(value: f64, expected: f64) {
+        assert!(
+            (value - expected).abs() < 1e-9,
+            "{} is not {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_forecast_linear() {
+        // y = 2x + 10
+        let points = vec![(-30.0, -50.0), (-20.0, -30.0), (-10.0, -10.0), (0.0, 10.0)];
+        assert_near(forecast_linear(&points, 0.0).unwrap(), 10.0);
+        assert_near(forecast_linear(&points, 60.0).unwrap(), 130.0);
+
+        assert!(forecast_linear(&[(0.0, 1.0)], 60.0).is_err());
+        assert!(forecast_linear(&[(0.0, 1.0), (0.0, 2.0)], 60.0).is_err());
+    }
+
+    #[test]
+    fn test_forecast_holt_winters() {
+        // A linear trend is followed without a season
+        let values = (0..10)
+            .map(|index| 3.0 * index as f64 + 1.0)
+            .collect::<Vec<f64>>();
+        assert_near(forecast_holt_winters(&values, 0.0, None).unwrap(), 28.0);
+        assert_near(forecast_holt_winters(&values, 5.0, None).unwrap(), 43.0);
+
+        // A trend with a season of 4 values
+        let pattern = [0.0, 5.0, 0.0, -5.0];
+        let values = (0..12)
+            .map(|index| 10.0 + index as f64 + pattern[index % 4])
+            .collect::<Vec<f64>>();
+        // The next value (index 12) is in the first phase of the season
+        assert_near(forecast_holt_winters(&values, 1.0, Some(4)).unwrap(), 22.0);
+        // index 14
+        assert_near(forecast_holt_winters(&values, 3.0, Some(4)).unwrap(), 24.0);
+        // index 17
+        assert_near(forecast_holt_winters(&values, 6.0, Some(4)).unwrap(), 32.0);
+
+        // Not enough values
+        assert!(forecast_holt_winters(&values[..7], 1.0, Some(4)).is_err());
+        assert!(forecast_holt_winters(&values, 1.0, Some(1)).is_err());
+        assert!(forecast_holt_winters(&values[..1], 1.0, None).is_err());
+    }
+}
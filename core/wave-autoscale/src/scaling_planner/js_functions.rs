@@ -1,71 +1,194 @@
+use super::forecast::{forecast_holt_winters, forecast_linear};
 use data_layer::data_layer::METRICS_DATA;
+use data_layer::types::metrics_data_item::MetricsDataItem;
+use data_layer::types::plan_expression_stats::PlanExpressionStats;
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::ops::Bound::Included;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use std::{collections::HashMap, str::FromStr};
 use tracing::{debug, error};
 use ulid::Ulid;
 
+// Constants
+const PLAN_EXPRESSION_PERIOD_SEC: u64 = 5 * 60;
+
 /**
- * PlanExpressionStats
+ * GetArgs
+ * The arguments of get() in the plan expressions
+ * - horizon_sec: how many seconds after the current time the forecast stats predict (default 0)
+ * - season_period_sec: the seasonality period of forecast_holt_winters (e.g. 3600 for an hourly pattern).
+ *   Without it, forecast_holt_winters smooths only the level and the trend.
  */
 #[derive(Debug, Clone)]
-enum PlanExpressionStats {
-    Latest,
-    Average,
-    Sum,
-    Count,
-    Minimum,
-    Maximum,
-    LinearSlope,
-    MovingAverageSlope,
+pub struct GetArgs {
+    pub metric_id: String,
+    pub name: Option<String>,
+    pub tags: HashMap<String, String>,
+    pub stats: String,
+    pub period_sec: u64,
+    pub horizon_sec: u64,
+    pub season_period_sec: Option<u64>,
 }
-impl std::fmt::Display for PlanExpressionStats {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            PlanExpressionStats::Latest => write!(f, "latest"),
-            PlanExpressionStats::Average => write!(f, "avg"),
-            PlanExpressionStats::Sum => write!(f, "sum"),
-            PlanExpressionStats::Count => write!(f, "count"),
-            PlanExpressionStats::Minimum => write!(f, "min"),
-            PlanExpressionStats::Maximum => write!(f, "max"),
-            PlanExpressionStats::LinearSlope => write!(f, "linear_slope"),
-            PlanExpressionStats::MovingAverageSlope => write!(f, "moving_average_slope"),
-        }
+
+impl GetArgs {
+    pub fn from_js_object(args: &rquickjs::Object<'_>) -> Result<GetArgs, rquickjs::Error> {
+        let metric_id = args
+            .get::<String, String>("metric_id".to_string())
+            .map_err(|_| {
+                error!("[ScalingPlan expression error] Failed to get metric_id");
+                rquickjs::Error::new_loading("Failed to get metric_id")
+            })?;
+        let name = args.get::<String, String>("name".to_string()).ok();
+        // tags, stats, period_sec is optional
+        let tags = match args.get::<String, HashMap<String, String>>("tags".to_string()) {
+            Ok(tags) => tags,
+            Err(_) => HashMap::new(),
+        };
+        let stats = args
+            .get::<String, String>("stats".to_string())
+            .unwrap_or("latest".to_string());
+        let period_sec = args
+            .get::<String, u64>("period_sec".to_string())
+            .unwrap_or(PLAN_EXPRESSION_PERIOD_SEC); // default 5 min
+        let horizon_sec = args
+            .get::<String, u64>("horizon_sec".to_string())
+            .unwrap_or(0);
+        let season_period_sec = args
+            .get::<String, u64>("season_period_sec".to_string())
+            .ok();
+        Ok(GetArgs {
+            metric_id,
+            name,
+            tags,
+            stats,
+            period_sec,
+            horizon_sec,
+            season_period_sec,
+        })
+    }
+
+    // The key of the arguments for GetValueCache. The order of the tags doesn't matter.
+    fn cache_key(&self) -> String {
+        let mut tags = self.tags.iter().collect::<Vec<(&String, &String)>>();
+        tags.sort();
+        format!(
+            "{}|{:?}|{:?}|{}|{}|{}|{:?}",
+            self.metric_id,
+            self.name,
+            tags,
+            self.stats.to_lowercase(),
+            self.period_sec,
+            self.horizon_sec,
+            self.season_period_sec
+        )
     }
 }
 
-// Constants
-const PLAN_EXPRESSION_PERIOD_SEC: u64 = 5 * 60;
+/**
+ * GetValueCache
+ * The values of get() in an evaluation (e.g. a tick of a scaling plan) by the arguments
+ * so that the same get() in the variables, the expressions and the history of the expressions is computed once.
+ * A new cache is needed for every evaluation because the metrics data changes.
+ */
+#[derive(Debug, Default, Clone)]
+pub struct GetValueCache {
+    // cache_key => the value or the error of get()
+    values: Arc<Mutex<HashMap<String, Result<f64, String>>>>,
+}
 
+impl GetValueCache {
+    pub fn new() -> Self {
+        GetValueCache::default()
+    }
+
+    // Get the cached value of the arguments or compute it with get_value
+    pub fn get_or_compute<F>(&self, args: &GetArgs, get_value: F) -> Result<f64, rquickjs::Error>
+    where
+        F: FnOnce() -> Result<f64, rquickjs::Error>,
+    {
+        let key = args.cache_key();
+        if let Some(cached) = self
+            .values
+            .lock()
+            .ok()
+            .and_then(|values| values.get(&key).cloned())
+        {
+            return cached.map_err(rquickjs::Error::new_loading);
+        }
+        let result = get_value();
+        // The errors of get_metric_value are the loading errors that can be recreated from the name
+        let cached = match &result {
+            Ok(value) => Some(Ok(*value)),
+            Err(rquickjs::Error::Loading { name, .. }) => Some(Err(name.clone())),
+            Err(_) => None,
+        };
+        if let (Some(cached), Ok(mut values)) = (cached, self.values.lock()) {
+            values.insert(key, cached);
+        }
+        result
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.values.lock().map(|values| values.len()).unwrap_or(0)
+    }
+}
+
+// get() without the cache. The scaling planners use get_in_js_with_cache.
+#[cfg(test)]
 pub fn get_in_js(args: rquickjs::Object<'_>) -> Result<f64, rquickjs::Error> {
-    let metric_id = args
-        .get::<String, String>("metric_id".to_string())
-        .map_err(|_| {
-            error!("[ScalingPlan expression error] Failed to get metric_id");
-            rquickjs::Error::new_loading("Failed to get metric_id")
-        })?;
-    let name = args.get::<String, String>("name".to_string()).ok();
-    // tags, stats, period_sec is optional
-    let tags = match args.get::<String, HashMap<String, String>>("tags".to_string()) {
-        Ok(tags) => tags,
-        Err(_) => HashMap::new(),
-    };
-    let stats = args
-        .get::<String, String>("stats".to_string())
-        .unwrap_or("latest".to_string());
-    let period_sec = args
-        .get::<String, u64>("period_sec".to_string())
-        .unwrap_or(PLAN_EXPRESSION_PERIOD_SEC); // default 5 min
+    let args = GetArgs::from_js_object(&args)?;
+    get_current_metric_value(&args)
+}
 
+/**
+get() with the cache of the evaluation
+- The values of the same arguments in the evaluation are computed once with the in-memory METRICS_DATA
+ */
+pub fn get_in_js_with_cache(
+    cache: GetValueCache,
+) -> impl Fn(rquickjs::Object<'_>) -> Result<f64, rquickjs::Error> {
+    move |args: rquickjs::Object<'_>| {
+        let args = GetArgs::from_js_object(&args)?;
+        cache.get_or_compute(&args, || get_current_metric_value(&args))
+    }
+}
+
+// Get the value of the metric with the in-memory METRICS_DATA at the current time
+fn get_current_metric_value(args: &GetArgs) -> Result<f64, rquickjs::Error> {
     let Ok(metrics_data) = METRICS_DATA.read() else {
         error!("[get_in_js] Failed to get metrics_data");
         return Err(rquickjs::Error::new_loading("Failed to get the metrics data"));
     };
-    let start_time = Ulid::from_datetime(
-        std::time::SystemTime::now() - Duration::from_millis(1000 * period_sec),
+    get_metric_value(&metrics_data.metrics_data_map, args, SystemTime::now())
+}
+
+/**
+Get the value of the metric with the stats
+- metrics_data_map: metric_id => (ULID => MetricsDataItem). It is the in-memory METRICS_DATA or the metrics data loaded from the database for backtesting
+- now: the end of the time range. It is the current time or the virtual clock for backtesting
+ */
+pub fn get_metric_value(
+    metrics_data_map: &HashMap<String, BTreeMap<String, MetricsDataItem>>,
+    args: &GetArgs,
+    now: SystemTime,
+) -> Result<f64, rquickjs::Error> {
+    let GetArgs {
+        metric_id,
+        name,
+        tags,
+        stats,
+        period_sec,
+        horizon_sec,
+        season_period_sec,
+    } = args;
+    let start_time = Ulid::from_parts(
+        to_timestamp_ms(now - Duration::from_millis(1000 * period_sec)),
+        0,
     );
-    let end_time = Ulid::new();
+    let end_time = Ulid::from_parts(to_timestamp_ms(now), u128::MAX);
 
     debug!(
         "[get_in_js] - metric_id: {}, name: {:?}, tags: {:?}, stats: {}, period_sec: {}",
@@ -73,12 +196,14 @@ pub fn get_in_js(args: rquickjs::Object<'_>) -> Result<f64, rquickjs::Error> {
     );
 
     // find metric_id
-    let Some(metric_values) = metrics_data.metrics_data_map.get(&metric_id) else {
+    let Some(metric_values) = metrics_data_map.get(metric_id) else {
         return Err(rquickjs::Error::new_loading("Failed to get metric_id from the metrics data"));
     };
 
     // Filtered metric values
     let mut target_value_arr: Vec<f64> = Vec::new();
+    // The timestamps (ms) of the filtered metric values for the forecast stats
+    let mut target_time_arr: Vec<u64> = Vec::new();
 
     // Validate whether the start_time is before the last item in the metric_values.
     // If the start_time is after the last item, then BTreeMap will panic.
@@ -99,7 +224,10 @@ pub fn get_in_js(args: rquickjs::Object<'_>) -> Result<f64, rquickjs::Error> {
     // Find the metric values between the time range (current time - period_sec, current time)
     metric_values
         .range((Included(start_time.to_string()), Included(end_time.to_string())))
-        .for_each(|(_ulid, metrics_data_item)| {
+        .for_each(|(ulid, metrics_data_item)| {
+            let timestamp_ms = Ulid::from_str(ulid.as_str())
+                .map(|ulid| ulid.timestamp_ms())
+                .unwrap_or(0);
             // Get the json string
             let Ok(value) = serde_json::to_value(metrics_data_item.clone()) else {
                 error!(
@@ -167,6 +295,7 @@ pub fn get_in_js(args: rquickjs::Object<'_>) -> Result<f64, rquickjs::Error> {
                 let item_value = json_value_item.get("value").and_then(Value::as_f64);
                 if item_value.is_some() {
                     target_value_arr.append(&mut vec![item_value.unwrap()]);
+                    target_time_arr.push(timestamp_ms);
                 }
             }
         });
@@ -238,6 +367,33 @@ pub fn get_in_js(args: rquickjs::Object<'_>) -> Result<f64, rquickjs::Error> {
                 calculate_slope(&moving_average)
                     .map_err(|_| rquickjs::Error::new_loading("Failed to calculate the slope"))
             }
+            // ForecastLinear (Simple Linear Regression over time)
+            ms if PlanExpressionStats::ForecastLinear.to_string() == ms => {
+                let now_ms = to_timestamp_ms(now);
+                let points = target_time_arr
+                    .iter()
+                    .zip(target_value_arr.iter())
+                    .map(|(time_ms, value)| ((*time_ms as f64 - now_ms as f64) / 1000.0, *value))
+                    .collect::<Vec<(f64, f64)>>();
+                forecast_linear(&points, *horizon_sec as f64).map_err(|error| {
+                    error!("[get_in_js] Failed to forecast - {}", error);
+                    rquickjs::Error::new_loading("Failed to forecast the value")
+                })
+            }
+            // ForecastHoltWinters (Additive Triple Exponential Smoothing)
+            ms if PlanExpressionStats::ForecastHoltWinters.to_string() == ms => {
+                calculate_holt_winters(
+                    &target_time_arr,
+                    &target_value_arr,
+                    to_timestamp_ms(now),
+                    *horizon_sec,
+                    *season_period_sec,
+                )
+                .map_err(|error| {
+                    error!("[get_in_js] Failed to forecast - {}", error);
+                    rquickjs::Error::new_loading("Failed to forecast the value")
+                })
+            }
             _ => {
                 error!("[get_in_js] stats is valid: {}", stats);
                 Err(rquickjs::Error::new_loading(
@@ -249,6 +405,12 @@ pub fn get_in_js(args: rquickjs::Object<'_>) -> Result<f64, rquickjs::Error> {
     metric_stats
 }
 
+fn to_timestamp_ms(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 /**
 Calculate the slope
 - x: index
@@ -272,3 +434,156 @@ fn calculate_slope(values: &[f64]) -> Result<f64, rquickjs::Error> {
         (x.len() as f64 * x_y_sum - x_sum * y_sum) / (x.len() as f64 * x_square_sum - x_sum_square);
     Ok(slope)
 }
+
+/**
+Forecast the value at horizon_sec after now with Holt-Winters
+- The values collected at the same time are averaged and the averages are treated as values at a regular interval
+- interval: the average interval of the collection times
+- season_length: season_period_sec / interval
+ */
+fn calculate_holt_winters(
+    times_ms: &[u64],
+    values: &[f64],
+    now_ms: u64,
+    horizon_sec: u64,
+    season_period_sec: Option<u64>,
+) -> anyhow::Result<f64> {
+    // timestamp_ms => (sum, count)
+    let mut values_by_time: BTreeMap<u64, (f64, f64)> = BTreeMap::new();
+    for (time_ms, value) in times_ms.iter().zip(values.iter()) {
+        let entry = values_by_time.entry(*time_ms).or_insert((0.0, 0.0));
+        entry.0 += value;
+        entry.1 += 1.0;
+    }
+    let (Some(first_ms), Some(last_ms)) = (
+        values_by_time.keys().next().copied(),
+        values_by_time.keys().last().copied(),
+    ) else {
+        return Err(anyhow::anyhow!("No values for the forecast"));
+    };
+    if values_by_time.len() < 2 {
+        return Err(anyhow::anyhow!(
+            "The values should be collected at 2 different times at least for the forecast"
+        ));
+    }
+    let series = values_by_time
+        .values()
+        .map(|(sum, count)| sum / count)
+        .collect::<Vec<f64>>();
+    let interval_sec = (last_ms - first_ms) as f64 / 1000.0 / (series.len() - 1) as f64;
+    // The forecast is for horizon_sec after now, not after the last value
+    let steps =
+        (now_ms.saturating_sub(last_ms) as f64 / 1000.0 + horizon_sec as f64) / interval_sec;
+    let season_length = season_period_sec
+        .map(|season_period_sec| (season_period_sec as f64 / interval_sec).round() as usize);
+    forecast_holt_winters(&series, steps, season_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_args(tags: &[(&str, &str)], stats: &str) -> GetArgs {
+        GetArgs {
+            metric_id: "metric_id".to_string(),
+            name: Some("cpu".to_string()),
+            tags: tags
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            stats: stats.to_string(),
+            period_sec: 60,
+            horizon_sec: 0,
+            season_period_sec: None,
+        }
+    }
+
+    #[test]
+    fn test_get_value_cache() {
+        let cache = GetValueCache::new();
+        let computed = std::cell::Cell::new(0);
+        let get_value = |value: f64| {
+            computed.set(computed.get() + 1);
+            Ok(value)
+        };
+
+        // The same arguments (regardless of the order of the tags) are computed once
+        let args = get_args(&[("a", "1"), ("b", "2"), ("c", "3")], "avg");
+        assert_eq!(cache.get_or_compute(&args, || get_value(1.0)).unwrap(), 1.0);
+        let args = get_args(&[("c", "3"), ("b", "2"), ("a", "1")], "AVG");
+        assert_eq!(cache.get_or_compute(&args, || get_value(2.0)).unwrap(), 1.0);
+        assert_eq!(computed.get(), 1);
+
+        // The other arguments are computed
+        let args = get_args(&[("a", "1")], "avg");
+        assert_eq!(cache.get_or_compute(&args, || get_value(3.0)).unwrap(), 3.0);
+        assert_eq!(computed.get(), 2);
+
+        // The errors are cached too
+        let args = get_args(&[], "max");
+        let error = cache.get_or_compute(&args, || {
+            Err(rquickjs::Error::new_loading("Failed to get metric_id"))
+        });
+        assert!(error.is_err());
+        let error = cache.get_or_compute(&args, || get_value(4.0));
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            rquickjs::Error::new_loading("Failed to get metric_id").to_string()
+        );
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_get_metric_value_forecast() {
+        // A value every 10 seconds that increases by 1 per second with an hourly-like season of 4 values
+        let now = SystemTime::now();
+        let pattern = [0.0, 5.0, 0.0, -5.0];
+        let mut metric_values = BTreeMap::new();
+        for index in 0..12 {
+            let time = now - Duration::from_secs(10 * (11 - index));
+            let value = 10.0 * index as f64 + pattern[index as usize % 4];
+            metric_values.insert(
+                Ulid::from_parts(to_timestamp_ms(time), 0).to_string(),
+                MetricsDataItem {
+                    json_value: serde_json::json!([{ "name": "cpu", "value": value }]).to_string(),
+                },
+            );
+        }
+        let metrics_data_map = HashMap::from([("metric_id".to_string(), metric_values)]);
+
+        let mut args = get_args(&[], "forecast_holt_winters");
+        args.period_sec = 120;
+        args.horizon_sec = 30;
+        args.season_period_sec = Some(40);
+        // index 14
+        let value = get_metric_value(&metrics_data_map, &args, now).unwrap();
+        assert!((value - 140.0).abs() < 1e-6, "{}", value);
+
+        // The linear regression ignores the season
+        args.stats = "forecast_linear".to_string();
+        args.season_period_sec = None;
+        let value = get_metric_value(&metrics_data_map, &args, now).unwrap();
+        let current = get_metric_value(
+            &metrics_data_map,
+            &GetArgs {
+                horizon_sec: 0,
+                ..args.clone()
+            },
+            now,
+        )
+        .unwrap();
+        assert!(value > current);
+        assert!(
+            (value - current - 30.0).abs() < 5.0,
+            "{} {}",
+            value,
+            current
+        );
+
+        // Not enough values for 2 seasons
+        args.stats = "forecast_holt_winters".to_string();
+        args.period_sec = 60;
+        args.season_period_sec = Some(40);
+        assert!(get_metric_value(&metrics_data_map, &args, now).is_err());
+    }
+}
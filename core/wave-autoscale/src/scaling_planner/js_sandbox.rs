@@ -0,0 +1,215 @@
+use anyhow::Result;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use utils::wave_config::WaveConfig;
+
+// The interrupt handler is called every few microseconds while JavaScript is running.
+// A longer gap between the calls means that JavaScript was not running (e.g. waiting for a scaling component),
+// so only up to MAX_INTERRUPT_CHECK_GAP of the gap is counted as the CPU time of the evaluation.
+const MAX_INTERRUPT_CHECK_GAP: Duration = Duration::from_millis(10);
+
+/**
+ * JsSandboxLimits
+ * The limits of the JavaScript evaluations (expressions, variables and the params of the scaling components)
+ * - time_limit_ms: the CPU time limit of an evaluation. 0 means no limit.
+ * - memory_limit_mb: the memory limit of the JavaScript runtime. 0 means no limit.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JsSandboxLimits {
+    pub time_limit_ms: u64,
+    pub memory_limit_mb: u64,
+}
+
+impl From<&WaveConfig> for JsSandboxLimits {
+    fn from(wave_config: &WaveConfig) -> Self {
+        JsSandboxLimits {
+            time_limit_ms: wave_config.expression_time_limit_ms,
+            memory_limit_mb: wave_config.expression_memory_limit_mb,
+        }
+    }
+}
+
+impl Default for JsSandboxLimits {
+    fn default() -> Self {
+        JsSandboxLimits::from(&WaveConfig::default())
+    }
+}
+
+// The CPU time spent by the current evaluation
+#[derive(Debug, Default)]
+struct EvaluationClock {
+    last_check: Option<Instant>,
+    elapsed: Duration,
+    interrupted: bool,
+}
+
+/**
+ * JsSandbox
+ * A JavaScript runtime with the CPU time and memory limits
+ * - An evaluation that runs longer than the time limit (e.g. while(true)) is interrupted by the interrupt handler
+ * - An evaluation that allocates more than the memory limit fails with "out of memory"
+ * - A fresh context is created for every tick so that the globals (e.g. "var $x") do not leak between ticks
+ */
+pub struct JsSandbox {
+    runtime: rquickjs::AsyncRuntime,
+    limits: JsSandboxLimits,
+    clock: Arc<Mutex<EvaluationClock>>,
+}
+
+impl JsSandbox {
+    pub async fn new(limits: JsSandboxLimits) -> Result<Self> {
+        let runtime = rquickjs::AsyncRuntime::new()?;
+        if limits.memory_limit_mb > 0 {
+            runtime
+                .set_memory_limit((limits.memory_limit_mb * 1024 * 1024) as usize)
+                .await;
+        }
+        let clock = Arc::new(Mutex::new(EvaluationClock::default()));
+        if limits.time_limit_ms > 0 {
+            let time_limit = Duration::from_millis(limits.time_limit_ms);
+            let handler_clock = clock.clone();
+            runtime
+                .set_interrupt_handler(Some(Box::new(move || {
+                    let Ok(mut clock) = handler_clock.lock() else {
+                        return false;
+                    };
+                    let now = Instant::now();
+                    if let Some(last_check) = clock.last_check {
+                        clock.elapsed += (now - last_check).min(MAX_INTERRUPT_CHECK_GAP);
+                    }
+                    clock.last_check = Some(now);
+                    if clock.elapsed > time_limit {
+                        clock.interrupted = true;
+                    }
+                    clock.interrupted
+                })))
+                .await;
+        }
+        Ok(JsSandbox {
+            runtime,
+            limits,
+            clock,
+        })
+    }
+
+    // A fresh context with the standard JavaScript objects
+    pub async fn new_context(&self) -> Result<rquickjs::AsyncContext> {
+        // Free the objects of the previous contexts first. Otherwise, the memory limit can be hit while creating the context.
+        self.runtime.run_gc().await;
+        Ok(rquickjs::AsyncContext::full(&self.runtime).await?)
+    }
+
+    // Start measuring the CPU time of an evaluation
+    pub fn start_evaluation(&self) {
+        if let Ok(mut clock) = self.clock.lock() {
+            *clock = EvaluationClock::default();
+        }
+    }
+
+    /**
+    Finish the evaluation and get the error message if the evaluation hit a limit
+    - fail_message: the error of the evaluation if it failed (to detect "out of memory")
+     */
+    pub fn finish_evaluation(&self, fail_message: Option<&str>) -> Option<String> {
+        let interrupted = self
+            .clock
+            .lock()
+            .map(|clock| clock.interrupted)
+            .unwrap_or(false);
+        if interrupted {
+            return Some(format!(
+                "The evaluation was interrupted because it exceeded the CPU time limit ({} ms)",
+                self.limits.time_limit_ms
+            ));
+        }
+        let out_of_memory = fail_message.map(is_out_of_memory_message).unwrap_or(false);
+        if self.limits.memory_limit_mb > 0 && out_of_memory {
+            return Some(format!(
+                "The evaluation failed because it exceeded the memory limit ({} MB)",
+                self.limits.memory_limit_mb
+            ));
+        }
+        None
+    }
+}
+
+// QuickJS throws InternalError "out of memory" when an allocation exceeds the memory limit
+fn is_out_of_memory_message(fail_message: &str) -> bool {
+    fail_message.contains("out of memory")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rquickjs::{async_with, CatchResultExt};
+
+    async fn eval_in_sandbox(sandbox: &JsSandbox, expression: &str) -> Option<String> {
+        let context = sandbox.new_context().await.unwrap();
+        sandbox.start_evaluation();
+        let fail_message = async_with!(context => |ctx| {
+            ctx.eval::<(), _>(expression)
+                .catch(ctx)
+                .err()
+                .map(|error| error.to_string())
+        })
+        .await;
+        sandbox.finish_evaluation(fail_message.as_deref())
+    }
+
+    #[tokio::test]
+    async fn test_js_sandbox_limits() {
+        let sandbox = JsSandbox::new(JsSandboxLimits {
+            time_limit_ms: 100,
+            memory_limit_mb: 16,
+        })
+        .await
+        .unwrap();
+
+        let error = eval_in_sandbox(&sandbox, "while(true) {}").await.unwrap();
+        assert!(error.contains("CPU time limit (100 ms)"));
+
+        // The sandbox can evaluate the next expressions after hitting the limit
+        assert!(eval_in_sandbox(&sandbox, "var $x = 1 + 2;").await.is_none());
+
+        // The memory limit is tested without the time limit.
+        // Under load, the interrupt can fire while QuickJS is throwing "out of memory", which makes the result flaky.
+        let sandbox = JsSandbox::new(JsSandboxLimits {
+            time_limit_ms: 0,
+            memory_limit_mb: 16,
+        })
+        .await
+        .unwrap();
+        let error = eval_in_sandbox(
+            &sandbox,
+            "var a = []; while(true) { a.push(new Array(1000).fill(1)); }",
+        )
+        .await
+        .unwrap();
+        assert!(error.contains("memory limit (16 MB)"));
+        assert!(eval_in_sandbox(&sandbox, "var $x = 1 + 2;").await.is_none());
+
+        // An exception of the expression itself is not the memory limit
+        assert!(eval_in_sandbox(&sandbox, "throw null").await.is_none());
+        assert!(eval_in_sandbox(&sandbox, "throw ''").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_js_sandbox_fresh_context() {
+        let sandbox = JsSandbox::new(JsSandboxLimits::default()).await.unwrap();
+        let context = sandbox.new_context().await.unwrap();
+        async_with!(context => |ctx| {
+            let _ = ctx.eval::<(), _>("var $x = 1;");
+        })
+        .await;
+
+        // The globals of the previous tick do not leak into the new context
+        let context = sandbox.new_context().await.unwrap();
+        let defined = async_with!(context => |ctx| {
+            ctx.eval::<bool, _>("typeof $x !== 'undefined'").unwrap()
+        })
+        .await;
+        assert!(!defined);
+    }
+}
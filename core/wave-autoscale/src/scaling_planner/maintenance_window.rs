@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+use data_layer::{types::plan_item_definition::PlanItemDefinition, MaintenanceWindowDefinition};
+use tracing::error;
+
+/**
+ * Remove the scaling components of a plan item that are in an open maintenance window
+ * - The disabled maintenance windows are ignored
+ * - Returns the plan item with the scaling components to apply and the suppressed component ids with the reason
+ */
+pub fn suppress_maintenance_window_components(
+    plan_item: &PlanItemDefinition,
+    plan_id: &str,
+    maintenance_windows: &[MaintenanceWindowDefinition],
+    now: DateTime<Utc>,
+) -> (PlanItemDefinition, Vec<(String, String)>) {
+    let mut available_plan_item = plan_item.clone();
+    let mut suppressed = Vec::new();
+    if maintenance_windows.is_empty() {
+        return (available_plan_item, suppressed);
+    }
+    available_plan_item.scaling_components.retain(|metadata| {
+        let Some(component_id) = metadata["component_id"].as_str() else {
+            return true;
+        };
+        for maintenance_window in maintenance_windows.iter() {
+            if !maintenance_window.enabled || !maintenance_window.selects(plan_id, component_id) {
+                continue;
+            }
+            match maintenance_window.get_active_until(now) {
+                Ok(Some(active_until)) => {
+                    suppressed.push((
+                        component_id.to_string(),
+                        format!(
+                            "Suppressed by maintenance window {} until {}",
+                            maintenance_window.id,
+                            active_until.to_rfc3339()
+                        ),
+                    ));
+                    return false;
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    error!(
+                        "[ScalingPlanner] Invalid maintenance window {} - {}",
+                        maintenance_window.id, error
+                    );
+                }
+            }
+        }
+        true
+    });
+    (available_plan_item, suppressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn get_plan_item() -> PlanItemDefinition {
+        serde_json::from_value(json!({
+            "id": "scale_out",
+            "priority": 1,
+            "expression": "true",
+            "scaling_components": [
+                { "component_id": "k8s_deployment", "replicas": 2 },
+                { "component_id": "ec2_asg", "desired": 2 },
+            ],
+        }))
+        .unwrap()
+    }
+
+    fn get_maintenance_window(value: serde_json::Value) -> MaintenanceWindowDefinition {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_suppress_maintenance_window_components() {
+        let plan_item = get_plan_item();
+        let now = DateTime::parse_from_rfc3339("2024-03-25T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        // No maintenance window
+        let (available, suppressed) =
+            suppress_maintenance_window_components(&plan_item, "plan_a", &[], now);
+        assert_eq!(available.scaling_components.len(), 2);
+        assert!(suppressed.is_empty());
+
+        // An open window for a scaling component
+        let windows = vec![get_maintenance_window(json!({
+            "id": "db_migration",
+            "start_time": "2024-03-25T09:00:00Z",
+            "end_time": "2024-03-25T11:00:00Z",
+            "component_ids": ["ec2_asg"],
+        }))];
+        let (available, suppressed) =
+            suppress_maintenance_window_components(&plan_item, "plan_a", &windows, now);
+        assert_eq!(
+            available.scaling_components,
+            vec![json!({ "component_id": "k8s_deployment", "replicas": 2 })]
+        );
+        assert_eq!(
+            suppressed,
+            vec![(
+                "ec2_asg".to_string(),
+                "Suppressed by maintenance window db_migration until 2024-03-25T11:00:00+00:00"
+                    .to_string()
+            )]
+        );
+
+        // An open window for another plan
+        let windows = vec![get_maintenance_window(json!({
+            "id": "deploy_freeze",
+            "cron_expression": "0 10 * * *",
+            "duration": "1h",
+            "plan_ids": ["plan_b"],
+        }))];
+        let (available, suppressed) =
+            suppress_maintenance_window_components(&plan_item, "plan_a", &windows, now);
+        assert_eq!(available.scaling_components.len(), 2);
+        assert!(suppressed.is_empty());
+        let (available, suppressed) =
+            suppress_maintenance_window_components(&plan_item, "plan_b", &windows, now);
+        assert!(available.scaling_components.is_empty());
+        assert_eq!(suppressed.len(), 2);
+
+        // A closed window and a disabled window
+        let windows = vec![
+            get_maintenance_window(json!({
+                "id": "closed",
+                "start_time": "2024-03-25T11:00:00Z",
+                "end_time": "2024-03-25T12:00:00Z",
+            })),
+            get_maintenance_window(json!({
+                "id": "disabled",
+                "start_time": "2024-03-25T09:00:00Z",
+                "end_time": "2024-03-25T11:00:00Z",
+                "enabled": false,
+            })),
+        ];
+        let (available, suppressed) =
+            suppress_maintenance_window_components(&plan_item, "plan_a", &windows, now);
+        assert_eq!(available.scaling_components.len(), 2);
+        assert!(suppressed.is_empty());
+    }
+}
@@ -1,27 +1,69 @@
+pub mod backtest;
 pub mod scaling_planner_manager;
+mod cool_down;
+pub mod expression_evaluator;
+mod fallback;
+mod forecast;
 mod js_functions;
+pub mod js_sandbox;
+mod maintenance_window;
+mod overrides;
+pub mod pending_actions;
+mod plan_item_state;
+mod plan_pause;
+mod plan_variables;
+pub mod rate_limit;
+mod stabilization;
+pub mod transaction;
 mod webhooks;
 
 use crate::{
-    metric_updater::SharedMetricUpdater, scaling_component::SharedScalingComponentManager,
+    metric_updater::SharedMetricUpdater,
+    scaling_component::{apply_to_shared, ScalingComponentManager, SharedScalingComponentManager},
 };
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use data_layer::{
     data_layer::DataLayer,
     types::{
-        plan_log_definition::PlanLogDefinition,
-        plan_item_definition::PlanItemDefinition, scaling_plan_definition::DEFAULT_PLAN_INTERVAL,
+        action::{ActionOverrides, ActionStatus},
+        cron_schedule::CronSchedule,
+        max_actions::MaxActions,
+        plan_log_definition::{PlanLogDefinition, PlanLogStatus},
+        plan_item_definition::PlanItemDefinition,
+        planner_state::PlannerState,
+        scaling_behavior::ScalingBehavior,
+        scaling_plan_definition::{
+            DEFAULT_PLAN_INTERVAL, EXECUTION_MODE_ALL_MATCHING, PLAN_MODE_SHADOW,
+        },
     },
-    ScalingPlanDefinition,
+    MaintenanceWindowDefinition, ScalingPlanDefinition,
 };
-use rquickjs::async_with;
+use rquickjs::{async_with, CatchResultExt};
 
 use serde_json::{json, Value};
-use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::{sync::RwLock, task::JoinHandle, time};
-use tracing::{debug, error, info};
-use js_functions::get_in_js;
+use tracing::{debug, error, info, warn};
+use cool_down::ComponentCoolDowns;
+use fallback::{is_fallback, sort_plan_items, FallbackTracker};
+use js_functions::{get_in_js_with_cache, GetValueCache};
+use js_sandbox::{JsSandbox, JsSandboxLimits};
+use maintenance_window::suppress_maintenance_window_components;
+use overrides::apply_overrides;
+use plan_item_state::{parse_for_duration, PlanItemState, PlanItemStateTracker};
+use plan_pause::PlanPauseCache;
+use plan_variables::set_plan_variables;
+use rate_limit::{ActionHistory, PlanRateLimit};
+use stabilization::{Stabilization, Stabilizer};
+use transaction::{apply_transaction, is_transactional, TransactionResult};
+
+// How often the action receiver polls the action queue without a notification
+const ACTION_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 
 /**
@@ -36,67 +78,93 @@ struct ExressionResult {
     expression_values: Option<Vec<HashMap<String, Option<f64>>>>
 }
 
-/**
- * Parse action from DataLayer
- */
-fn parse_action(action: serde_json::Value) -> Result<(String, String)> {
-    let action = action.as_object();
-    if action.is_none() {
-        return Err(anyhow::anyhow!("Failed to parse action"));
-    }
-    let action = action.unwrap();
-
-    let plan_id = action.get("plan_id").and_then(serde_json::Value::as_str);
-    if plan_id.is_none() {
-        return Err(anyhow::anyhow!("Failed to parse plan_id"));
-    }
-    let plan_id = plan_id.unwrap();
-
-    let plan_item_id = action
-        .get("plan_item_id")
-        .and_then(serde_json::Value::as_str);
-    if plan_item_id.is_none() {
-        return Err(anyhow::anyhow!("Failed to parse plan_item_id"));
-    }
-    let plan_item_id = plan_item_id.unwrap();
-
-    Ok((plan_id.to_string(), plan_item_id.to_string()))
-}
-
 async fn apply_scaling_components(
-    scaling_components_metadata: &[Value],
+    scaling_components: Vec<HashMap<String, Value>>,
     shared_scaling_component_manager: &SharedScalingComponentManager,
-    context: rquickjs::AsyncContext
+    context: rquickjs::AsyncContext,
+    shadow: bool,
 ) -> Vec<Result<HashMap<String, serde_json::Value>>> {
     let mut scaling_results: Vec<Result<HashMap<String, serde_json::Value>>> = Vec::new();
-    for metadata in scaling_components_metadata.iter() {
-        let Some(scaling_component_id) = metadata["component_id"].as_str() else {
+    for params in scaling_components {
+        let Some(scaling_component_id) = params.get("component_id").and_then(Value::as_str).map(str::to_string) else {
             error!("[ScalingPlanner] Failed to get component_id");
             continue;
         };
+
+        // In shadow mode, the resolved params are returned without applying them
+        if shadow {
+            info!(
+                "[ScalingPlanner] Shadow mode. Would have applied to {} - {:?}",
+                scaling_component_id, params
+            );
+            scaling_results.push(Ok(params));
+            continue;
+        }
+
+        let result = apply_to_shared(
+            shared_scaling_component_manager,
+            &scaling_component_id,
+            params,
+            context.clone(),
+        )
+        .await;
+        scaling_results.push(result);
+    }
+    scaling_results
+}
+
+// Resolve the params of every scaling component of a plan item before any of them is applied
+async fn resolve_plan_item_params(
+    plan_item: &PlanItemDefinition,
+    context: rquickjs::AsyncContext,
+) -> Vec<HashMap<String, Value>> {
+    let mut scaling_components = Vec::new();
+    for metadata in plan_item.scaling_components.iter() {
         let Some(metadata_object) = metadata.as_object() else {
             error!("[ScalingPlanner] Failed to get metadata as object");
             continue;
         };
-        // Convert metadata to HashMap => (Number to f64, String to String)
-        let mut params = HashMap::new();
-        for (key, value) in metadata_object.iter() {
-            if value.is_string() {
-                params.insert(key.to_string(), convert_js_expression(context.clone(), value.as_str().unwrap()).await);
-            } else {
-                params.insert(key.to_string(), value.clone());
-            }
-        }
+        scaling_components
+            .push(resolve_scaling_component_params(metadata_object, context.clone()).await);
+    }
+    scaling_components
+}
 
-        {
-            let shared_scaling_component_manager = shared_scaling_component_manager.read().await;
-            let result = shared_scaling_component_manager
-                .apply_to(scaling_component_id, params, context.clone())
-                .await;
-            scaling_results.push(result);
+// Convert the metadata of a scaling component to HashMap => (Number to f64, String to String)
+// The string values are evaluated as JS expressions (e.g. "$replicas * 2")
+async fn resolve_scaling_component_params(
+    metadata_object: &serde_json::Map<String, Value>,
+    context: rquickjs::AsyncContext,
+) -> HashMap<String, Value> {
+    let mut params = HashMap::new();
+    for (key, value) in metadata_object.iter() {
+        if value.is_string() {
+            params.insert(
+                key.to_string(),
+                convert_js_expression(context.clone(), value.as_str().unwrap()).await,
+            );
+        } else {
+            params.insert(key.to_string(), value.clone());
         }
     }
-    scaling_results
+    params
+}
+
+/**
+ * PlanLogContext
+ * The values that are shared by all plan logs and webhooks of a ScalingPlanner
+ */
+#[derive(Clone)]
+struct PlanLogContext {
+    data_layer: Arc<DataLayer>,
+    plan_db_id: String,
+    plan_id: String,
+    plan_webhooks: Option<Vec<String>>,
+    webhooks: Option<Vec<utils::wave_config::Webhooks>>,
+    // The evaluated variables of the current tick for the plan logs
+    variables_json: Option<String>,
+    // The parameter overrides of the manual run for the plan logs
+    overrides_json: Option<String>,
 }
 
 /**
@@ -106,18 +174,15 @@ Create a PlanLogDefinition
 - plan_item_json
 - metric_values_json
 - metadata_values_json
+- status
 */
 async fn create_plan_log(
-    // plan_db_id, paln
-    data_layer: &Arc<DataLayer>,
-    plan_db_id: String,
-    plan_id: String,
+    plan_log_context: &PlanLogContext,
     plan_item: &PlanItemDefinition,
     expression_value_map: Option<&Vec<HashMap<String, Option<f64>>>>,
     scaling_components_metadata: Option<&Result<HashMap<String,Value>>>,
     fail_message: Option<String>,
-    plan_webhooks: Option<Vec<String>>,
-    webhooks: Option<Vec<utils::wave_config::Webhooks>>,
+    status: PlanLogStatus,
 ) {
     let plan_item_id = plan_item.clone().id;
 
@@ -139,41 +204,390 @@ async fn create_plan_log(
         } else {
             "".to_string()
         };
-    let plan_log: PlanLogDefinition = PlanLogDefinition::new(
-        plan_db_id,
-        plan_id.clone(),
+    let mut plan_log: PlanLogDefinition = PlanLogDefinition::new(
+        plan_log_context.plan_db_id.clone(),
+        plan_log_context.plan_id.clone(),
         json!(plan_item.clone()).to_string(),
         metric_values_json,
         metadata_values_json.clone(),
         fail_message.clone(),
+        status.clone(),
     );
-    debug!(
-        "[ScalingPlanner] plan_log - {:?}",
-        plan_log
-    );
-    let _ = data_layer
-        .add_plan_logs(plan_log)
-        .await;
+    plan_log.variables_json = plan_log_context.variables_json.clone();
+    plan_log.overrides_json = plan_log_context.overrides_json.clone();
+    debug!("[ScalingPlanner] plan_log - {:?}", plan_log);
+    let _ = plan_log_context.data_layer.add_plan_logs(plan_log).await;
 
     let webhook_request_body = webhooks::WebhookRequestBody {
-        plan_id,
+        plan_id: plan_log_context.plan_id.clone(),
         plan_item_id,
         scaling_component_json_str: metadata_values_json,
         fail_message: fail_message.clone(),
+        status,
+    };
+    webhooks::send_webhooks(
+        plan_log_context.webhooks.clone(),
+        plan_log_context.plan_webhooks.clone(),
+        webhook_request_body,
+    );
+}
+
+// Create the plan logs of an executed plan item. A transactional plan item has one plan log for the whole sequence.
+async fn create_plan_item_logs(
+    plan_log_context: &PlanLogContext,
+    plan_item: &PlanItemDefinition,
+    expression_value_map: Option<&Vec<HashMap<String, Option<f64>>>>,
+    plan_item_results: &PlanItemResults,
+    shadow: bool,
+) {
+    match plan_item_results {
+        PlanItemResults::Transactional(transaction) => {
+            let status = if transaction.fail_message.is_some() {
+                PlanLogStatus::Fail
+            } else {
+                PlanLogStatus::Success
+            };
+            create_plan_log(
+                plan_log_context,
+                plan_item,
+                expression_value_map,
+                Some(&Ok(transaction.to_plan_log_metadata())),
+                transaction.fail_message.clone(),
+                status,
+            )
+            .await;
+        }
+        PlanItemResults::Independent(results) => {
+            for result in results.iter() {
+                let (fail_message, status) = match result {
+                    Ok(_) if shadow => (None, PlanLogStatus::Shadow),
+                    Ok(_) => (None, PlanLogStatus::Success),
+                    Err(error) => (Some(error.to_string()), PlanLogStatus::Fail),
+                };
+                create_plan_log(
+                    plan_log_context,
+                    plan_item,
+                    expression_value_map,
+                    Some(result),
+                    fail_message,
+                    status,
+                )
+                .await;
+            }
+        }
+    }
+}
+
+// Get the webhook ids of the plan from the metadata (e.g. "webhooks": ["webhook_id"])
+fn get_plan_webhooks(plan_metadata: &HashMap<String, Value>) -> Result<Option<Vec<String>>> {
+    let Some(plan_webhooks) = plan_metadata.get("webhooks") else {
+        return Ok(None);
+    };
+    let Some(plan_webhooks) = plan_webhooks.as_array() else {
+        return Err(anyhow::anyhow!(
+            "Failed to get plan webhooks Not Array - {:?}",
+            plan_webhooks
+        ));
+    };
+    let plan_webhooks = plan_webhooks
+        .iter()
+        .map(|webhook| {
+            webhook
+                .as_str()
+                .unwrap_or_else(|| {
+                    error!(
+                        "[ScalingPlanner] Failed to get plan webhook Not String - {:?}",
+                        webhook
+                    );
+                    ""
+                })
+                .to_string()
+        })
+        .collect::<Vec<String>>();
+    Ok(Some(plan_webhooks))
+}
+
+// Get the interval of the plan in milliseconds from the metadata
+fn get_plan_interval(scaling_plan_definition: &ScalingPlanDefinition) -> u16 {
+    let plan_interval: u16 = scaling_plan_definition
+        .metadata
+        .get("interval")
+        .unwrap_or(&json!(DEFAULT_PLAN_INTERVAL))
+        .as_u64()
+        .unwrap_or(DEFAULT_PLAN_INTERVAL as u64) as u16;
+    // plan_interval should be at least DEFAULT_PLAN_INTERVAL
+    let plan_interval = if plan_interval < DEFAULT_PLAN_INTERVAL {
+        DEFAULT_PLAN_INTERVAL
+    } else {
+        plan_interval
+    };
+    // If there is a cron_expression in plans then plan_interval should be set to 1 second to check the cron_expression every second
+    if scaling_plan_definition
+        .plans
+        .iter()
+        .any(|plan| plan.cron_expression.is_some())
+    {
+        DEFAULT_PLAN_INTERVAL
+    } else {
+        plan_interval
+    }
+}
+
+// The timezone of the cron expressions in the plan (metadata.timezone)
+fn get_plan_timezone(scaling_plan_definition: &ScalingPlanDefinition) -> Option<String> {
+    scaling_plan_definition
+        .metadata
+        .get("timezone")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+// A plan runs every matching plan item in an interval if the metadata has "execution_mode: all_matching"
+fn is_all_matching_mode(scaling_plan_definition: &ScalingPlanDefinition) -> bool {
+    scaling_plan_definition
+        .metadata
+        .get("execution_mode")
+        .and_then(Value::as_str)
+        .map(|execution_mode| execution_mode == EXECUTION_MODE_ALL_MATCHING)
+        .unwrap_or(false)
+}
+
+// The cool down of a plan item in seconds. The cool down of the plan item overrides the cool down of the plan.
+fn get_plan_item_cool_down(
+    plan_item: &PlanItemDefinition,
+    plan_metadata: &HashMap<String, Value>,
+) -> u64 {
+    plan_item
+        .cool_down
+        .or_else(|| plan_metadata.get("cool_down").and_then(Value::as_u64))
+        .unwrap_or(0)
+}
+
+// Save the fired occurrence of the cron expression of a plan item to catch up the missed ones from it
+// In shadow mode, it is kept only in memory because nothing was applied at the occurrence.
+async fn save_cron_last_fired(
+    plan_log_context: &PlanLogContext,
+    cron_last_fired: &RwLock<HashMap<String, DateTime<Utc>>>,
+    plan_item_id: &str,
+    cron_occurrence: DateTime<Utc>,
+    shadow: bool,
+) {
+    cron_last_fired
+        .write()
+        .await
+        .insert(plan_item_id.to_string(), cron_occurrence);
+    if shadow {
+        return;
+    }
+    let result = plan_log_context
+        .data_layer
+        .set_cron_last_fired(&plan_log_context.plan_id, plan_item_id, cron_occurrence)
+        .await;
+    if let Err(error) = result {
+        error!(
+            "[ScalingPlanner] Failed to save the last fired cron occurrence: {}",
+            error
+        );
+    }
+}
+
+// A rejected plan item is not requested again until its cool down (seconds) ends
+async fn is_rejection_cooling_down(
+    plan_log_context: &PlanLogContext,
+    plan_item: &PlanItemDefinition,
+    cool_down: u64,
+    now: DateTime<Utc>,
+) -> bool {
+    match plan_log_context
+        .data_layer
+        .get_last_rejected_at(&plan_log_context.plan_id, &plan_item.id)
+        .await
+    {
+        Ok(Some(rejected_at)) => {
+            let cooling_down = now < rejected_at + chrono::Duration::seconds(cool_down as i64);
+            if cooling_down {
+                debug!(
+                    "[ScalingPlanner] {} was rejected at {} and is cooling down",
+                    plan_item.id, rejected_at
+                );
+            }
+            cooling_down
+        }
+        Ok(None) => false,
+        Err(error) => {
+            error!(
+                "[ScalingPlanner] Failed to get the rejected actions: {}",
+                error
+            );
+            true
+        }
+    }
+}
+
+/**
+Save the resolved params of the scaling components as a pending action that waits for a human approval
+- A plan item has at most one pending action at a time
+- The PENDING_APPROVAL plan logs send the webhooks with the id of the pending action
+- Returns whether a pending action was created
+ */
+async fn request_approval(
+    plan_log_context: &PlanLogContext,
+    plan_item: &PlanItemDefinition,
+    scaling_components: Vec<HashMap<String, Value>>,
+    expression_value_map: &Vec<HashMap<String, Option<f64>>>,
+    pending_action_ttl: chrono::Duration,
+) -> bool {
+    let data_layer = &plan_log_context.data_layer;
+    let now = Utc::now();
+    if let Err(error) = data_layer.expire_pending_actions(now).await {
+        error!(
+            "[ScalingPlanner] Failed to expire the pending actions: {}",
+            error
+        );
+    }
+    match data_layer
+        .has_pending_action(&plan_log_context.plan_id, &plan_item.id)
+        .await
+    {
+        Ok(false) => {}
+        Ok(true) => {
+            debug!(
+                "[ScalingPlanner] {} is already waiting for the approval",
+                plan_item.id
+            );
+            return false;
+        }
+        Err(error) => {
+            error!(
+                "[ScalingPlanner] Failed to get the pending actions: {}",
+                error
+            );
+            return false;
+        }
+    }
+
+    let expires_at = now + pending_action_ttl;
+    let pending_action = match data_layer
+        .add_pending_action(
+            &plan_log_context.plan_db_id,
+            &plan_log_context.plan_id,
+            plan_item,
+            &scaling_components,
+            expires_at,
+        )
+        .await
+    {
+        Ok(pending_action) => pending_action,
+        Err(error) => {
+            error!(
+                "[ScalingPlanner] Failed to add the pending action: {}",
+                error
+            );
+            create_plan_log(
+                plan_log_context,
+                plan_item,
+                Some(expression_value_map),
+                None,
+                Some(format!("Failed to add the pending action. {}", error)),
+                PlanLogStatus::Fail,
+            )
+            .await;
+            return false;
+        }
+    };
+    info!(
+        "[ScalingPlanner] {} is waiting for the approval of the pending action {}",
+        plan_item.id, pending_action.id
+    );
+    let message = format!(
+        "Waiting for the approval of the pending action {} until {}",
+        pending_action.id, pending_action.expires_at
+    );
+    for params in scaling_components {
+        create_plan_log(
+            plan_log_context,
+            plan_item,
+            Some(expression_value_map),
+            Some(&Ok(params)),
+            Some(message.clone()),
+            PlanLogStatus::PendingApproval,
+        )
+        .await;
+    }
+    true
+}
+
+// Save the runtime state of the planner in the DataLayer to restore it when the planner is recreated
+async fn save_planner_state(
+    data_layer: &DataLayer,
+    plan_id: &str,
+    last_plan_item_id: &RwLock<String>,
+    component_cool_downs: &RwLock<ComponentCoolDowns>,
+) {
+    let state = PlannerState {
+        last_plan_item_id: last_plan_item_id.read().await.clone(),
+        component_actions: component_cool_downs.read().await.get_actions().clone(),
+    };
+    if let Err(error) = data_layer.save_planner_state(plan_id, &state).await {
+        error!(
+            "[ScalingPlanner] Failed to save the planner state: {}",
+            error
+        );
+    }
+}
+
+// Restore the runtime state of the planner that was saved before the planner was recreated (e.g. restart, definition change)
+async fn restore_planner_state(
+    data_layer: &DataLayer,
+    plan_id: &str,
+    last_plan_item_id: &RwLock<String>,
+    component_cool_downs: &RwLock<ComponentCoolDowns>,
+) {
+    let state = match data_layer.get_planner_state(plan_id).await {
+        Ok(Some(state)) => state,
+        Ok(None) => return,
+        Err(error) => {
+            error!(
+                "[ScalingPlanner] Failed to get the planner state: {}",
+                error
+            );
+            return;
+        }
     };
-    webhooks::send_webhooks(webhooks, plan_webhooks, webhook_request_body);
+    debug!("[ScalingPlanner] Restore the planner state of {}", plan_id);
+    *last_plan_item_id.write().await = state.last_plan_item_id;
+    *component_cool_downs.write().await = ComponentCoolDowns::from_actions(state.component_actions);
 }
 
 pub struct ScalingPlanner {
     definition: ScalingPlanDefinition,
     metric_updater: SharedMetricUpdater,
     scaling_component_manager: SharedScalingComponentManager,
+    // The last applied plan item
     last_plan_item_id: Arc<RwLock<String>>,
-    last_plan_timestamp: Arc<RwLock<Option<DateTime<Utc>>>>,
-    last_cool_down: Arc<RwLock<u64>>,
+    // The last action and the cool down of each scaling component
+    component_cool_downs: Arc<RwLock<ComponentCoolDowns>>,
+    // plan_item_id => the last fired occurrence of the cron expression (persisted in the DataLayer)
+    cron_last_fired: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    // The recommendations and the last applied params for the stabilization windows (behavior)
+    stabilizer: Arc<RwLock<Stabilizer>>,
+    // Since when the expression of each plan item has been true (for the "for" duration)
+    plan_item_states: Arc<RwLock<PlanItemStateTracker>>,
+    // The recent actions of the plan for metadata.max_actions
+    plan_action_history: Arc<RwLock<ActionHistory>>,
     data_layer: Arc<DataLayer>,
     task: Option<JoinHandle<()>>,
     webhooks: Option<Vec<utils::wave_config::Webhooks>>,
+    // Global shadow mode (WaveConfig). It overrides the "mode" in the plan metadata.
+    shadow_mode: bool,
+    // Whether the runtime state is saved in the DataLayer and restored when the planner is recreated
+    persist_state: bool,
+    // The CPU time and memory limits of the JavaScript evaluations
+    js_limits: JsSandboxLimits,
+    // How long the pending actions of the plan items with require_approval wait for the approval
+    pending_action_ttl: chrono::Duration,
+    // The maintenance windows in which the scaling components are not applied automatically
+    maintenance_windows: Vec<MaintenanceWindowDefinition>,
     // For instant action
     action_task: Option<JoinHandle<()>>,
     last_plan_item_id_by_action: Arc<RwLock<String>>,
@@ -187,192 +601,255 @@ impl<'a> ScalingPlanner {
         scaling_component_manager: SharedScalingComponentManager,
         data_layer: Arc<DataLayer>,
         webhooks: Option<Vec<utils::wave_config::Webhooks>>,
+        shadow_mode: bool,
     ) -> Self {
         ScalingPlanner {
             definition,
             metric_updater,
             scaling_component_manager,
             last_plan_item_id: Arc::new(RwLock::new(String::new())),
-            last_plan_timestamp: Arc::new(RwLock::new(None)),
-            last_cool_down: Arc::new(RwLock::new(0)),
+            component_cool_downs: Arc::new(RwLock::new(ComponentCoolDowns::new())),
+            cron_last_fired: Arc::new(RwLock::new(HashMap::new())),
+            stabilizer: Arc::new(RwLock::new(Stabilizer::new())),
+            plan_item_states: Arc::new(RwLock::new(PlanItemStateTracker::new())),
+            plan_action_history: Arc::new(RwLock::new(ActionHistory::new())),
             data_layer,
             task: None,
             webhooks,
+            shadow_mode,
+            persist_state: false,
+            js_limits: JsSandboxLimits::default(),
+            pending_action_ttl: chrono::Duration::hours(1),
+            maintenance_windows: Vec::new(),
             action_task: None,
             last_plan_item_id_by_action: Arc::new(RwLock::new(String::new())),
             last_plan_timestamp_by_action: Arc::new(RwLock::new(None)),
         }
     }
     fn sort_plan_by_priority(&self) -> Vec<PlanItemDefinition> {
-        let mut plans = self.definition.plans.clone();
-        plans.sort_by(|a, b| a.priority.cmp(&b.priority).reverse());
-        plans
+        sort_plan_items(&self.definition.plans)
+    }
+
+    // The ScalingPlannerManager persists the state so that a restart or a definition change does not reset the cool downs
+    pub fn set_persist_state(&mut self, persist_state: bool) {
+        self.persist_state = persist_state;
+    }
+
+    pub fn set_js_limits(&mut self, js_limits: JsSandboxLimits) {
+        self.js_limits = js_limits;
+    }
+
+    pub fn set_pending_action_ttl(&mut self, pending_action_ttl: chrono::Duration) {
+        self.pending_action_ttl = pending_action_ttl;
+    }
+
+    pub fn set_maintenance_windows(
+        &mut self,
+        maintenance_windows: Vec<MaintenanceWindowDefinition>,
+    ) {
+        self.maintenance_windows = maintenance_windows;
+    }
+
+    // The ScalingPlannerManager keeps the recent actions of the plan so that a definition change does not reset max_actions
+    pub fn set_plan_action_history(&mut self, plan_action_history: Arc<RwLock<ActionHistory>>) {
+        self.plan_action_history = plan_action_history;
     }
 
     pub fn get_id(&self) -> String {
         self.definition.id.clone()
     }
 
+    // A plan runs in shadow mode if the global shadow mode is on or the plan metadata has "mode: shadow"
+    fn is_shadow_mode(&self) -> bool {
+        if self.shadow_mode {
+            return true;
+        }
+        self.definition
+            .metadata
+            .get("mode")
+            .and_then(Value::as_str)
+            .map(|mode| mode == PLAN_MODE_SHADOW)
+            .unwrap_or(false)
+    }
+
     pub fn run(&mut self) {
         let _shared_metric_updater = self.metric_updater.clone();
         let shared_scaling_component_manager = self.scaling_component_manager.clone();
         let shared_last_run = self.last_plan_item_id.clone();
-        let shared_last_plan_timestamp = self.last_plan_timestamp.clone();
-        let shared_last_cool_down = self.last_cool_down.clone();
-        let data_layer: Arc<DataLayer> = self.data_layer.clone();
-        let webhooks = self.webhooks.clone();
+        let shared_component_cool_downs = self.component_cool_downs.clone();
+        let shared_cron_last_fired = self.cron_last_fired.clone();
+        let shared_stabilizer = self.stabilizer.clone();
+        let shared_plan_item_states = self.plan_item_states.clone();
+        let shadow = self.is_shadow_mode();
+        let all_matching = is_all_matching_mode(&self.definition);
+        let persist_state = self.persist_state;
+        let js_limits = self.js_limits;
+        let pending_action_ttl = self.pending_action_ttl;
+        let maintenance_windows = self.maintenance_windows.clone();
 
         // PlanDefinition
         let scaling_plan_definition = self.definition.clone();
-        let plan_id = scaling_plan_definition.id.clone();
-        let plan_db_id = scaling_plan_definition.db_id.clone();
         let plan_metadata = scaling_plan_definition.metadata.clone();
         let plan_variables = scaling_plan_definition.variables.clone();
+        let plan_behavior = scaling_plan_definition.behavior.clone();
+        let plan_timezone = get_plan_timezone(&scaling_plan_definition);
+        let plan_rate_limit = match self.get_plan_rate_limit() {
+            Ok(plan_rate_limit) => plan_rate_limit,
+            Err(error) => {
+                error!(
+                    "[ScalingPlanner] Failed to get plan max_actions - {}",
+                    error
+                );
+                return;
+            }
+        };
 
         // For plan_interval
-        let plan_interval: u16 = plan_metadata
-            .get("interval")
-            .unwrap_or(&json!(DEFAULT_PLAN_INTERVAL))
-            .as_u64()
-            .unwrap_or(DEFAULT_PLAN_INTERVAL as u64) as u16;
-        // plan_interval should be at least DEFAULT_PLAN_INTERVAL
-        let plan_interval = if plan_interval < DEFAULT_PLAN_INTERVAL {
-            DEFAULT_PLAN_INTERVAL
-        } else {
-            plan_interval
-        };
-        // If there is a cron_expression in plans then plan_interval should be set to 1 second to check the cron_expression every second
-        let plan_interval = if scaling_plan_definition
-            .plans
-            .iter()
-            .any(|plan| plan.cron_expression.is_some())
-        {
-            DEFAULT_PLAN_INTERVAL
-        } else {
-            plan_interval
-        };
-        let plan_webhooks = match plan_metadata.get("webhooks") {
-            Some(plan_webhooks) => {
-                let Some(plan_webhooks) = plan_webhooks.as_array() else {
-                    error!("[ScalingPlanner] Failed to get plan webhooks Not Array - {:?}", plan_webhooks);
-                    return;
-                };
-                let plan_webhooks = plan_webhooks
-                    .iter()
-                    .map(|webhook| 
-                        webhook.as_str().unwrap_or_else(|| {
-                            error!("[ScalingPlanner] Failed to get plan webhook Not String - {:?}", webhook);
-                            ""
-                        }).to_string()
-                    )
-                    .collect::<Vec<String>>();
-                Some(plan_webhooks)
+        let plan_interval = get_plan_interval(&scaling_plan_definition);
+        let mut plan_log_context = match self.get_plan_log_context() {
+            Ok(plan_log_context) => plan_log_context,
+            Err(error) => {
+                error!("[ScalingPlanner] {}", error);
+                return;
             }
-            None => None,
         };
 
         let plan_items = self.sort_plan_by_priority();
 
         let mut interval = time::interval(Duration::from_millis(plan_interval as u64));
 
+        let action_plan_log_context = plan_log_context.clone();
+        let action_plan_rate_limit = plan_rate_limit.clone();
         let task = tokio::spawn(async move {
-            // Initialize the runtime and context to evaluate the scaling plan expressions
+            // Initialize the sandboxed runtime to evaluate the scaling plan expressions
             // TODO: Support Python and other languages
-            let Ok(runtime) = rquickjs::AsyncRuntime::new() else {
-                error!("[ScalingPlanner] Error creating runtime");
-                return;
-            };
-            let Ok(context) = rquickjs::AsyncContext::full(&runtime).await else {
-                error!("[ScalingPlanner] Error creating context");
-                return;
+            let sandbox = match JsSandbox::new(js_limits).await {
+                Ok(sandbox) => sandbox,
+                Err(error) => {
+                    error!("[ScalingPlanner] Error creating runtime - {}", error);
+                    return;
+                }
             };
 
-            // let mut scaling_plan_cool_down: Option<u64> = None;
+            // Restore the last fired occurrences of the cron expressions to catch up the missed ones
+            match plan_log_context
+                .data_layer
+                .get_cron_last_fired(&plan_log_context.plan_id)
+                .await
+            {
+                Ok(cron_last_fired) => shared_cron_last_fired.write().await.extend(cron_last_fired),
+                Err(error) => error!(
+                    "[ScalingPlanner] Failed to get the last fired cron occurrences: {}",
+                    error
+                ),
+            }
+
+            // Restore the last applied plan item and the cool downs so that a recreated planner does not scale again immediately
+            if persist_state {
+                restore_planner_state(
+                    &plan_log_context.data_layer,
+                    &plan_log_context.plan_id,
+                    &shared_last_run,
+                    &shared_component_cool_downs,
+                )
+                .await;
+            }
+
+            // The consecutive ticks with no matching plan item for the fallback plan items
+            let mut fallback_tracker = FallbackTracker::new();
+            // The stages of a matched plan item are shared with the backtest
+            let stages = PlanItemStages {
+                plan_id: &plan_log_context.plan_id,
+                plan_behavior: plan_behavior.as_ref(),
+                maintenance_windows: &maintenance_windows,
+                component_cool_downs: &shared_component_cool_downs,
+                stabilizer: &shared_stabilizer,
+                plan_rate_limit: &plan_rate_limit,
+                scaling_component_manager: &shared_scaling_component_manager,
+            };
+            // Whether the plan was paused in the last tick
+            let mut paused = false;
+            let mut plan_pause_cache =
+                PlanPauseCache::new(&plan_log_context.data_layer, &plan_log_context.plan_id);
 
             // Run the loop every interval
             loop {
-                /*
-                 * Cool Down Stage
-                 */
-                {
-                    let mut shared_last_cool_down: tokio::sync::RwLockWriteGuard<'_, u64> =
-                        shared_last_cool_down.write().await;
-                    let cool_down = *shared_last_cool_down;
-                    // apply cool down
-                    if let Some(last_plan_timestamp) = *shared_last_plan_timestamp.read().await {
-                        let now = Utc::now();
-                        let cool_down_duration = chrono::Duration::seconds(cool_down as i64);
-                        let time_left = last_plan_timestamp + cool_down_duration - now;
-                        if time_left.num_milliseconds() > 0 {
-                            debug!(
-                                "[ScalingPlanner] Cooling down. Skip the plan. {} seconds left.",
-                                time_left.num_seconds()
-                            );
-                            interval.tick().await;
-                            continue;
-                        } else {
-                            *shared_last_cool_down = 0;
-                        }
+                // Wait for the next interval. The first tick completes immediately.
+                interval.tick().await;
+
+                // A paused plan is not evaluated until it is resumed or the pause expires (POST /api/plans/{id}/pause)
+                let is_paused = plan_pause_cache
+                    .is_paused(&plan_log_context.data_layer, Utc::now())
+                    .await;
+                if is_paused != paused {
+                    if is_paused {
+                        info!("[ScalingPlanner] {} is paused", plan_log_context.plan_id);
+                    } else {
+                        info!("[ScalingPlanner] {} is resumed", plan_log_context.plan_id);
                     }
+                    paused = is_paused;
+                }
+                if paused {
+                    // The "for" durations start again after the plan is resumed
+                    *shared_plan_item_states.write().await = PlanItemStateTracker::new();
+                    continue;
                 }
                 {
+                    // A fresh context for every tick so that the globals (e.g. "var $x") do not leak between ticks
+                    let context = match sandbox.new_context().await {
+                        Ok(context) => context,
+                        Err(error) => {
+                            error!("[ScalingPlanner] Error creating context - {}", error);
+                            continue;
+                        }
+                    };
                     // Prepare the context to evaluate the scaling plan expressions that are written in JavaScript
-                    // Set the get function to get the metric values
+                    // Set the get function to get the metric values. The values are cached in the tick.
+                    let get_value_cache = GetValueCache::new();
                     async_with!(context => |ctx| {
                         let _ = ctx.globals().set(
                             "get",
-                            rquickjs::prelude::Func::new("get", get_in_js),
+                            rquickjs::prelude::Func::new("get", get_in_js_with_cache(get_value_cache)),
                         );
                     })
                     .await;
 
                     // Evaluate "variables" in the scaling plan
-                    for (key, value) in plan_variables.clone().iter() {
-                        // If the value is a string, it should be assigned without quotes.
-                        let value_for_set = if value.is_string() {
-                            let value = value.as_str();
-                            if value.is_none() {
-                                error!("[ScalingPlanner] Failed to evaluate the variable: {}", key);
-                                continue;
-                            }
-                            value.unwrap().to_string()
-                        } else {
-                            let value = serde_json::to_string(value);
-                            if value.is_err() {
-                                error!("[ScalingPlanner] Failed to evaluate the variable: {}", key);
-                                continue;
-                            }
-                            value.unwrap()
-                        };
-
-                        // Evaluate the variable and set the value to the context with "$key"
-                        async_with!(context => |ctx| {
-                            let expression_format = format!("var ${} = {};", key, value_for_set);
-                            println!("{}", expression_format);
-                            let expression = expression_format;
-                            let result = ctx.eval::<(), _>(expression);
-                            if result.is_err() {
-                                error!(
-                                    "[ScalingPlanner] Failed to set the variable: {} - {}",
-                                    key,
-                                    result.err().unwrap()
-                                );
-                            }
-
-                            // // Confirm the variable
-                            // let result = ctx.eval::<f64, _>(format!("${}", key));
-                            // if result.is_err() {
-                            //     error!(
-                            //         "[ScalingPlanner] Failed to confirm the variable: {}",
-                            //         key
-                            //     );
-                            // }
-                            // let result = result.unwrap();
-                        })
-                        .await;
+                    sandbox.start_evaluation();
+                    let evaluated_variables = set_plan_variables(&context, &plan_variables).await;
+                    // The evaluated variables are saved in the plan logs of this tick
+                    plan_log_context.variables_json = if evaluated_variables.values.is_empty() {
+                        None
+                    } else {
+                        Some(json!(evaluated_variables.values).to_string())
+                    };
+                    if let Some(limit_error) =
+                        sandbox.finish_evaluation(evaluated_variables.fail_message.as_deref())
+                    {
+                        // No plan item can be evaluated without the variables
+                        error!(
+                            "[ScalingPlanner] Failed to evaluate the variables - {}",
+                            limit_error
+                        );
+                        for plan_item in plan_items.iter() {
+                            create_plan_log(
+                                &plan_log_context,
+                                plan_item,
+                                None,
+                                None,
+                                Some(format!("Failed to evaluate the variables. {}", limit_error)),
+                                PlanLogStatus::Fail,
+                            )
+                            .await;
+                        }
+                        continue;
                     }
 
                     let mut excuted = false;
+                    // Whether the expression of a plan item (not a fallback plan item) was true in this tick
+                    let mut matched = false;
+                    // The plan items whose expressions were evaluated in this tick
+                    let mut evaluated_plan_item_ids = HashSet::new();
 
                     /*
                      * Find the plan to execute
@@ -381,130 +858,111 @@ impl<'a> ScalingPlanner {
                      * 3. Execute the plan
                      */
                     for plan_item in plan_items.iter() {
-                        if plan_item.cron_expression.is_none() && plan_item.expression.is_none() {
-                            error!(
-                                "[ScalingPlanner] Both cron_expression and expression are empty"
-                            );
-                            // Skip this plan
-                            continue;
-                        }
-                        /*
-                         * 1. Cron Expression
-                         */
-                        if let Some(cron_expression) = plan_item.cron_expression.as_ref() {
-                            if cron_expression.is_empty() {
-                                error!("[ScalingPlanner] cron_expression is empty");
-                                // Skip this plan
+                        // The fallback plan item runs only if no plan item matched for "fallback_after" ticks and it is not cooling down
+                        if is_fallback(plan_item) {
+                            let cool_down = get_plan_item_cool_down(plan_item, &plan_metadata);
+                            if matched || !fallback_tracker.is_due(plan_item, cool_down, Utc::now())
+                            {
                                 continue;
                             }
-                            debug!("[ScalingPlanner] cron_expression - {}", cron_expression);
-                            let schedule = cron::Schedule::from_str(cron_expression.as_str());
-                            if schedule.is_err() {
-                                error!(
-                                    "[ScalingPlanner] Error parsing cron expression: {}",
-                                    cron_expression
-                                );
+                        }
+                        let cron_last_fired = shared_cron_last_fired
+                            .read()
+                            .await
+                            .get(&plan_item.id)
+                            .copied();
+                        sandbox.start_evaluation();
+                        let evaluation = evaluate_plan_item(
+                            plan_item,
+                            &context,
+                            Utc::now(),
+                            DEFAULT_PLAN_INTERVAL as i64,
+                            plan_timezone.as_deref(),
+                            cron_last_fired,
+                        )
+                        .await;
+                        evaluated_plan_item_ids.insert(plan_item.id.as_str());
+                        let evaluation = match evaluation {
+                            PlanItemEvaluation::Failed(fail_message) => {
+                                // The limit of the sandbox is the reason of the failure
+                                let limit_error =
+                                    sandbox.finish_evaluation(Some(fail_message.as_str()));
+                                PlanItemEvaluation::Failed(limit_error.unwrap_or(fail_message))
+                            }
+                            evaluation => evaluation,
+                        };
+                        let (expression_value_map_for_history, cron_occurrence) = match evaluation {
+                            PlanItemEvaluation::Matched(expression_values, cron_occurrence) => {
+                                (expression_values, cron_occurrence)
+                            }
+                            PlanItemEvaluation::Failed(fail_message) => {
+                                shared_plan_item_states.write().await.reset(&plan_item.id);
                                 // If the expression is invalid, create a PlanLogDefinition for the error
                                 create_plan_log(
-                                    &data_layer.clone(),
-                                    plan_db_id.clone(),
-                                    plan_id.clone(),
+                                    &plan_log_context,
                                     plan_item,
                                     None,
                                     None,
-                                    Some("Failed to parse cron expression".to_string()),
-                                    plan_webhooks.clone(),
-                                    webhooks.clone(),
+                                    Some(fail_message),
+                                    PlanLogStatus::Fail,
                                 )
                                 .await;
                                 // Skip this plan
                                 continue;
                             }
-                            let schedule = schedule.unwrap();
-                            let now = chrono::Utc::now();
-                            let datetime = schedule.upcoming(chrono::Utc).take(1).next();
-                            if datetime.is_none() {
-                                error!("[ScalingPlanner] Error getting next datetime for cron expression: {}", cron_expression);
+                            PlanItemEvaluation::NotMatched => {
+                                shared_plan_item_states.write().await.reset(&plan_item.id);
                                 // Skip this plan
                                 continue;
                             }
-                            let datetime = datetime.unwrap();
-                            let duration = datetime - now;
-                            let duration = duration.num_milliseconds();
-                            if duration < 0 || duration > (DEFAULT_PLAN_INTERVAL as i64) {
-                                info!("[ScalingPlanner] The datetime is not yet reached for cron expression: {}", cron_expression);
-                                // Skip this plan
-                                continue;
-                            }
-                            // It's confirmed that the cron expression is valid and the datetime is reached
+                        };
+                        // A plan item that is pending for the "for" duration also keeps the fallback plan items from running
+                        if !is_fallback(plan_item) {
+                            matched = true;
                         }
 
-                        /*
-                         * 2. JS Expression
-                         */
-                        let mut expression_value_map_for_history: Vec<
-                            HashMap<String, Option<f64>>,
-                        > = Vec::new();
-
-                        if let Some(expression) = plan_item.expression.as_ref() {
-                            if expression.is_empty() {
-                                error!("[ScalingPlanner] expression is empty");
-                                // Skip this plan
-                                continue;
-                            }
-                            debug!("[ScalingPlanner] expression\n{}", expression);
-                            // Evaluate the expression.
-                            let expression_result = async_with!(context => |ctx| {
-                                let result = ctx.eval::<bool, _>(expression.clone());
-
-                                // expression get value (for history)
-                                let expression_map = expression_get_value(expression.clone(), ctx).await;
-                                
-                                if result.is_err() {
-                                    let message = result.err().unwrap().to_string();
-                                    error!("[ScalingPlanner] Failed to evaluate expression\n{}\n\n{}", expression, message);
-                                    let message = format!("Failed to evaluate expression\n{}", expression);
-                                    return ExressionResult {
-                                        result: false,
-                                        error: true,
-                                        message: Some(message),
-                                        expression_values: Some(expression_map),
-                                    };
-                                }
-                                let result = result.unwrap();
-                                ExressionResult {
-                                    result,
-                                    error: false,
-                                    message: None,
-                                    expression_values: Some(expression_map),
+                        // Wait until the expression has been true for the "for" duration
+                        if let Some(for_duration) = plan_item.for_duration.as_ref() {
+                            let for_duration_value = match parse_for_duration(for_duration) {
+                                Ok(for_duration_value) => for_duration_value,
+                                Err(error) => {
+                                    error!("[ScalingPlanner] {}", error);
+                                    create_plan_log(
+                                        &plan_log_context,
+                                        plan_item,
+                                        None,
+                                        None,
+                                        Some(error.to_string()),
+                                        PlanLogStatus::Fail,
+                                    )
+                                    .await;
+                                    // Skip this plan
+                                    continue;
                                 }
-                            }).await;
-
-                            if let Some(expression_values) = expression_result.clone().expression_values {
-                                let mut expression_values = expression_values.clone();
-                                expression_value_map_for_history.append(&mut expression_values);
-                            }
-
-                            debug!(
-                                "[ScalingPlanner] expression result - {:?}",
-                                expression_result
+                            };
+                            let state = shared_plan_item_states.write().await.observe_true(
+                                &plan_item.id,
+                                for_duration_value,
+                                Utc::now(),
                             );
-
-
-                            // If the expression is false, move to the next plan
-                            if !expression_result.result {
-                                // If the expression is invalid, create a PlanLogDefinition for the error
-                                if expression_result.error {
+                            if let PlanItemState::Pending {
+                                since,
+                                transitioned,
+                            } = state
+                            {
+                                debug!(
+                                    "[ScalingPlanner] {} is pending since {}",
+                                    plan_item.id, since
+                                );
+                                // Only the transition to pending is recorded in the plan log
+                                if transitioned {
                                     create_plan_log(
-                                        &data_layer.clone(),
-                                        plan_db_id.clone(),
-                                        plan_id.clone(),
+                                        &plan_log_context,
                                         plan_item,
+                                        Some(&expression_value_map_for_history),
                                         None,
-                                        None,
-                                        expression_result.message,
-                                        plan_webhooks.clone(),
-                                        webhooks.clone(),
+                                        Some(format!("Pending until the expression is true for {} (since {})", for_duration, since.to_rfc3339())),
+                                        PlanLogStatus::Pending,
                                     )
                                     .await;
                                 }
@@ -513,31 +971,176 @@ impl<'a> ScalingPlanner {
                             }
                         }
 
-                        let results =
-                            run_plan_item(plan_item, &shared_scaling_component_manager, context.clone()).await;
+                        if is_fallback(plan_item) {
+                            info!("[ScalingPlanner] No plan item matched. Run the fallback plan item {}", plan_item.id);
+                            fallback_tracker.record_fired(&plan_item.id, Utc::now());
+                        }
+
+                        /*
+                         * Maintenance Window, Cool Down, Stabilization and Rate Limit Stages
+                         * The scaling components that are held back by a stage are not applied
+                         */
+                        let (stage_outcome, stage_logs) = run_plan_item_stages(
+                            plan_item,
+                            &stages,
+                            &sandbox,
+                            context.clone(),
+                            Utc::now(),
+                        )
+                        .await;
+                        for stage_log in stage_logs.iter() {
+                            if stage_log.status == PlanLogStatus::RateLimited {
+                                warn!("[ScalingPlanner] {} - {}", plan_item.id, stage_log.message);
+                            } else {
+                                info!("[ScalingPlanner] {} - {}", plan_item.id, stage_log.message);
+                            }
+                            create_plan_log(
+                                &plan_log_context,
+                                plan_item,
+                                Some(&expression_value_map_for_history),
+                                Some(&Ok(stage_log.scaling_component.clone())),
+                                Some(stage_log.message.clone()),
+                                stage_log.status.clone(),
+                            )
+                            .await;
+                        }
+                        let scaling_components = match stage_outcome {
+                            StageOutcome::Apply(scaling_components) => scaling_components,
+                            // If every scaling component is held back, the plan item is not applied in this interval
+                            StageOutcome::Held(stage) => {
+                                // The cron occurrence is consumed in a maintenance window so that it is not caught up after the window
+                                if let (Stage::MaintenanceWindow, Some(cron_occurrence)) =
+                                    (stage, cron_occurrence)
+                                {
+                                    save_cron_last_fired(
+                                        &plan_log_context,
+                                        &shared_cron_last_fired,
+                                        &plan_item.id,
+                                        cron_occurrence,
+                                        shadow,
+                                    )
+                                    .await;
+                                }
+                                excuted = true;
+                                if all_matching {
+                                    continue;
+                                }
+                                break;
+                            }
+                            // The params of the scaling components hit the limit of the sandbox
+                            StageOutcome::Failed(limit_error) => {
+                                error!("[ScalingPlanner] {} - {}", plan_item.id, limit_error);
+                                create_plan_log(
+                                    &plan_log_context,
+                                    plan_item,
+                                    Some(&expression_value_map_for_history),
+                                    None,
+                                    Some(format!("Failed to evaluate the params of the scaling components. {}", limit_error)),
+                                    PlanLogStatus::Fail,
+                                )
+                                .await;
+                                continue;
+                            }
+                        };
+
+                        /*
+                         * Approval Stage
+                         * The plan item with require_approval waits for a human approval as a pending action instead of being applied
+                         */
+                        if plan_item.require_approval.unwrap_or(false) && !shadow {
+                            let cool_down = get_plan_item_cool_down(plan_item, &plan_metadata);
+                            let requested = !is_rejection_cooling_down(
+                                &plan_log_context,
+                                plan_item,
+                                cool_down,
+                                Utc::now(),
+                            )
+                            .await
+                                && request_approval(
+                                    &plan_log_context,
+                                    plan_item,
+                                    scaling_components,
+                                    &expression_value_map_for_history,
+                                    pending_action_ttl,
+                                )
+                                .await;
+                            // The occurrence of the cron expression is not requested again
+                            if let (true, Some(cron_occurrence)) = (requested, cron_occurrence) {
+                                save_cron_last_fired(
+                                    &plan_log_context,
+                                    &shared_cron_last_fired,
+                                    &plan_item.id,
+                                    cron_occurrence,
+                                    shadow,
+                                )
+                                .await;
+                            }
+                            excuted = true;
+                            if all_matching {
+                                continue;
+                            }
+                            break;
+                        }
+
+                        // The target expressions of the scaling components (e.g. "$replicas * 2") have their own time limit
+                        sandbox.start_evaluation();
+                        let plan_item_results = run_plan_item(
+                            plan_item,
+                            scaling_components,
+                            &shared_scaling_component_manager,
+                            context.clone(),
+                            shadow,
+                        )
+                        .await;
+                        let results = plan_item_results.results();
+
+                        // Save the applied params for the stabilization windows
+                        {
+                            let mut stabilizer = shared_stabilizer.write().await;
+                            for params in results.iter().flatten() {
+                                stabilizer.record_applied(params);
+                            }
+                        }
+
+                        // Save the fired occurrence of the cron expression
+                        if let (false, Some(cron_occurrence)) =
+                            (results.is_empty(), cron_occurrence)
+                        {
+                            save_cron_last_fired(
+                                &plan_log_context,
+                                &shared_cron_last_fired,
+                                &plan_item.id,
+                                cron_occurrence,
+                                shadow,
+                            )
+                            .await;
+                        }
 
                         // update last plan timestamp
                         if !results.is_empty() {
-                            let mut shared_last_plan_timestamp =
-                                shared_last_plan_timestamp.write().await;
-                            *shared_last_plan_timestamp = Some(Utc::now());
-
+                            // Start the cool down of the applied scaling components. The failed ones are applied again in the next interval.
+                            let cool_down = get_plan_item_cool_down(plan_item, &plan_metadata);
                             {
-                                let mut shared_last_cool_down = shared_last_cool_down.write().await;
-                                // save sub cool down
-                                if let Some(sub_cool_down) = plan_item.cool_down {
-                                    *shared_last_cool_down = sub_cool_down;
-                                // save plan cool down
-                                } else if let Some(cool_down) = plan_metadata.get("cool_down") {
-                                    let Some(cool_down) = cool_down.as_u64() else {
-                                        error!("Failed to get cool_down (none) - {:?}", cool_down);
-                                        return;
-                                    };
-                                    *shared_last_cool_down = cool_down;
-                                } else {
-                                    *shared_last_cool_down = 0;
+                                let mut component_cool_downs =
+                                    shared_component_cool_downs.write().await;
+                                for component_id in get_applied_component_ids(results) {
+                                    component_cool_downs.record_applied(
+                                        component_id,
+                                        &plan_item.id,
+                                        cool_down,
+                                        Utc::now(),
+                                    );
                                 }
                             }
+
+                            // Count the action for max_actions of the plan and the applied scaling components
+                            record_rate_limited_actions(
+                                results,
+                                &plan_rate_limit,
+                                &shared_scaling_component_manager,
+                                shadow,
+                            )
+                            .await;
                         }
 
                         // Update the last run
@@ -548,97 +1151,333 @@ impl<'a> ScalingPlanner {
                             info!("[ScalingPlanner] Applied scaling plan: {}", scaling_plan_id);
                         }
 
-                        // Add the result of the scaling plan to the history
-                        for (_index, result) in results.iter().enumerate() {
-                            let fail_message: Option<String> = match result {
-                                Ok(_) => None,
-                                Err(error) => Some(error.to_string()),
-                            };
-                            let _scaling_components_metadata = &plan_item.scaling_components;
-
-                            // Create a PlanLogDefinition
-                            create_plan_log(
-                                &data_layer,
-                                scaling_plan_definition.db_id.clone(),
-                                scaling_plan_definition.id.clone(),
-                                plan_item,
-                                Some(&expression_value_map_for_history),
-                                Some(result),
-                                fail_message,
-                                plan_webhooks.clone(),
-                                webhooks.clone(),
+                        // Persist the runtime state of the planner
+                        // In shadow mode, nothing was applied, so the state is kept only in memory and does not delay the actions after the plan becomes active
+                        if persist_state && !shadow && !results.is_empty() {
+                            save_planner_state(
+                                &plan_log_context.data_layer,
+                                &plan_log_context.plan_id,
+                                &shared_last_run,
+                                &shared_component_cool_downs,
                             )
                             .await;
                         }
-                        // Stop the loop. We only want to execute one plan per interval.
+
+                        // Add the result of the scaling plan to the history
+                        create_plan_item_logs(
+                            &plan_log_context,
+                            plan_item,
+                            Some(&expression_value_map_for_history),
+                            &plan_item_results,
+                            shadow,
+                        )
+                        .await;
                         excuted = true;
+                        // In the all_matching execution mode, the next matching plan item is also executed
+                        if all_matching {
+                            continue;
+                        }
+                        // Stop the loop. We only want to execute one plan per interval.
                         break;
                     }
 
+                    fallback_tracker.end_tick(matched);
+                    // The plan items after the executed one were not evaluated, so their "for" durations start again
+                    shared_plan_item_states
+                        .write()
+                        .await
+                        .reset_unevaluated(&evaluated_plan_item_ids);
+
                     // If no plan was executed
                     if !excuted {
                         debug!("[ScalingPlanner] No scaling plan was executed");
                     }
                 }
-                // Wait for the next interval.
-                interval.tick().await;
             }
         });
         self.task = Some(task);
 
         // Run the action receiver
-        self.run_action_receiver();
+        self.run_action_receiver(action_plan_log_context, action_plan_rate_limit);
     }
-    fn run_action_receiver(&mut self) {
+    /**
+     * Run the manual actions of this plan in the action queue (POST /api/run-plan)
+     * - The receiver wakes up on a new action and polls the queue for the actions that were queued by other instances
+     * - The result of each action is saved in the action queue, the plan logs and the webhooks
+     */
+    fn run_action_receiver(
+        &mut self,
+        plan_log_context: PlanLogContext,
+        plan_rate_limit: PlanRateLimit,
+    ) {
         let mut receiver = self.data_layer.subscribe_action();
         let definition = self.definition.clone();
         let scaling_component_manager = self.scaling_component_manager.clone();
         let last_plan_id_by_action = self.last_plan_item_id_by_action.clone();
         let last_plan_timestamp_by_action = self.last_plan_timestamp_by_action.clone();
+        let shared_stabilizer = self.stabilizer.clone();
+        let shared_component_cool_downs = self.component_cool_downs.clone();
+        let shared_last_run = self.last_plan_item_id.clone();
+        let data_layer = self.data_layer.clone();
+        let persist_state = self.persist_state;
+        let shadow = self.is_shadow_mode();
+        let js_limits = self.js_limits;
         let action_task = tokio::spawn(async move {
-            let Ok(runtime) = rquickjs::AsyncRuntime::new() else {
-                error!("[ScalingPlanner] Error creating runtime");
-                return;
-            };
-            let Ok(context) = rquickjs::AsyncContext::full(&runtime).await else {
-                error!("[ScalingPlanner] Error creating context");
-                return;
-            };
-            while let action = receiver.recv().await {
-                if action.is_err() {
-                    continue;
+            let sandbox = match JsSandbox::new(js_limits).await {
+                Ok(sandbox) => sandbox,
+                Err(error) => {
+                    error!("[ScalingPlanner] Error creating runtime - {}", error);
+                    return;
                 }
-                let action = action.unwrap();
-
-                let (plan_id, plan_item_id) = match parse_action(action) {
-                    Ok((plan_id, plan_item_id)) => (plan_id, plan_item_id),
+            };
+            // The actions that were running when the scaling planner stopped (e.g. a reload or a leader change) never finish.
+            // They are not queued again because they may have been applied partially.
+            match data_layer
+                .fail_running_actions(
+                    &definition.id,
+                    "The scaling planner stopped while the action was running",
+                )
+                .await
+            {
+                Ok(0) => {}
+                Ok(count) => warn!(
+                    "[ScalingPlanner] {} running actions of {} are failed",
+                    count, definition.id
+                ),
+                Err(error) => error!(
+                    "[ScalingPlanner] Failed to fail the running actions - {}",
+                    error
+                ),
+            }
+            loop {
+                // Wake up on a new action. The queue is also polled for the actions that were queued by other instances.
+                let _ = tokio::time::timeout(ACTION_POLL_INTERVAL, receiver.recv()).await;
+                let actions = match data_layer.get_queued_actions(&definition.id).await {
+                    Ok(actions) => actions,
                     Err(error) => {
-                        error!("Failed to parse action: {}", error);
+                        error!(
+                            "[ScalingPlanner] Failed to get the queued actions - {}",
+                            error
+                        );
                         continue;
                     }
                 };
-                if plan_id != definition.id {
-                    continue;
-                }
-                let plan_item = definition.plans.iter().find(|plan| plan.id == plan_item_id);
+                for action in actions {
+                    // Another scaling planner of this plan may have started it
+                    match data_layer.start_action(&action.id).await {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(error) => {
+                            error!(
+                                "[ScalingPlanner] Failed to start the action {} - {}",
+                                action.id, error
+                            );
+                            continue;
+                        }
+                    }
+                    let plan_item_id = action.plan_item_id.clone();
+                    let Some(plan_item) = definition.plans.iter().find(|plan| plan.id == plan_item_id) else {
+                        error!("Failed to find plan_item: {}", plan_item_id);
+                        let error = format!("Failed to find plan_item: {}", plan_item_id);
+                        let _ = data_layer.finish_action(&action.id, ActionStatus::Failed, &[], Some(error)).await;
+                        continue;
+                    };
+
+                    // Merge the parameter overrides into the scaling components of the plan item
+                    let overrides = match action
+                        .overrides_json
+                        .as_deref()
+                        .map(serde_json::from_str::<ActionOverrides>)
+                    {
+                        None => ActionOverrides::new(),
+                        Some(Ok(overrides)) => overrides,
+                        Some(Err(error)) => {
+                            let error = format!("Invalid overrides - {}", error);
+                            let _ = data_layer
+                                .finish_action(&action.id, ActionStatus::Failed, &[], Some(error))
+                                .await;
+                            continue;
+                        }
+                    };
+                    let plan_item = match apply_overrides(plan_item, &overrides) {
+                        Ok(plan_item) => plan_item,
+                        Err(error) => {
+                            error!("[ScalingPlanner] {} - {}", plan_item_id, error);
+                            let _ = data_layer
+                                .finish_action(
+                                    &action.id,
+                                    ActionStatus::Failed,
+                                    &[],
+                                    Some(error.to_string()),
+                                )
+                                .await;
+                            continue;
+                        }
+                    };
+                    let plan_item = &plan_item;
+                    let mut plan_log_context = plan_log_context.clone();
+                    plan_log_context.overrides_json = action.overrides_json.clone();
+
+                    // The manual runs share max_actions with the interval runs. The action is not applied partially.
+                    let (_, rate_limited) = check_rate_limit(
+                        plan_item,
+                        &definition.id,
+                        &plan_rate_limit,
+                        &scaling_component_manager,
+                        Utc::now(),
+                    )
+                    .await;
+                    if !rate_limited.is_empty() {
+                        for (component_id, message) in rate_limited.iter() {
+                            warn!("[ScalingPlanner] {} - {}", plan_item_id, message);
+                            create_plan_log(
+                                &plan_log_context,
+                                plan_item,
+                                None,
+                                Some(&Ok(HashMap::from([(
+                                    "component_id".to_string(),
+                                    json!(component_id),
+                                )]))),
+                                Some(message.clone()),
+                                PlanLogStatus::RateLimited,
+                            )
+                            .await;
+                        }
+                        let error = rate_limited
+                            .into_iter()
+                            .map(|(_, message)| message)
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        let _ = data_layer
+                            .finish_action(&action.id, ActionStatus::Failed, &[], Some(error))
+                            .await;
+                        continue;
+                    }
 
-                if plan_item.is_none() {
-                    error!("Failed to find plan_item: {}", plan_item_id);
-                    continue;
-                }
+                    // A fresh context for every action
+                    let context = match sandbox.new_context().await {
+                        Ok(context) => context,
+                        Err(error) => {
+                            error!("[ScalingPlanner] Error creating context - {}", error);
+                            let error = format!("Error creating context - {}", error);
+                            let _ = data_layer
+                                .finish_action(&action.id, ActionStatus::Failed, &[], Some(error))
+                                .await;
+                            continue;
+                        }
+                    };
+                    // Nothing is applied if the params of the scaling components hit the limit of the sandbox
+                    sandbox.start_evaluation();
+                    let scaling_components =
+                        resolve_plan_item_params(plan_item, context.clone()).await;
+                    if let Some(limit_error) = sandbox.finish_evaluation(None) {
+                        error!("[ScalingPlanner] {} - {}", plan_item_id, limit_error);
+                        let error = format!(
+                            "Failed to evaluate the params of the scaling components. {}",
+                            limit_error
+                        );
+                        create_plan_log(
+                            &plan_log_context,
+                            plan_item,
+                            None,
+                            None,
+                            Some(error.clone()),
+                            PlanLogStatus::Fail,
+                        )
+                        .await;
+                        let _ = data_layer
+                            .finish_action(&action.id, ActionStatus::Failed, &[], Some(error))
+                            .await;
+                        continue;
+                    }
+
+                    // The target expressions of the scaling components (e.g. "$replicas * 2") have their own time limit
+                    sandbox.start_evaluation();
+                    let plan_item_results = run_plan_item(
+                        plan_item,
+                        scaling_components,
+                        &scaling_component_manager,
+                        context.clone(),
+                        shadow,
+                    )
+                    .await;
+                    let results = plan_item_results.results();
 
-                let plan_item = plan_item.unwrap();
-                let _results = run_plan_item(plan_item, &scaling_component_manager, context.clone()).await;
+                    // Save the applied params for the stabilization windows
+                    {
+                        let mut stabilizer = shared_stabilizer.write().await;
+                        for params in results.iter().flatten() {
+                            stabilizer.record_applied(params);
+                        }
+                    }
 
-                // Update the last run
-                {
-                    let mut shared_last_run = last_plan_id_by_action.write().await;
-                    *shared_last_run = plan_item_id.clone();
-                    debug!("[ScalingPlanner] Applied scaling plan: {}", plan_item_id);
+                    // The action also starts the cool down of the scaling components and uses max_actions
+                    if !results.is_empty() {
+                        record_rate_limited_actions(
+                            results,
+                            &plan_rate_limit,
+                            &scaling_component_manager,
+                            shadow,
+                        )
+                        .await;
+                        let cool_down = get_plan_item_cool_down(plan_item, &definition.metadata);
+                        let mut component_cool_downs = shared_component_cool_downs.write().await;
+                        for component_id in get_applied_component_ids(results) {
+                            component_cool_downs.record_applied(
+                                component_id,
+                                &plan_item.id,
+                                cool_down,
+                                Utc::now(),
+                            );
+                        }
+                    }
+
+                    // Update the last run
+                    {
+                        let mut shared_last_run = last_plan_id_by_action.write().await;
+                        *shared_last_run = plan_item_id.clone();
+                        debug!("[ScalingPlanner] Applied scaling plan: {}", plan_item_id);
+
+                        let mut shared_last_plan_timestamp_by_action =
+                            last_plan_timestamp_by_action.write().await;
+                        *shared_last_plan_timestamp_by_action = Some(Utc::now());
+                    }
 
-                    let mut shared_last_plan_timestamp_by_action =
-                        last_plan_timestamp_by_action.write().await;
-                    *shared_last_plan_timestamp_by_action = Some(Utc::now());
+                    // Persist the cool downs that were started by the action (not in shadow mode like the interval runs)
+                    if persist_state && !shadow && !results.is_empty() {
+                        save_planner_state(
+                            &data_layer,
+                            &definition.id,
+                            &shared_last_run,
+                            &shared_component_cool_downs,
+                        )
+                        .await;
+                    }
+
+                    // Save the result of the action like the automatic runs
+                    create_plan_item_logs(
+                        &plan_log_context,
+                        plan_item,
+                        None,
+                        &plan_item_results,
+                        shadow,
+                    )
+                    .await;
+                    let (result, error) = plan_item_results.to_action_result();
+                    let status = if error.is_none() {
+                        ActionStatus::Succeeded
+                    } else {
+                        ActionStatus::Failed
+                    };
+                    if let Err(error) = data_layer
+                        .finish_action(&action.id, status, &result, error)
+                        .await
+                    {
+                        error!(
+                            "[ScalingPlanner] Failed to save the result of the action {} - {}",
+                            action.id, error
+                        );
+                    }
                 }
             }
         });
@@ -661,13 +1500,72 @@ impl<'a> ScalingPlanner {
     }
     // For testing
     #[allow(dead_code)]
-    pub fn get_last_plan_timestamp(&self) -> Arc<RwLock<Option<DateTime<Utc>>>> {
-        self.last_plan_timestamp.clone()
+    pub fn get_component_cool_downs(&self) -> Arc<RwLock<ComponentCoolDowns>> {
+        self.component_cool_downs.clone()
     }
     // For testing
     #[allow(dead_code)]
-    pub fn get_last_cool_down(&self) -> Arc<RwLock<u64>> {
-        self.last_cool_down.clone()
+    pub fn get_plan_item_states(&self) -> Arc<RwLock<PlanItemStateTracker>> {
+        self.plan_item_states.clone()
+    }
+    // The plan logs and the webhooks of the plan. The approved pending actions use the same ones as the interval runs.
+    fn get_plan_log_context(&self) -> Result<PlanLogContext> {
+        Ok(PlanLogContext {
+            data_layer: self.data_layer.clone(),
+            plan_db_id: self.definition.db_id.clone(),
+            plan_id: self.definition.id.clone(),
+            plan_webhooks: get_plan_webhooks(&self.definition.metadata)?,
+            webhooks: self.webhooks.clone(),
+            variables_json: None,
+            overrides_json: None,
+        })
+    }
+    /**
+     * Record an approved pending action like an interval run of the plan item
+     * - The applied scaling components start the cool down of the plan item
+     * - The applied params are saved for the stabilization windows
+     */
+    pub async fn record_approved_action(
+        &self,
+        plan_item: &PlanItemDefinition,
+        applied_params: &[HashMap<String, Value>],
+        now: DateTime<Utc>,
+    ) {
+        if applied_params.is_empty() {
+            return;
+        }
+        let cool_down = get_plan_item_cool_down(plan_item, &self.definition.metadata);
+        {
+            let mut component_cool_downs = self.component_cool_downs.write().await;
+            let mut stabilizer = self.stabilizer.write().await;
+            for params in applied_params.iter() {
+                if let Some(component_id) = params.get("component_id").and_then(Value::as_str) {
+                    component_cool_downs.record_applied(
+                        component_id,
+                        &plan_item.id,
+                        cool_down,
+                        now,
+                    );
+                }
+                stabilizer.record_applied(params);
+            }
+        }
+        if self.persist_state {
+            save_planner_state(
+                &self.data_layer,
+                &self.definition.id,
+                &self.last_plan_item_id,
+                &self.component_cool_downs,
+            )
+            .await;
+        }
+    }
+    // max_actions of the plan is shared by the interval runs, the manual runs and the approvals
+    pub fn get_plan_rate_limit(&self) -> Result<PlanRateLimit> {
+        Ok(PlanRateLimit {
+            max_actions: MaxActions::from_metadata(&self.definition.metadata)?,
+            action_history: self.plan_action_history.clone(),
+        })
     }
     // For testing
     #[allow(dead_code)]
@@ -681,91 +1579,688 @@ impl<'a> ScalingPlanner {
     }
 }
 
+/**
+ * PlanItemResults
+ * - Independent: the result of each scaling component (a plan log for each)
+ * - Transactional: the result of the ordered execution with the rollback (a plan log for the whole sequence)
+ */
+enum PlanItemResults {
+    Independent(Vec<Result<HashMap<String, serde_json::Value>>>),
+    Transactional(TransactionResult),
+}
+
+impl PlanItemResults {
+    // The result of each scaling component. Only the scaling components that stay applied are Ok.
+    fn results(&self) -> &Vec<Result<HashMap<String, serde_json::Value>>> {
+        match self {
+            PlanItemResults::Independent(results) => results,
+            PlanItemResults::Transactional(transaction) => &transaction.results,
+        }
+    }
+
+    // The result of each scaling component for the action queue and the error if any of them failed
+    fn to_action_result(&self) -> (Vec<Value>, Option<String>) {
+        match self {
+            PlanItemResults::Transactional(transaction) => {
+                (transaction.steps.clone(), transaction.fail_message.clone())
+            }
+            PlanItemResults::Independent(results) => {
+                let mut errors = Vec::new();
+                let result = results
+                    .iter()
+                    .map(|result| match result {
+                        Ok(params) => json!({
+                            "component_id": params.get("component_id"),
+                            "params": params,
+                            "status": "applied",
+                        }),
+                        Err(error) => {
+                            errors.push(error.to_string());
+                            json!({ "status": "failed", "error": error.to_string() })
+                        }
+                    })
+                    .collect();
+                let error = if errors.is_empty() {
+                    None
+                } else {
+                    Some(errors.join(", "))
+                };
+                (result, error)
+            }
+        }
+    }
+}
+
+// Apply the resolved params of the scaling components of a plan item
 async fn run_plan_item(
     plan: &PlanItemDefinition,
+    scaling_components: Vec<HashMap<String, Value>>,
     shared_scaling_component_manager: &Arc<
         RwLock<crate::scaling_component::ScalingComponentManager>,
     >,
     context: rquickjs::AsyncContext,
-) -> Vec<Result<HashMap<String, serde_json::Value>>> {
+    shadow: bool,
+) -> PlanItemResults {
+    // Apply the scaling components in order and revert them on failure. In shadow mode, nothing is applied.
+    if is_transactional(plan) && !shadow {
+        let transaction = apply_transaction(
+            scaling_components,
+            shared_scaling_component_manager,
+            context,
+        )
+        .await;
+        debug!("[ScalingPlanner] transaction - {:?}", transaction);
+        return PlanItemResults::Transactional(transaction);
+    }
+
     // Apply the scaling components
-    let scaling_components_metadata = &plan.scaling_components;
     let results = apply_scaling_components(
-        scaling_components_metadata,
+        scaling_components,
         shared_scaling_component_manager,
         context,
+        shadow,
     )
     .await;
 
     debug!("[ScalingPlanner] results - {:?}", results);
 
-    results
+    PlanItemResults::Independent(results)
 }
 
+/**
+Skip the scaling components of a plan item that are cooling down
+- Returns the plan item with the scaling components to apply and the skipped component ids with the time left
+ */
+fn skip_cooling_down_components(
+    plan_item: &PlanItemDefinition,
+    component_cool_downs: &ComponentCoolDowns,
+    now: DateTime<Utc>,
+) -> (PlanItemDefinition, Vec<(String, chrono::Duration)>) {
+    let mut available_plan_item = plan_item.clone();
+    let mut skipped = Vec::new();
+    available_plan_item.scaling_components.retain(|metadata| {
+        let Some(component_id) = metadata["component_id"].as_str() else {
+            return true;
+        };
+        match component_cool_downs.get_time_left(component_id, now) {
+            Some(time_left) => {
+                skipped.push((component_id.to_string(), time_left));
+                false
+            }
+            None => true,
+        }
+    });
+    (available_plan_item, skipped)
+}
 
-async fn expression_get_value(
-    expression: String,
-    ctx: rquickjs::Ctx<'_>,
-) -> Vec<HashMap<String, Option<f64>>> {
-    let re_get_fn = regex::Regex::new(r"get\([^)]*\)").unwrap();
-    let re_varables = regex::Regex::new(r"\$[A-Za-z0-9\_]+").unwrap();
-    let expression = expression.replace('\n', "");
-    let mut expression_value_map: Vec<HashMap<String, Option<f64>>> = Vec::new();
-    let mut expression_arr = Vec::new();
-    // get function from expression
-    for cap in re_get_fn.find_iter(expression.as_str()) {
-        expression_arr.push(cap.as_str().to_string());
+/**
+Apply max_actions of the scaling plan and the scaling components to a plan item
+- If the scaling plan reached its max_actions, every scaling component is rate limited
+- Returns the plan item with the scaling components to apply and the rate-limited component ids with the reason
+ */
+fn rate_limit_plan_item(
+    plan_item: &PlanItemDefinition,
+    plan_id: &str,
+    plan_rate_limit: Option<(&MaxActions, &ActionHistory)>,
+    scaling_component_manager: &ScalingComponentManager,
+    now: DateTime<Utc>,
+) -> (PlanItemDefinition, Vec<(String, String)>) {
+    let mut available_plan_item = plan_item.clone();
+    let mut rate_limited = Vec::new();
+    let plan_wait_time = plan_rate_limit.and_then(|(max_actions, action_history)| {
+        action_history
+            .get_wait_time(max_actions, now)
+            .map(|wait_time| (max_actions, wait_time))
+    });
+    available_plan_item.scaling_components.retain(|metadata| {
+        let Some(component_id) = metadata["component_id"].as_str() else {
+            return true;
+        };
+        let message = if let Some((max_actions, wait_time)) = plan_wait_time {
+            format!(
+                "Rate limited because the scaling plan {} reached max_actions ({}). The next action is allowed in {} seconds.",
+                plan_id,
+                max_actions,
+                wait_time.num_seconds()
+            )
+        } else if let Some((max_actions, wait_time)) = scaling_component_manager.get_rate_limit(component_id, now) {
+            format!(
+                "Rate limited because the scaling component {} reached max_actions ({}). The next action is allowed in {} seconds.",
+                component_id,
+                max_actions,
+                wait_time.num_seconds()
+            )
+        } else {
+            return true;
+        };
+        rate_limited.push((component_id.to_string(), message));
+        false
+    });
+    (available_plan_item, rate_limited)
+}
+
+// Apply max_actions to a plan item with the shared budgets of the scaling plan and the scaling components
+async fn check_rate_limit(
+    plan_item: &PlanItemDefinition,
+    plan_id: &str,
+    plan_rate_limit: &PlanRateLimit,
+    shared_scaling_component_manager: &SharedScalingComponentManager,
+    now: DateTime<Utc>,
+) -> (PlanItemDefinition, Vec<(String, String)>) {
+    let plan_action_history = plan_rate_limit.action_history.read().await;
+    let scaling_component_manager = shared_scaling_component_manager.read().await;
+    rate_limit_plan_item(
+        plan_item,
+        plan_id,
+        plan_rate_limit
+            .max_actions
+            .as_ref()
+            .map(|max_actions| (max_actions, &*plan_action_history)),
+        &scaling_component_manager,
+        now,
+    )
+}
+
+/**
+ * Use the budgets of the scaling plan and the scaling components for a plan item before it is applied (e.g. an approval of a pending action)
+ * - The budgets are checked and used at once, so concurrent approvals can't exceed max_actions
+ * - Nothing is used if a budget is used up. Returns the rate limited scaling components with the messages.
+ */
+async fn reserve_rate_limit(
+    plan_item: &PlanItemDefinition,
+    plan_id: &str,
+    plan_rate_limit: &PlanRateLimit,
+    shared_scaling_component_manager: &SharedScalingComponentManager,
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    // The other approvals of the plan wait until the budget is used
+    let mut plan_action_history = plan_rate_limit.action_history.write().await;
+    let scaling_component_manager = shared_scaling_component_manager.read().await;
+    let check = |plan_action_history: &ActionHistory| {
+        let (_, rate_limited) = rate_limit_plan_item(
+            plan_item,
+            plan_id,
+            plan_rate_limit
+                .max_actions
+                .as_ref()
+                .map(|max_actions| (max_actions, plan_action_history)),
+            &scaling_component_manager,
+            now,
+        );
+        rate_limited
+    };
+    let rate_limited = check(&plan_action_history);
+    if !rate_limited.is_empty() {
+        return rate_limited;
     }
-    // variables from expression
-    for cap in re_varables.find_iter(expression.as_str()) {
-        expression_arr.push(cap.as_str().to_string());
+    let component_ids: Vec<&str> = plan_item
+        .scaling_components
+        .iter()
+        .filter_map(|metadata| metadata["component_id"].as_str())
+        .collect();
+    // Another plan may have used the budget of a scaling component after the check
+    if !scaling_component_manager.try_record_actions(&component_ids, now) {
+        return check(&plan_action_history);
     }
-    for value in expression_arr.iter() {
-        let get_value =  ctx.eval::<f64, _>(value.as_str());
-        let mut history_map = HashMap::new();
-        history_map.insert(value.as_str().to_string(), match get_value {
-            Ok(value) => Some(value),
-            Err(_) => None,
-        });
-        expression_value_map.append(&mut vec![history_map.clone()]);
+    if let Some(max_actions) = plan_rate_limit.max_actions.as_ref() {
+        plan_action_history.record(max_actions, now);
     }
-    expression_value_map
+    Vec::new()
 }
 
-async fn convert_js_expression(context: rquickjs::AsyncContext, expression: &str) -> serde_json::Value {
-    async_with!(context => |ctx| {
-        let Ok(result) = ctx.eval::<f64, _>(expression) else {
-            return serde_json::Value::from(expression);
-        };
-        serde_json::Value::from(result)
-    }).await
+// Give back the budgets of the reserved scaling components that were not applied. The budget of the scaling plan is given back if none of them were applied.
+async fn release_rate_limit(
+    component_ids: &[&str],
+    applied_component_ids: &[&str],
+    plan_rate_limit: &PlanRateLimit,
+    shared_scaling_component_manager: &SharedScalingComponentManager,
+    reserved_at: DateTime<Utc>,
+) {
+    if applied_component_ids.is_empty() {
+        plan_rate_limit.remove(reserved_at).await;
+    }
+    let scaling_component_manager = shared_scaling_component_manager.read().await;
+    for component_id in component_ids {
+        if !applied_component_ids.contains(component_id) {
+            scaling_component_manager.remove_action(component_id, reserved_at);
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::metric_updater::MetricUpdater;
-    use crate::scaling_component::ScalingComponentManager;
-    use data_layer::data_layer::DataLayer;
-    use data_layer::types::object_kind::ObjectKind;
-    use data_layer::MetricDefinition;
+// The ids of the scaling components that were applied (the Ok results)
+fn get_applied_component_ids(results: &[Result<HashMap<String, Value>>]) -> Vec<&str> {
+    results
+        .iter()
+        .flatten()
+        .filter_map(|params| params.get("component_id").and_then(Value::as_str))
+        .collect()
+}
 
-    use serde_json::json;
-    use tracing_test::traced_test;
-    use std::collections::HashMap;
-    use std::sync::Arc;
-    use tokio::sync::RwLock;
+// Count the applied scaling components of a plan item toward max_actions of the scaling plan and the scaling components
+// The failed scaling components do not use the budgets. In shadow mode, the budgets of the scaling components are not used because nothing is applied to them.
+async fn record_rate_limited_actions(
+    results: &[Result<HashMap<String, Value>>],
+    plan_rate_limit: &PlanRateLimit,
+    shared_scaling_component_manager: &SharedScalingComponentManager,
+    shadow: bool,
+) {
+    let applied_component_ids = get_applied_component_ids(results);
+    if applied_component_ids.is_empty() {
+        return;
+    }
+    plan_rate_limit.record(Utc::now()).await;
+    if shadow {
+        return;
+    }
+    let scaling_component_manager = shared_scaling_component_manager.read().await;
+    for component_id in applied_component_ids {
+        scaling_component_manager.record_action(component_id, Utc::now());
+    }
+}
 
-    const COLLECTOR: &str = "vector";
+/**
+Apply the stabilization windows of the behavior to the resolved params of the scaling components of a plan item
+- It returns the plan item with the stabilized params and the suppressed scaling components with the reason
+ */
+fn stabilize_plan_item(
+    resolved_plan_item: &PlanItemDefinition,
+    behavior: &ScalingBehavior,
+    stabilizer: &Stabilizer,
+    now: DateTime<Utc>,
+) -> (PlanItemDefinition, Vec<StageLog>) {
+    let mut stabilized_plan_item = resolved_plan_item.clone();
+    let mut scaling_components = Vec::new();
+    let mut suppressed = Vec::new();
+    for params in get_plan_item_params(resolved_plan_item) {
+        match stabilizer.stabilize(&params, behavior, now) {
+            Stabilization::Apply(params) => scaling_components.push(json!(params)),
+            Stabilization::Suppressed(message) => suppressed.push(StageLog {
+                scaling_component: params,
+                message,
+                status: PlanLogStatus::Suppressed,
+            }),
+        }
+    }
+    stabilized_plan_item.scaling_components = scaling_components;
+    (stabilized_plan_item, suppressed)
+}
+
+// Get the params of the scaling components of a plan item whose params are already resolved
+fn get_plan_item_params(resolved_plan_item: &PlanItemDefinition) -> Vec<HashMap<String, Value>> {
+    resolved_plan_item
+        .scaling_components
+        .iter()
+        .filter_map(|metadata| metadata.as_object())
+        .map(|metadata_object| {
+            metadata_object
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+        .collect()
+}
+
+/**
+ * PlanItemStages
+ * The state of a scaling plan that the stages of a matched plan item use (the scaling planner and the backtest)
+ */
+struct PlanItemStages<'a> {
+    plan_id: &'a str,
+    plan_behavior: Option<&'a ScalingBehavior>,
+    maintenance_windows: &'a [MaintenanceWindowDefinition],
+    component_cool_downs: &'a RwLock<ComponentCoolDowns>,
+    stabilizer: &'a RwLock<Stabilizer>,
+    plan_rate_limit: &'a PlanRateLimit,
+    scaling_component_manager: &'a SharedScalingComponentManager,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    MaintenanceWindow,
+    CoolDown,
+    Stabilization,
+    RateLimit,
+}
+
+/**
+ * StageOutcome
+ * The result of the stages of a matched plan item
+ */
+#[derive(Debug)]
+enum StageOutcome {
+    // The resolved params of the scaling components that passed every stage
+    Apply(Vec<HashMap<String, Value>>),
+    // Every scaling component was held back by the stage. The plan item takes the turn of the interval without being applied.
+    Held(Stage),
+    // The params of the scaling components hit the limit of the sandbox
+    Failed(String),
+}
+
+/**
+ * StageLog
+ * A scaling component that was held back by a stage
+ * - scaling_component: the component_id, or the resolved params for the stabilization windows
+ */
+#[derive(Debug)]
+struct StageLog {
+    scaling_component: HashMap<String, Value>,
+    message: String,
+    status: PlanLogStatus,
+}
+
+impl StageLog {
+    fn new(component_id: &str, message: String, status: PlanLogStatus) -> Self {
+        StageLog {
+            scaling_component: HashMap::from([("component_id".to_string(), json!(component_id))]),
+            message,
+            status,
+        }
+    }
+}
+
+/**
+Run the stages of a matched plan item before it is applied
+- The params of the scaling components are resolved once and saved as the recommendations of the stabilization windows
+  before any stage, so that a scaling component that is held back still has its recommendations
+1. Maintenance Window: the scaling components in an open maintenance window are not applied
+2. Cool Down: the scaling components that are cooling down are skipped
+3. Stabilization: the scaling components are suppressed by the stabilization windows of the behavior
+4. Rate Limit: the scaling components are not applied if the plan or the scaling component reached max_actions
+- The stages stop when every scaling component of the plan item is held back
+- Returns the outcome and the scaling components that were held back for the plan logs
+ */
+async fn run_plan_item_stages(
+    plan_item: &PlanItemDefinition,
+    stages: &PlanItemStages<'_>,
+    sandbox: &JsSandbox,
+    context: rquickjs::AsyncContext,
+    now: DateTime<Utc>,
+) -> (StageOutcome, Vec<StageLog>) {
+    let mut stage_logs = Vec::new();
+
+    // Nothing is applied if the params of the scaling components hit the limit of the sandbox
+    sandbox.start_evaluation();
+    let scaling_components = resolve_plan_item_params(plan_item, context).await;
+    if let Some(limit_error) = sandbox.finish_evaluation(None) {
+        return (StageOutcome::Failed(limit_error), stage_logs);
+    }
+    let behavior = ScalingBehavior::merge(plan_item.behavior.as_ref(), stages.plan_behavior);
+    {
+        let mut stabilizer = stages.stabilizer.write().await;
+        for params in scaling_components.iter() {
+            stabilizer.record_recommendation(params, &behavior, now);
+        }
+    }
+    let mut resolved_plan_item = plan_item.clone();
+    resolved_plan_item.scaling_components = scaling_components
+        .iter()
+        .map(|params| json!(params))
+        .collect();
+
+    let (plan_item_in_service, in_maintenance) = suppress_maintenance_window_components(
+        &resolved_plan_item,
+        stages.plan_id,
+        stages.maintenance_windows,
+        now,
+    );
+    for (component_id, message) in in_maintenance.iter() {
+        stage_logs.push(StageLog::new(
+            component_id,
+            message.clone(),
+            PlanLogStatus::Suppressed,
+        ));
+    }
+    if plan_item_in_service.scaling_components.is_empty() && !in_maintenance.is_empty() {
+        return (StageOutcome::Held(Stage::MaintenanceWindow), stage_logs);
+    }
+
+    let (available_plan_item, skipped) = {
+        let component_cool_downs = stages.component_cool_downs.read().await;
+        skip_cooling_down_components(&plan_item_in_service, &component_cool_downs, now)
+    };
+    for (component_id, time_left) in skipped.iter() {
+        let message = format!(
+            "Skipped because the scaling component {} is cooling down. {} seconds left.",
+            component_id,
+            time_left.num_seconds()
+        );
+        stage_logs.push(StageLog::new(component_id, message, PlanLogStatus::Skipped));
+    }
+    if available_plan_item.scaling_components.is_empty() && !skipped.is_empty() {
+        return (StageOutcome::Held(Stage::CoolDown), stage_logs);
+    }
+
+    let (stabilized_plan_item, suppressed) = {
+        let stabilizer = stages.stabilizer.read().await;
+        stabilize_plan_item(&available_plan_item, &behavior, &stabilizer, now)
+    };
+    let held = stabilized_plan_item.scaling_components.is_empty() && !suppressed.is_empty();
+    stage_logs.extend(suppressed);
+    if held {
+        return (StageOutcome::Held(Stage::Stabilization), stage_logs);
+    }
+
+    let (available_plan_item, rate_limited) = check_rate_limit(
+        &stabilized_plan_item,
+        stages.plan_id,
+        stages.plan_rate_limit,
+        stages.scaling_component_manager,
+        now,
+    )
+    .await;
+    for (component_id, message) in rate_limited.iter() {
+        stage_logs.push(StageLog::new(
+            component_id,
+            message.clone(),
+            PlanLogStatus::RateLimited,
+        ));
+    }
+    if available_plan_item.scaling_components.is_empty() && !rate_limited.is_empty() {
+        return (StageOutcome::Held(Stage::RateLimit), stage_logs);
+    }
+    (
+        StageOutcome::Apply(get_plan_item_params(&available_plan_item)),
+        stage_logs,
+    )
+}
+
+/**
+ * PlanItemEvaluation
+ * The result of evaluating the cron expression and the JS expression of a plan item
+ */
+#[derive(Debug, Clone)]
+enum PlanItemEvaluation {
+    NotMatched,
+    Failed(String),
+    // The values of get() and the variables in the expression (for history) and the occurrence of the cron expression to fire
+    Matched(Vec<HashMap<String, Option<f64>>>, Option<DateTime<Utc>>),
+}
+
+/**
+Evaluate a plan item at the given time
+1. Cron Expression (if the next datetime is not within cron_window_ms from now, it's not matched)
+2. JS Expression (if it's false, it's not matched)
+- plan_timezone: the timezone in the plan metadata for the cron expression
+- cron_last_fired: the last fired occurrence of the cron expression to catch up the missed occurrences
+ */
+async fn evaluate_plan_item(
+    plan_item: &PlanItemDefinition,
+    context: &rquickjs::AsyncContext,
+    now: DateTime<Utc>,
+    cron_window_ms: i64,
+    plan_timezone: Option<&str>,
+    cron_last_fired: Option<DateTime<Utc>>,
+) -> PlanItemEvaluation {
+    if plan_item.cron_expression.is_none() && plan_item.expression.is_none() {
+        // A fallback plan item without conditions runs whenever it is due
+        if is_fallback(plan_item) {
+            return PlanItemEvaluation::Matched(Vec::new(), None);
+        }
+        error!("[ScalingPlanner] Both cron_expression and expression are empty");
+        return PlanItemEvaluation::NotMatched;
+    }
+    /*
+     * 1. Cron Expression
+     */
+    let mut cron_occurrence: Option<DateTime<Utc>> = None;
+    if let Some(cron_expression) = plan_item.cron_expression.as_ref() {
+        if cron_expression.is_empty() {
+            error!("[ScalingPlanner] cron_expression is empty");
+            return PlanItemEvaluation::NotMatched;
+        }
+        debug!("[ScalingPlanner] cron_expression - {}", cron_expression);
+        // The timezone of the plan item overrides the timezone of the plan
+        let timezone = plan_item.timezone.as_deref().or(plan_timezone);
+        let schedule = CronSchedule::parse(cron_expression.as_str(), timezone);
+        if let Err(error) = schedule {
+            error!("[ScalingPlanner] {}", error);
+            return PlanItemEvaluation::Failed(error.to_string());
+        }
+        let schedule = schedule.unwrap();
+        // The missed occurrences are caught up by the catch_up policy
+        let catch_up = plan_item.catch_up.unwrap_or_default();
+        let Some(occurrence) = schedule.get_due_occurrence(&now, cron_window_ms, cron_last_fired, catch_up) else {
+            info!("[ScalingPlanner] The datetime is not yet reached for cron expression: {}", cron_expression);
+            return PlanItemEvaluation::NotMatched;
+        };
+        // It's confirmed that the cron expression is valid and the datetime is reached
+        cron_occurrence = Some(occurrence);
+    }
+
+    /*
+     * 2. JS Expression
+     */
+    let mut expression_value_map_for_history: Vec<HashMap<String, Option<f64>>> = Vec::new();
+
+    if let Some(expression) = plan_item.expression.as_ref() {
+        if expression.is_empty() {
+            error!("[ScalingPlanner] expression is empty");
+            return PlanItemEvaluation::NotMatched;
+        }
+        debug!("[ScalingPlanner] expression\n{}", expression);
+        // Evaluate the expression.
+        let expression_result = async_with!(context => |ctx| {
+            let result = ctx.eval::<bool, _>(expression.clone()).catch(ctx);
+
+            // expression get value (for history)
+            let expression_map = expression_get_value(expression.clone(), ctx).await;
+
+            if result.is_err() {
+                let message = result.err().unwrap().to_string();
+                error!("[ScalingPlanner] Failed to evaluate expression\n{}\n\n{}", expression, message);
+                let message = format!("Failed to evaluate expression\n{}\n\n{}", expression, message);
+                return ExressionResult {
+                    result: false,
+                    error: true,
+                    message: Some(message),
+                    expression_values: Some(expression_map),
+                };
+            }
+            let result = result.unwrap();
+            ExressionResult {
+                result,
+                error: false,
+                message: None,
+                expression_values: Some(expression_map),
+            }
+        }).await;
+
+        if let Some(mut expression_values) = expression_result.clone().expression_values {
+            expression_value_map_for_history.append(&mut expression_values);
+        }
+
+        debug!(
+            "[ScalingPlanner] expression result - {:?}",
+            expression_result
+        );
+
+        // If the expression is false, the plan item is not matched
+        if !expression_result.result {
+            if expression_result.error {
+                return PlanItemEvaluation::Failed(expression_result.message.unwrap_or_default());
+            }
+            return PlanItemEvaluation::NotMatched;
+        }
+    }
+
+    PlanItemEvaluation::Matched(expression_value_map_for_history, cron_occurrence)
+}
+
+async fn expression_get_value(
+    expression: String,
+    ctx: rquickjs::Ctx<'_>,
+) -> Vec<HashMap<String, Option<f64>>> {
+    let re_get_fn = regex::Regex::new(r"get\([^)]*\)").unwrap();
+    let re_varables = regex::Regex::new(r"\$[A-Za-z0-9\_]+").unwrap();
+    let expression = expression.replace('\n', "");
+    let mut expression_value_map: Vec<HashMap<String, Option<f64>>> = Vec::new();
+    let mut expression_arr = Vec::new();
+    // get function from expression
+    for cap in re_get_fn.find_iter(expression.as_str()) {
+        expression_arr.push(cap.as_str().to_string());
+    }
+    // variables from expression
+    for cap in re_varables.find_iter(expression.as_str()) {
+        expression_arr.push(cap.as_str().to_string());
+    }
+    for value in expression_arr.iter() {
+        let get_value = ctx.eval::<f64, _>(value.as_str());
+        let mut history_map = HashMap::new();
+        history_map.insert(
+            value.as_str().to_string(),
+            match get_value {
+                Ok(value) => Some(value),
+                Err(_) => None,
+            },
+        );
+        expression_value_map.append(&mut vec![history_map.clone()]);
+    }
+    expression_value_map
+}
+
+async fn convert_js_expression(
+    context: rquickjs::AsyncContext,
+    expression: &str,
+) -> serde_json::Value {
+    async_with!(context => |ctx| {
+        let Ok(result) = ctx.eval::<f64, _>(expression) else {
+            return serde_json::Value::from(expression);
+        };
+        serde_json::Value::from(result)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::js_functions::get_in_js;
+    use crate::metric_updater::MetricUpdater;
+    use crate::scaling_component::ScalingComponentManager;
+    use data_layer::data_layer::DataLayer;
+    use data_layer::types::cron_schedule::CatchUpPolicy;
+    use data_layer::types::pending_action::PendingActionStatus;
+    use data_layer::types::plan_item_definition::ApplyMode;
+    use data_layer::types::object_kind::ObjectKind;
+    use data_layer::MetricDefinition;
+    use data_layer::ScalingComponentDefinition;
+
+    use serde_json::json;
+    use tracing_test::traced_test;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    const COLLECTOR: &str = "vector";
     const METRIC_DEFINTION_ID: &str = "metric1";
 
-    async fn get_scaling_planner_with_variables(
+    async fn get_scaling_planner_with_sql_url(
+        sql_url: &str,
         plans: Vec<PlanItemDefinition>,
         variables: HashMap<String, serde_json::Value>,
         plan_metadata: HashMap<String, serde_json::Value>,
     ) -> (Arc<DataLayer>, ScalingPlanner) {
         // Initialize DataLayer
-        let data_layer = DataLayer::new("", 500_000, false).await;
+        let data_layer = DataLayer::new(sql_url, 500_000, false).await;
         data_layer.sync("").await;
         let data_layer = Arc::new(data_layer);
         // Create a MetricDefinition
@@ -796,6 +2291,7 @@ mod tests {
             metadata: plan_metadata,
             plans,
             enabled: true,
+            behavior: None,
         };
 
         let scaling_planner = ScalingPlanner::new(
@@ -804,9 +2300,17 @@ mod tests {
             scaling_component_manager,
             data_layer.clone(),
             None,
+            false,
         );
         (data_layer, scaling_planner)
     }
+    async fn get_scaling_planner_with_variables(
+        plans: Vec<PlanItemDefinition>,
+        variables: HashMap<String, serde_json::Value>,
+        plan_metadata: HashMap<String, serde_json::Value>,
+    ) -> (Arc<DataLayer>, ScalingPlanner) {
+        get_scaling_planner_with_sql_url("", plans, variables, plan_metadata).await
+    }
     async fn get_scaling_planner(
         plans: Vec<PlanItemDefinition>,
         plan_metadata: HashMap<String, serde_json::Value>,
@@ -903,7 +2407,7 @@ mod tests {
     #[tokio::test]
     async fn test_get_in_js() {
         // Initialize DataLayer
-        let data_layer = DataLayer::new("", 500_000, false).await;
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
         data_layer.sync("").await;
         let data_layer = Arc::new(data_layer);
 
@@ -1096,33 +2600,42 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_last_plan_timestamp() {
+    async fn test_last_component_action() {
         let plan_id = uuid::Uuid::new_v4().to_string();
         // Create a ScalingPlanner
         let (_, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
-                expression: None,
                 cron_expression: Some("*/2 * * * * * *".to_string()),
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![json!({"component_id": "test_component_id"})],
-                ui: None,
+                ..Default::default()
             }],
             HashMap::new(),
         )
         .await;
 
+        scaling_planner
+            .scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: "test_component_id".to_string(),
+                component_kind: "wa-logger".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
         scaling_planner.run();
 
         // Wait for the scaling planner to execute the plan
         tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
         {
             scaling_planner.stop();
-            let after_last_plan_timestamp = scaling_planner.get_last_plan_timestamp().clone();
-            let shared_after_last_plan_timestamp = after_last_plan_timestamp.read().await;
-            assert!(shared_after_last_plan_timestamp.is_some());
+            let component_cool_downs = scaling_planner.get_component_cool_downs();
+            let component_cool_downs = component_cool_downs.read().await;
+            assert!(component_cool_downs
+                .get_last_action("test_component_id")
+                .is_some());
         }
     }
 
@@ -1133,15 +2646,12 @@ mod tests {
         let (data_layer, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
                 expression: Some(
                     "get({ metric_id: 'metric1', stats: 'max', period_sec: 120, name: 'test', tags: { tag1: 'value1'}}) > 0".to_string()
                 ),
-                cron_expression: None,
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             HashMap::new(),
         ).await;
@@ -1178,13 +2688,10 @@ mod tests {
         let (data_layer, mut scaling_planner) = get_scaling_planner_with_variables(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
                 expression: Some("$test_variable / $number_value == 2 && $boolean_value && $string_value == \"string\" ".to_string()),
-                cron_expression: None,
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             [
                 // Define a numeric variable
@@ -1252,13 +2759,10 @@ mod tests {
         let (data_layer, mut scaling_planner) = get_scaling_planner_with_variables(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
                 expression: Some("$test_variable == 10".to_string()),
-                cron_expression: None,
                 priority: 1,
-                cool_down: None,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             [
                 // Define a numeric variable
@@ -1308,13 +2812,10 @@ mod tests {
         let (data_layer, mut scaling_planner) = get_scaling_planner_with_variables(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
                 expression: Some("$test_variable2".to_string()),
-                cron_expression: None,
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             [
                 // Define a boolean variable
@@ -1362,13 +2863,10 @@ mod tests {
         let (data_layer, mut scaling_planner) = get_scaling_planner_with_variables(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
                 expression: Some("$test_variable3 == 'string variable'".to_string()),
-                cron_expression: None,
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             [
                 // Define a string variable
@@ -1412,13 +2910,10 @@ mod tests {
         let (_, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
-                expression: None,
                 cron_expression: Some("*/2 * * * * * *".to_string()),
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             HashMap::new(),
         )
@@ -1440,15 +2935,13 @@ mod tests {
         let (data_layer, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
                 expression: Some(
                     "get({ metric_id: 'metric1', stats: 'max', period_sec: 120, name: 'test', tags: { tag1: 'value1'}}) > 0".to_string()
                 ),
                 cron_expression: Some("*/2 * * * * * *".to_string()),
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             HashMap::new(),
         ).await;
@@ -1484,15 +2977,13 @@ mod tests {
         let (data_layer, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
                 expression: Some(
                     "get({ metric_id: 'metric1', stats: 'avg', period_sec: 0, name: 'test', tags: { tag1: 'value1'}}) > 0".to_string()
                 ),
                 cron_expression: Some("*/2 * * * * * *".to_string()),
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             HashMap::new(),
         ).await;
@@ -1528,15 +3019,13 @@ mod tests {
         let (data_layer, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
                 expression: Some(
                     "get({ metric_id: 'metric1', stats: 'avg', period_sec: 60, name: 'test', tags: { tag1: 'value1'}}) > 0".to_string()
                 ),
                 cron_expression: Some("* * * * * * 2007".to_string()),
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             HashMap::new(),
         ).await;
@@ -1572,13 +3061,9 @@ mod tests {
         let (_, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: plan_id.clone(),
-                description: None,
-                expression: None,
-                cron_expression: None,
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             HashMap::new(),
         )
@@ -1597,25 +3082,36 @@ mod tests {
     #[tokio::test]
     async fn test_run_action_receiver() {
         let plan_item_id = uuid::Uuid::new_v4().to_string();
-        // Create a ScalingPlanner
-        let (data_layer, mut scaling_planner) = get_scaling_planner(
+        // Create a ScalingPlanner with its own action queue
+        let (data_layer, mut scaling_planner) = get_scaling_planner_with_sql_url(
+            "sqlite::memory:",
             vec![PlanItemDefinition {
                 id: plan_item_id.clone(),
-                description: None,
-                expression: None,
-                cron_expression: None,
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![],
-                ui: None,
+                ..Default::default()
             }],
             HashMap::new(),
+            HashMap::new(),
         )
         .await;
+        // An action that was running when the previous scaling planner stopped
+        let stale_action = data_layer
+            .send_plan_action(
+                scaling_planner.definition.id.clone(),
+                plan_item_id.clone(),
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        assert!(data_layer.start_action(&stale_action.id).await.unwrap());
         scaling_planner.run();
 
         let plan_id = scaling_planner.definition.id.clone();
-        let _ = data_layer.send_plan_action(plan_id, plan_item_id.clone());
+        let action = data_layer
+            .send_plan_action(plan_id, plan_item_id.clone(), &HashMap::new())
+            .await
+            .unwrap();
 
         // Wait for the scaling planner to execute the plan
         tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
@@ -1624,6 +3120,105 @@ mod tests {
             let shared_last_plan_item_id = last_plan_item_id.read().await;
             assert_eq!(*shared_last_plan_item_id, plan_item_id);
         }
+        let action = data_layer.get_action(&action.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Succeeded.to_string());
+        let stale_action = data_layer
+            .get_action(&stale_action.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stale_action.status, ActionStatus::Failed.to_string());
+        assert_eq!(
+            stale_action.error,
+            Some("The scaling planner stopped while the action was running".to_string())
+        );
+
+        // An action of an unknown plan item fails
+        let action = data_layer
+            .send_plan_action(
+                scaling_planner.definition.id.clone(),
+                "unknown".to_string(),
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let action = data_layer.get_action(&action.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Failed.to_string());
+        assert_eq!(
+            action.error,
+            Some("Failed to find plan_item: unknown".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_action_receiver_with_overrides() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": 2}),
+                ],
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        scaling_planner.definition.id = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        let plan_id = scaling_planner.definition.id.clone();
+        let overrides: ActionOverrides = HashMap::from([(
+            "test_component_id".to_string(),
+            HashMap::from([("replicas".to_string(), json!(40))]),
+        )]);
+        let action = data_layer
+            .send_plan_action(plan_id.clone(), plan_item_id.clone(), &overrides)
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        let action = data_layer.get_action(&action.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Succeeded.to_string());
+
+        // The plan log has the overridden params and the overrides
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some(plan_id.clone()), from_date, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(plan_logs.len(), 1);
+        let params: HashMap<String, Value> =
+            serde_json::from_str(plan_logs[0].metadata_values_json.as_str()).unwrap();
+        assert_eq!(params.get("replicas").and_then(Value::as_f64), Some(40.0));
+        let overrides_json: ActionOverrides =
+            serde_json::from_str(plan_logs[0].overrides_json.as_ref().unwrap()).unwrap();
+        assert_eq!(overrides_json, overrides);
+
+        // An override of a scaling component that is not in the plan item fails the action
+        let overrides: ActionOverrides = HashMap::from([(
+            "unknown".to_string(),
+            HashMap::from([("replicas".to_string(), json!(40))]),
+        )]);
+        let action = data_layer
+            .send_plan_action(plan_id, plan_item_id.clone(), &overrides)
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        scaling_planner.stop();
+        let action = data_layer.get_action(&action.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Failed.to_string());
+        assert_eq!(
+            action.error,
+            Some(format!(
+                "Scaling component unknown is not in plan_item: {}",
+                plan_item_id
+            ))
+        );
     }
 
     #[tokio::test]
@@ -1639,27 +3234,38 @@ mod tests {
         let (_, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: "plan_1".to_string(),
-                description: None,
                 expression: Some("2>1".to_string()),
-                cron_expression: None,
                 cool_down: Some(2),
                 priority: 1,
                 scaling_components: vec![json!({"component_id": "test_component_id"})],
-                ui: None,
+                ..Default::default()
             }],
             plan_metadata,
         )
         .await;
+        scaling_planner
+            .scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: "test_component_id".to_string(),
+                component_kind: "wa-logger".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
         scaling_planner.run();
 
-        // sec 0: [cool_down: 0]
-        // sec 1: plan_1 is executed -> [sub cool_down 2]
-
-        let sec_0_cool_down = *scaling_planner.get_last_cool_down().read().await;
-        assert_eq!(sec_0_cool_down, 0);
+        // sec 0: plan_1 is executed -> [sub cool_down 2] of the scaling component
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-        let sec_1_cool_down = *scaling_planner.get_last_cool_down().read().await;
-        assert_eq!(sec_1_cool_down, 2);
+        let component_cool_downs = scaling_planner.get_component_cool_downs();
+        let component_cool_downs = component_cool_downs.read().await;
+        let action = component_cool_downs
+            .get_last_action("test_component_id")
+            .unwrap();
+        assert_eq!(
+            (action.cool_down_until - action.applied_at).num_seconds(),
+            2
+        );
 
         scaling_planner.stop();
     }
@@ -1677,29 +3283,1606 @@ mod tests {
         let (_, mut scaling_planner) = get_scaling_planner(
             vec![PlanItemDefinition {
                 id: "plan_1".to_string(),
-                description: None,
                 expression: Some("2>1".to_string()),
-                cron_expression: None,
-                cool_down: None,
                 priority: 1,
                 scaling_components: vec![json!({"component_id": "test_component_id"})],
-                ui: None,
+                ..Default::default()
             }],
             plan_metadata,
         )
         .await;
+        scaling_planner
+            .scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: "test_component_id".to_string(),
+                component_kind: "wa-logger".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
         scaling_planner.run();
 
-        // sec 0: [cool_down: 0]
-        // sec 1: plan_1 is executed -> [default cool_down 1]
-
-        let sec_0_cool_down = *scaling_planner.get_last_cool_down().read().await;
-        assert_eq!(sec_0_cool_down, 0);
+        // sec 0: plan_1 is executed -> [default cool_down 1] of the scaling component
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-        let sec_1_cool_down = *scaling_planner.get_last_cool_down().read().await;
-        assert_eq!(sec_1_cool_down, 1);
+        let component_cool_downs = scaling_planner.get_component_cool_downs();
+        let component_cool_downs = component_cool_downs.read().await;
+        let action = component_cool_downs
+            .get_last_action("test_component_id")
+            .unwrap();
+        assert_eq!(
+            (action.cool_down_until - action.applied_at).num_seconds(),
+            1
+        );
+
+        scaling_planner.stop();
+    }
+
+    #[tokio::test]
+    async fn test_pause_plan() {
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: "plan_1".to_string(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                scaling_components: vec![json!({"component_id": "test_component_id"})],
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        scaling_planner.definition.id = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let plan_id = scaling_planner.definition.id.clone();
+        data_layer.pause_plan(&plan_id, None).await.unwrap();
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        // The paused plan is not evaluated
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some(plan_id.clone()), from_date, Utc::now())
+            .await
+            .unwrap();
+        assert!(plan_logs.is_empty());
 
+        // The running planner evaluates the plan again after it is resumed
+        data_layer.resume_plan(&plan_id).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        scaling_planner.stop();
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some(plan_id), from_date, Utc::now())
+            .await
+            .unwrap();
+        assert!(!plan_logs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_window() {
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: "plan_1".to_string(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                scaling_components: vec![json!({"component_id": "test_component_id"})],
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        scaling_planner.definition.id = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let plan_id = scaling_planner.definition.id.clone();
+        // A window that opens every minute for 2 minutes is always open
+        scaling_planner.set_maintenance_windows(vec![serde_json::from_value(json!({
+            "id": "always",
+            "cron_expression": "* * * * *",
+            "duration": "2m",
+            "component_ids": ["test_component_id"],
+        }))
+        .unwrap()]);
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        scaling_planner.stop();
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some(plan_id), from_date, Utc::now())
+            .await
+            .unwrap();
+        assert!(!plan_logs.is_empty());
+        for plan_log in plan_logs.iter() {
+            assert_eq!(plan_log.status, Some(PlanLogStatus::Suppressed.to_string()));
+            assert!(plan_log.fail_message.as_ref().is_some_and(
+                |message| message.starts_with("Suppressed by maintenance window always until")
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shadow_mode() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        // Create a ScalingPlanner in shadow mode
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                // The component does not exist, so applying it would fail
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": "1 + 2"}),
+                ],
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        // Wait for the scaling planner to execute the plan
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_log = plan_logs
+            .iter()
+            .find(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .expect("plan log in shadow mode");
+        assert_eq!(plan_log.status, Some(PlanLogStatus::Shadow.to_string()));
+        assert!(plan_log.fail_message.is_none());
+        // The params are resolved as if they were applied
+        let params: HashMap<String, Value> =
+            serde_json::from_str(plan_log.metadata_values_json.as_str()).unwrap();
+        assert_eq!(params.get("replicas").and_then(Value::as_f64), Some(3.0));
+    }
+
+    #[tokio::test]
+    async fn test_shadow_mode_state() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        // The other tests save the state of the plan "test" in the on-disk database
+        let (data_layer, mut scaling_planner) = get_scaling_planner_with_sql_url(
+            "sqlite::memory:",
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                cron_expression: Some("@daily".to_string()),
+                cool_down: Some(100),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": 3}),
+                ],
+                catch_up: Some(CatchUpPolicy::Latest),
+                ..Default::default()
+            }],
+            HashMap::new(),
+            plan_metadata,
+        )
+        .await;
+        let last_fired = Utc::now() - chrono::Duration::days(2);
+        data_layer
+            .set_cron_last_fired("test", plan_item_id.as_str(), last_fired)
+            .await
+            .unwrap();
+        scaling_planner.set_persist_state(true);
+        scaling_planner.run();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
         scaling_planner.stop();
+
+        // The simulated action starts the cool down only in memory
+        let component_cool_downs = scaling_planner.get_component_cool_downs();
+        assert!(component_cool_downs
+            .read()
+            .await
+            .get_time_left("test_component_id", Utc::now())
+            .is_some());
+        // Nothing is persisted for the plan after it becomes active
+        assert!(data_layer
+            .get_planner_state("test")
+            .await
+            .unwrap()
+            .is_none());
+        let cron_last_fired = data_layer.get_cron_last_fired("test").await.unwrap();
+        let cron_last_fired = cron_last_fired.get(plan_item_id.as_str()).unwrap();
+        assert!(*cron_last_fired < Utc::now() - chrono::Duration::days(1));
+    }
+
+    #[tokio::test]
+    async fn test_require_approval() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                // The component does not exist, so applying it would fail
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": "1 + 2"}),
+                ],
+                require_approval: Some(true),
+                ..Default::default()
+            }],
+            HashMap::new(),
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+        // 2 intervals: 0s, 1s
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        scaling_planner.stop();
+
+        // Only one pending action while it waits for the approval
+        let pending_actions = data_layer.get_pending_actions(None).await.unwrap();
+        let pending_actions = pending_actions
+            .iter()
+            .filter(|pending_action| pending_action.plan_item_id == plan_item_id)
+            .collect::<Vec<_>>();
+        assert_eq!(pending_actions.len(), 1);
+        assert_eq!(pending_actions[0].status, "PENDING");
+        let params: Vec<HashMap<String, Value>> =
+            serde_json::from_str(pending_actions[0].scaling_components_json.as_str()).unwrap();
+        assert_eq!(params[0].get("replicas").and_then(Value::as_f64), Some(3.0));
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_logs = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(plan_logs.len(), 1);
+        assert_eq!(
+            plan_logs[0].status,
+            Some(PlanLogStatus::PendingApproval.to_string())
+        );
+        assert!(plan_logs[0]
+            .fail_message
+            .as_ref()
+            .unwrap()
+            .contains(pending_actions[0].id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_reject_pending_action() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                cool_down: Some(60),
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": 3}),
+                ],
+                require_approval: Some(true),
+                ..Default::default()
+            }],
+            HashMap::new(),
+        )
+        .await;
+        scaling_planner.run();
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let pending_actions = data_layer
+            .get_pending_actions(Some(PendingActionStatus::Pending))
+            .await
+            .unwrap();
+        let pending_action = pending_actions
+            .iter()
+            .find(|pending_action| pending_action.plan_item_id == plan_item_id)
+            .unwrap();
+        assert!(data_layer
+            .update_pending_action_status(
+                pending_action.id.as_str(),
+                PendingActionStatus::Pending,
+                PendingActionStatus::Rejected
+            )
+            .await
+            .unwrap());
+
+        // The expression is still true, but the rejected plan item is cooling down
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        scaling_planner.stop();
+        let pending_actions = data_layer.get_pending_actions(None).await.unwrap();
+        let pending_actions = pending_actions
+            .iter()
+            .filter(|pending_action| pending_action.plan_item_id == plan_item_id)
+            .collect::<Vec<_>>();
+        assert_eq!(pending_actions.len(), 1);
+        assert_eq!(
+            pending_actions[0].status,
+            PendingActionStatus::Rejected.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plan_variables_in_plan_log() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        // The variables depend on each other regardless of the order of the keys
+        let (data_layer, mut scaling_planner) = get_scaling_planner_with_variables(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("$is_high && $label == 'replicas-4'".to_string()),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": "$replicas"}),
+                ],
+                ..Default::default()
+            }],
+            [
+                ("is_high".to_string(), json!("$replicas > $limits.min")),
+                ("replicas".to_string(), json!("$base * 2")),
+                ("base".to_string(), json!(2)),
+                ("label".to_string(), json!("'replicas-' + $replicas")),
+                ("limits".to_string(), json!({ "min": 1 })),
+            ]
+            .into_iter()
+            .collect(),
+            plan_metadata,
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        // Wait for the scaling planner to execute the plan
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_log = plan_logs
+            .iter()
+            .find(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .expect("plan log with the variables");
+        assert_eq!(plan_log.status, Some(PlanLogStatus::Shadow.to_string()));
+        let variables: Value =
+            serde_json::from_str(plan_log.variables_json.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            variables,
+            json!({
+                "$base": 2,
+                "$is_high": true,
+                "$label": "replicas-4",
+                "$limits": { "min": 1 },
+                "$replicas": 4,
+            })
+        );
+        let params: HashMap<String, Value> =
+            serde_json::from_str(plan_log.metadata_values_json.as_str()).unwrap();
+        assert_eq!(params.get("replicas").and_then(Value::as_f64), Some(4.0));
+    }
+
+    #[tokio::test]
+    async fn test_stabilization_window() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let behavior = ScalingBehavior {
+            scale_up: None,
+            scale_down: Some(data_layer::types::scaling_behavior::ScalingRules {
+                stabilization_window_seconds: 300,
+            }),
+        };
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": "1 + 2"}),
+                ],
+                behavior: Some(behavior.clone()),
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+
+        // 10 replicas were recommended and applied just before
+        {
+            let mut stabilizer = scaling_planner.stabilizer.write().await;
+            let params: HashMap<String, Value> = HashMap::from([
+                ("component_id".to_string(), json!("test_component_id")),
+                ("replicas".to_string(), json!(10)),
+            ]);
+            stabilizer.record_recommendation(&params, &behavior, Utc::now());
+            stabilizer.record_applied(&params);
+        }
+
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        // Wait for the scaling planner to execute the plan
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_log = plan_logs
+            .iter()
+            .find(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .expect("suppressed plan log");
+        assert_eq!(plan_log.status, Some(PlanLogStatus::Suppressed.to_string()));
+        assert!(plan_log
+            .fail_message
+            .as_ref()
+            .unwrap()
+            .contains("scale_down stabilization window (300 seconds)"));
+        // The recommendation is recorded in the plan log
+        let params: HashMap<String, Value> =
+            serde_json::from_str(plan_log.metadata_values_json.as_str()).unwrap();
+        assert_eq!(params.get("replicas").and_then(Value::as_f64), Some(3.0));
+        // Nothing is applied, so the last plan item is not updated
+        assert_ne!(
+            *scaling_planner.get_last_plan_item_id().read().await,
+            plan_item_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stabilization_window_across_cool_down() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let behavior = ScalingBehavior {
+            scale_up: None,
+            scale_down: Some(data_layer::types::scaling_behavior::ScalingRules {
+                stabilization_window_seconds: 300,
+            }),
+        };
+        let get_plan_item = |replicas: i64| PlanItemDefinition {
+            id: plan_item_id.clone(),
+            scaling_components: vec![json!({"component_id": "component_a", "replicas": replicas})],
+            behavior: Some(behavior.clone()),
+            ..Default::default()
+        };
+        let now = Utc::now();
+
+        // 10 replicas were applied and the scaling component is cooling down for 60 seconds
+        let stabilizer = RwLock::new(Stabilizer::new());
+        stabilizer.write().await.record_applied(&HashMap::from([
+            ("component_id".to_string(), json!("component_a")),
+            ("replicas".to_string(), json!(10)),
+        ]));
+        let component_cool_downs = RwLock::new(ComponentCoolDowns::new());
+        component_cool_downs
+            .write()
+            .await
+            .record_applied("component_a", &plan_item_id, 60, now);
+        let plan_rate_limit = PlanRateLimit::default();
+        let scaling_component_manager = ScalingComponentManager::new_shared();
+        let stages = PlanItemStages {
+            plan_id: "test",
+            plan_behavior: None,
+            maintenance_windows: &[],
+            component_cool_downs: &component_cool_downs,
+            stabilizer: &stabilizer,
+            plan_rate_limit: &plan_rate_limit,
+            scaling_component_manager: &scaling_component_manager,
+        };
+        let sandbox = JsSandbox::new(JsSandboxLimits::default()).await.unwrap();
+        let context = sandbox.new_context().await.unwrap();
+
+        // 10 replicas are recommended while the scaling component is cooling down
+        let (stage_outcome, _) = run_plan_item_stages(
+            &get_plan_item(10),
+            &stages,
+            &sandbox,
+            context.clone(),
+            now + chrono::Duration::seconds(30),
+        )
+        .await;
+        assert!(matches!(stage_outcome, StageOutcome::Held(Stage::CoolDown)));
+
+        // After the cool down, scaling down is still suppressed by the recommendation in the cool down
+        let (stage_outcome, stage_logs) = run_plan_item_stages(
+            &get_plan_item(3),
+            &stages,
+            &sandbox,
+            context.clone(),
+            now + chrono::Duration::seconds(61),
+        )
+        .await;
+        assert!(matches!(
+            stage_outcome,
+            StageOutcome::Held(Stage::Stabilization)
+        ));
+        assert!(stage_logs[0]
+            .message
+            .contains("scale_down stabilization window (300 seconds)"));
+
+        // After the window, the resolved params are applied
+        let (stage_outcome, _) = run_plan_item_stages(
+            &get_plan_item(3),
+            &stages,
+            &sandbox,
+            context,
+            now + chrono::Duration::seconds(331),
+        )
+        .await;
+        let StageOutcome::Apply(scaling_components) = stage_outcome else {
+            panic!("Expected to be applied");
+        };
+        assert_eq!(scaling_components[0]["replicas"], json!(3));
+    }
+
+    #[tokio::test]
+    async fn test_for_duration_pending() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": 3}),
+                ],
+                for_duration: Some("1h".to_string()),
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        // Wait for the scaling planner to evaluate the plan a few times
+        tokio::time::sleep(tokio::time::Duration::from_millis(2500)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_logs = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        // Only the transition to pending is recorded and the plan item is not executed
+        assert_eq!(plan_logs.len(), 1);
+        assert_eq!(
+            plan_logs[0].status,
+            Some(PlanLogStatus::Pending.to_string())
+        );
+        assert!(plan_logs[0].fail_message.as_ref().unwrap().contains("1h"));
+        assert!(scaling_planner
+            .get_plan_item_states()
+            .read()
+            .await
+            .get_true_since(plan_item_id.as_str())
+            .is_some());
+        assert_ne!(
+            *scaling_planner.get_last_plan_item_id().read().await,
+            plan_item_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_for_duration_with_higher_plan_item() {
+        let higher_plan_item_id = uuid::Uuid::new_v4().to_string();
+        let lower_plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let start = Utc::now().timestamp_millis();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![
+                // Matches in the intervals of 1s and 2s
+                PlanItemDefinition {
+                    id: higher_plan_item_id.clone(),
+                    expression: Some(format!(
+                        "Date.now() > {} && Date.now() < {}",
+                        start + 500,
+                        start + 2500
+                    )),
+                    priority: 2,
+                    scaling_components: vec![
+                        json!({"component_id": "higher_component_id", "replicas": 3}),
+                    ],
+                    ..Default::default()
+                },
+                PlanItemDefinition {
+                    id: lower_plan_item_id.clone(),
+                    expression: Some("2>1".to_string()),
+                    priority: 1,
+                    scaling_components: vec![
+                        json!({"component_id": "lower_component_id", "replicas": 1}),
+                    ],
+                    for_duration: Some("2s".to_string()),
+                    ..Default::default()
+                },
+            ],
+            plan_metadata,
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+        // 4 intervals: 0s (lower pending), 1s, 2s (higher executed), 3s (lower pending again)
+        tokio::time::sleep(tokio::time::Duration::from_millis(3500)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let statuses = |plan_item_id: &str| {
+            plan_logs
+                .iter()
+                .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id))
+                .map(|plan_log| plan_log.status.clone().unwrap_or_default())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            statuses(&higher_plan_item_id),
+            vec![PlanLogStatus::Shadow.to_string(); 2]
+        );
+        // The "for" duration of the lower plan item started again after the higher plan item was executed
+        assert_eq!(
+            statuses(&lower_plan_item_id),
+            vec![PlanLogStatus::Pending.to_string(); 2]
+        );
+        let true_since = scaling_planner
+            .get_plan_item_states()
+            .read()
+            .await
+            .get_true_since(lower_plan_item_id.as_str())
+            .unwrap();
+        assert!(true_since.timestamp_millis() > start + 2500);
+    }
+
+    #[tokio::test]
+    async fn test_all_matching_execution_mode() {
+        let plan_item_id_1 = uuid::Uuid::new_v4().to_string();
+        let plan_item_id_2 = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [
+            ("mode".to_string(), json!(PLAN_MODE_SHADOW)),
+            (
+                "execution_mode".to_string(),
+                json!(EXECUTION_MODE_ALL_MATCHING),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![
+                PlanItemDefinition {
+                    id: plan_item_id_1.clone(),
+                    expression: Some("2>1".to_string()),
+                    cool_down: Some(100),
+                    priority: 2,
+                    scaling_components: vec![
+                        json!({"component_id": "web_component_id", "replicas": 3}),
+                    ],
+                    ..Default::default()
+                },
+                PlanItemDefinition {
+                    id: plan_item_id_2.clone(),
+                    expression: Some("2>1".to_string()),
+                    cool_down: Some(100),
+                    priority: 1,
+                    scaling_components: vec![
+                        json!({"component_id": "waf_component_id", "rate_limit": 100}),
+                    ],
+                    ..Default::default()
+                },
+            ],
+            plan_metadata,
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        // Wait for the scaling planner to evaluate the plan a few times
+        tokio::time::sleep(tokio::time::Duration::from_millis(2500)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        // Both plan items are executed once and then each of them is skipped while cooling down
+        for plan_item_id in [&plan_item_id_1, &plan_item_id_2] {
+            let count = |status: PlanLogStatus| {
+                plan_logs
+                    .iter()
+                    .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+                    .filter(|plan_log| plan_log.status == Some(status.to_string()))
+                    .count()
+            };
+            assert_eq!(count(PlanLogStatus::Shadow), 1);
+            assert_eq!(count(PlanLogStatus::Skipped), 2);
+        }
+        // Each scaling component is cooling down
+        for component_id in ["web_component_id", "waf_component_id"] {
+            assert!(scaling_planner
+                .get_component_cool_downs()
+                .read()
+                .await
+                .get_time_left(component_id, Utc::now())
+                .is_some());
+        }
+        // The lower priority plan item is executed last
+        assert_eq!(
+            *scaling_planner.get_last_plan_item_id().read().await,
+            plan_item_id_2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_component_cool_down() {
+        let plan_item_id_1 = uuid::Uuid::new_v4().to_string();
+        let plan_item_id_2 = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![
+                PlanItemDefinition {
+                    id: plan_item_id_1.clone(),
+                    expression: Some("2>1".to_string()),
+                    cool_down: Some(100),
+                    priority: 2,
+                    scaling_components: vec![json!({"component_id": "component_a", "replicas": 3})],
+                    ..Default::default()
+                },
+                PlanItemDefinition {
+                    id: plan_item_id_2.clone(),
+                    expression: Some("2>1".to_string()),
+                    cool_down: Some(100),
+                    priority: 1,
+                    scaling_components: vec![
+                        json!({"component_id": "component_a", "replicas": 1}),
+                        json!({"component_id": "component_b", "replicas": 5}),
+                    ],
+                    ..Default::default()
+                },
+            ],
+            plan_metadata,
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        // sec 0: plan_item_1 is executed -> [component_a cool down]
+        // sec 1, 2: plan_item_1 is skipped because every scaling component is cooling down.
+        //           It is handled like the other stages, so plan_item_2 is not evaluated.
+        tokio::time::sleep(tokio::time::Duration::from_millis(2500)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let get_statuses = |plan_item_id: &str| {
+            let mut statuses = plan_logs
+                .iter()
+                .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id))
+                .map(|plan_log| {
+                    (
+                        plan_log.status.clone().unwrap_or_default(),
+                        plan_log.metadata_values_json.contains("component_b"),
+                    )
+                })
+                .collect::<Vec<_>>();
+            statuses.sort();
+            statuses
+        };
+        assert_eq!(
+            get_statuses(plan_item_id_1.as_str()),
+            vec![
+                (PlanLogStatus::Shadow.to_string(), false),
+                (PlanLogStatus::Skipped.to_string(), false),
+                (PlanLogStatus::Skipped.to_string(), false),
+            ]
+        );
+        assert!(get_statuses(plan_item_id_2.as_str()).is_empty());
+
+        let component_cool_downs = scaling_planner.get_component_cool_downs();
+        let component_cool_downs = component_cool_downs.read().await;
+        assert_eq!(
+            component_cool_downs
+                .get_last_action("component_a")
+                .unwrap()
+                .plan_item_id,
+            plan_item_id_1
+        );
+        assert!(component_cool_downs
+            .get_last_action("component_b")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_component_cool_down_with_failed_components() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let applied_component_id = format!("component_{}", uuid::Uuid::new_v4().simple());
+        let failed_component_id = format!("component_{}", uuid::Uuid::new_v4().simple());
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                cool_down: Some(100),
+                priority: 1,
+                // The second scaling component is not defined, so it fails in every interval
+                scaling_components: vec![
+                    json!({"component_id": applied_component_id, "replicas": 3}),
+                    json!({"component_id": failed_component_id, "replicas": 3}),
+                ],
+                ..Default::default()
+            }],
+            HashMap::new(),
+        )
+        .await;
+        scaling_planner
+            .scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: applied_component_id.clone(),
+                component_kind: "wa-logger".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let from_date = Utc::now();
+        scaling_planner.run();
+        // 2 intervals: 0s, 1s
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        scaling_planner.stop();
+
+        // The failed scaling component does not cool down and is applied again in the next interval
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let failed_count = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .filter(|plan_log| plan_log.status == Some(PlanLogStatus::Fail.to_string()))
+            .count();
+        assert_eq!(failed_count, 2);
+        let component_cool_downs = scaling_planner.get_component_cool_downs();
+        let component_cool_downs = component_cool_downs.read().await;
+        assert!(component_cool_downs
+            .get_time_left(&applied_component_id, Utc::now())
+            .is_some());
+        assert!(component_cool_downs
+            .get_time_left(&failed_component_id, Utc::now())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cron_catch_up() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                cron_expression: Some("@daily".to_string()),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": 3}),
+                ],
+                catch_up: Some(CatchUpPolicy::Latest),
+                ..Default::default()
+            }],
+            HashMap::new(),
+        )
+        .await;
+        scaling_planner
+            .scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: "test_component_id".to_string(),
+                component_kind: "wa-logger".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        // The planner was down for two days
+        let last_fired = Utc::now() - chrono::Duration::days(2);
+        data_layer
+            .set_cron_last_fired("test", plan_item_id.as_str(), last_fired)
+            .await
+            .unwrap();
+        let from_date = Utc::now();
+        scaling_planner.run();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        scaling_planner.stop();
+
+        // Only the latest missed occurrence is fired
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let count = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .count();
+        assert_eq!(count, 1);
+        let cron_last_fired = data_layer.get_cron_last_fired("test").await.unwrap();
+        let cron_last_fired = cron_last_fired.get(plan_item_id.as_str()).unwrap();
+        assert!(*cron_last_fired > last_fired + chrono::Duration::days(1));
+        assert!(*cron_last_fired <= Utc::now());
+    }
+
+    #[tokio::test]
+    async fn test_restore_planner_state() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let cooling_component_id = uuid::Uuid::new_v4().to_string();
+        let applied_component_id = uuid::Uuid::new_v4().to_string();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                cool_down: Some(100),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": cooling_component_id, "replicas": 3}),
+                    json!({"component_id": applied_component_id, "replicas": 3}),
+                ],
+                ..Default::default()
+            }],
+            HashMap::new(),
+        )
+        .await;
+        for component_id in [&cooling_component_id, &applied_component_id] {
+            scaling_planner
+                .scaling_component_manager
+                .write()
+                .await
+                .add_definition(ScalingComponentDefinition {
+                    id: component_id.clone(),
+                    component_kind: "wa-logger".to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        // The previous planner applied the plan item to one of the scaling components just before it was recreated
+        let mut component_cool_downs = ComponentCoolDowns::new();
+        component_cool_downs.record_applied(&cooling_component_id, &plan_item_id, 100, Utc::now());
+        let previous_state = PlannerState {
+            last_plan_item_id: plan_item_id.clone(),
+            component_actions: component_cool_downs.get_actions().clone(),
+        };
+        data_layer
+            .save_planner_state("test", &previous_state)
+            .await
+            .unwrap();
+
+        let from_date = Utc::now();
+        scaling_planner.set_persist_state(true);
+        scaling_planner.run();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        scaling_planner.stop();
+
+        // The restored cool down skips the scaling component and the other scaling component is applied
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let mut statuses = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .map(|plan_log| {
+                (
+                    plan_log.status.clone().unwrap_or_default(),
+                    plan_log
+                        .metadata_values_json
+                        .contains(applied_component_id.as_str()),
+                )
+            })
+            .collect::<Vec<_>>();
+        statuses.sort();
+        // In the next tick, both scaling components are cooling down
+        assert_eq!(
+            statuses,
+            vec![
+                (PlanLogStatus::Skipped.to_string(), false),
+                (PlanLogStatus::Skipped.to_string(), false),
+                (PlanLogStatus::Skipped.to_string(), true),
+                (PlanLogStatus::Success.to_string(), true),
+            ]
+        );
+
+        // The new state is saved for the next planner
+        let state = data_layer.get_planner_state("test").await.unwrap().unwrap();
+        assert_eq!(state.last_plan_item_id, plan_item_id);
+        assert_eq!(
+            state.component_actions.get(&cooling_component_id),
+            previous_state.component_actions.get(&cooling_component_id)
+        );
+        assert!(state.component_actions.contains_key(&applied_component_id));
+    }
+
+    #[tokio::test]
+    async fn test_plan_max_actions() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [
+            ("mode".to_string(), json!(PLAN_MODE_SHADOW)),
+            ("max_actions".to_string(), json!("2 per 1h")),
+        ]
+        .into_iter()
+        .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("true".to_string()),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": 3}),
+                ],
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+        // 3 intervals: 0s, 1s, 2s
+        tokio::time::sleep(tokio::time::Duration::from_millis(2500)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let statuses = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .map(|plan_log| plan_log.status.clone().unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            statuses,
+            vec![
+                PlanLogStatus::Shadow.to_string(),
+                PlanLogStatus::Shadow.to_string(),
+                PlanLogStatus::RateLimited.to_string(),
+            ]
+        );
+        let rate_limited = plan_logs
+            .iter()
+            .find(|plan_log| plan_log.status == Some(PlanLogStatus::RateLimited.to_string()))
+            .unwrap();
+        assert!(rate_limited
+            .fail_message
+            .as_ref()
+            .unwrap()
+            .contains("the scaling plan test reached max_actions (2 per 1h)"));
+    }
+
+    #[tokio::test]
+    async fn test_run_action_receiver_with_max_actions() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [
+            ("mode".to_string(), json!(PLAN_MODE_SHADOW)),
+            ("max_actions".to_string(), json!("1 per 1h")),
+        ]
+        .into_iter()
+        .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": 3}),
+                ],
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        scaling_planner.definition.id = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let plan_id = scaling_planner.definition.id.clone();
+        let from_date = Utc::now();
+        scaling_planner.run();
+
+        // The first manual run uses the budget of the plan
+        let action = data_layer
+            .send_plan_action(plan_id.clone(), plan_item_id.clone(), &HashMap::new())
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        let action = data_layer.get_action(&action.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Succeeded.to_string());
+
+        // The second manual run is rate limited and not applied
+        let action = data_layer
+            .send_plan_action(plan_id.clone(), plan_item_id.clone(), &HashMap::new())
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        scaling_planner.stop();
+        let action = data_layer.get_action(&action.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Failed.to_string());
+        assert!(action
+            .error
+            .unwrap()
+            .contains("reached max_actions (1 per 1h)"));
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some(plan_id), from_date, Utc::now())
+            .await
+            .unwrap();
+        let statuses = plan_logs
+            .iter()
+            .map(|plan_log| plan_log.status.clone().unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            statuses,
+            vec![
+                PlanLogStatus::Shadow.to_string(),
+                PlanLogStatus::RateLimited.to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fallback_plan_item() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let fallback_item_id = uuid::Uuid::new_v4().to_string();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![
+                PlanItemDefinition {
+                    id: plan_item_id.clone(),
+                    expression: Some("2<1".to_string()),
+                    priority: 1,
+                    scaling_components: vec![
+                        json!({"component_id": "test_component_id", "replicas": 5}),
+                    ],
+                    ..Default::default()
+                },
+                // No expression. It runs after 2 ticks with no matching plan item.
+                PlanItemDefinition {
+                    id: fallback_item_id.clone(),
+                    cool_down: Some(3600),
+                    priority: 10,
+                    scaling_components: vec![
+                        json!({"component_id": "test_component_id", "replicas": 1}),
+                    ],
+                    fallback: Some(true),
+                    fallback_after: Some(2),
+                    ..Default::default()
+                },
+            ],
+            HashMap::new(),
+        )
+        .await;
+        scaling_planner
+            .scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: "test_component_id".to_string(),
+                component_kind: "wa-logger".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let from_date = Utc::now();
+        scaling_planner.run();
+        // 3 intervals: 0s, 1s, 2s
+        tokio::time::sleep(tokio::time::Duration::from_millis(2500)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        // The fallback plan item runs once at 1s and then cools down
+        let fallback_logs = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(fallback_item_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(fallback_logs.len(), 1);
+        assert_eq!(
+            fallback_logs[0].status,
+            Some(PlanLogStatus::Success.to_string())
+        );
+        assert_eq!(
+            *scaling_planner.get_last_plan_item_id().read().await,
+            fallback_item_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transactional_apply_mode() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let component_id = format!("component_{}", uuid::Uuid::new_v4().simple());
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("true".to_string()),
+                priority: 1,
+                // The second component does not exist, so nothing is applied
+                scaling_components: vec![
+                    json!({"component_id": component_id, "replicas": 3}),
+                    json!({"component_id": "unknown_component_id", "replicas": 3}),
+                ],
+                apply_mode: Some(ApplyMode::Transactional),
+                ..Default::default()
+            }],
+            HashMap::new(),
+        )
+        .await;
+        scaling_planner
+            .scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: component_id.clone(),
+                component_kind: "wa-logger".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let from_date = Utc::now();
+        scaling_planner.run();
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_logs = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        // One plan log for the whole sequence
+        assert_eq!(plan_logs.len(), 1);
+        assert_eq!(plan_logs[0].status, Some(PlanLogStatus::Fail.to_string()));
+        assert!(plan_logs[0]
+            .fail_message
+            .as_ref()
+            .unwrap()
+            .starts_with("Failed to capture the current params of unknown_component_id"));
+        let metadata: Value =
+            serde_json::from_str(plan_logs[0].metadata_values_json.as_str()).unwrap();
+        assert_eq!(metadata["steps"][0]["status"], "not_applied");
+        assert_eq!(metadata["steps"][1]["status"], "not_applied");
+    }
+
+    #[tokio::test]
+    async fn test_component_max_actions() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let component_id = format!("component_{}", uuid::Uuid::new_v4().simple());
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("true".to_string()),
+                priority: 1,
+                scaling_components: vec![json!({"component_id": component_id, "replicas": 3})],
+                ..Default::default()
+            }],
+            HashMap::new(),
+        )
+        .await;
+        scaling_planner
+            .scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: component_id.clone(),
+                component_kind: "wa-logger".to_string(),
+                metadata: HashMap::from([("max_actions".to_string(), json!("1 per 1h"))]),
+                ..Default::default()
+            })
+            .unwrap();
+        let from_date = Utc::now();
+        scaling_planner.run();
+        // 2 intervals: 0s, 1s
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        scaling_planner.stop();
+
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_logs = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(plan_logs.len(), 2);
+        assert_eq!(
+            plan_logs[0].status,
+            Some(PlanLogStatus::Success.to_string())
+        );
+        assert_eq!(
+            plan_logs[1].status,
+            Some(PlanLogStatus::RateLimited.to_string())
+        );
+        assert!(plan_logs[1]
+            .metadata_values_json
+            .contains(component_id.as_str()));
+        assert!(plan_logs[1].fail_message.as_ref().unwrap().contains(
+            format!(
+                "the scaling component {} reached max_actions (1 per 1h)",
+                component_id
+            )
+            .as_str()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_max_actions_with_failed_components() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let component_id = format!("component_{}", uuid::Uuid::new_v4().simple());
+        let plan_metadata = [("max_actions".to_string(), json!("1 per 1h"))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("true".to_string()),
+                priority: 1,
+                // The scaling component is not defined, so it fails in every interval
+                scaling_components: vec![json!({"component_id": component_id, "replicas": 3})],
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        let from_date = Utc::now();
+        scaling_planner.run();
+        // 2 intervals: 0s, 1s
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        scaling_planner.stop();
+
+        // The failed scaling components do not use max_actions of the plan
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let statuses = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .map(|plan_log| plan_log.status.clone().unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            statuses,
+            vec![
+                PlanLogStatus::Fail.to_string(),
+                PlanLogStatus::Fail.to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expression_time_limit() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let variables = [(
+            "counter".to_string(),
+            json!("typeof $counter === 'undefined' ? 1 : $counter + 1"),
+        )]
+        .into_iter()
+        .collect();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner_with_variables(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("$counter > 1 || (function() { while(true) {} })()".to_string()),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id", "replicas": 3}),
+                ],
+                ..Default::default()
+            }],
+            variables,
+            plan_metadata,
+        )
+        .await;
+        scaling_planner.set_js_limits(JsSandboxLimits {
+            time_limit_ms: 100,
+            memory_limit_mb: 0,
+        });
+        let from_date = Utc::now();
+        scaling_planner.run();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        scaling_planner.stop();
+
+        // The infinite loop is interrupted in every tick because "var $counter" does not leak into the next tick
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_logs = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(plan_logs.len(), 2);
+        for plan_log in plan_logs {
+            assert_eq!(plan_log.status, Some(PlanLogStatus::Fail.to_string()));
+            assert!(plan_log
+                .fail_message
+                .as_ref()
+                .unwrap()
+                .contains("CPU time limit (100 ms)"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_params_time_limit() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id_1", "replicas": 3}),
+                    json!({
+                        "component_id": "test_component_id_2",
+                        "replicas": "(function() { while(true) {} })()"
+                    }),
+                ],
+                cool_down: Some(60),
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        scaling_planner.set_js_limits(JsSandboxLimits {
+            time_limit_ms: 100,
+            memory_limit_mb: 0,
+        });
+        let from_date = Utc::now();
+        scaling_planner.run();
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        scaling_planner.stop();
+
+        // No scaling component of the plan item is applied and no cool down is started
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_logs = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        assert!(!plan_logs.is_empty());
+        for plan_log in plan_logs {
+            assert_eq!(plan_log.status, Some(PlanLogStatus::Fail.to_string()));
+            assert!(plan_log
+                .fail_message
+                .as_ref()
+                .unwrap()
+                .contains("CPU time limit (100 ms)"));
+        }
+        let component_cool_downs = scaling_planner.get_component_cool_downs();
+        let component_cool_downs = component_cool_downs.read().await;
+        assert!(component_cool_downs
+            .get_time_left("test_component_id_1", Utc::now())
+            .is_none());
+        assert!(scaling_planner
+            .get_last_plan_item_id()
+            .read()
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_require_approval_with_time_limit() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                expression: Some("2>1".to_string()),
+                priority: 1,
+                scaling_components: vec![json!({
+                    "component_id": "test_component_id",
+                    "replicas": "(function() { while(true) {} })()"
+                })],
+                require_approval: Some(true),
+                ..Default::default()
+            }],
+            HashMap::new(),
+        )
+        .await;
+        scaling_planner.set_js_limits(JsSandboxLimits {
+            time_limit_ms: 100,
+            memory_limit_mb: 0,
+        });
+        let from_date = Utc::now();
+        scaling_planner.run();
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        scaling_planner.stop();
+
+        // The unresolved params are not saved for the approval
+        let pending_actions = data_layer.get_pending_actions(None).await.unwrap();
+        assert!(!pending_actions
+            .iter()
+            .any(|pending_action| pending_action.plan_item_id == plan_item_id));
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("test".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        let plan_logs = plan_logs
+            .iter()
+            .filter(|plan_log| plan_log.plan_item_json.contains(plan_item_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(plan_logs.len(), 1);
+        assert_eq!(plan_logs[0].status, Some(PlanLogStatus::Fail.to_string()));
+        assert!(plan_logs[0]
+            .fail_message
+            .as_ref()
+            .unwrap()
+            .contains("CPU time limit (100 ms)"));
+    }
+
+    #[tokio::test]
+    async fn test_run_action_receiver_with_time_limit() {
+        let plan_item_id = uuid::Uuid::new_v4().to_string();
+        let plan_metadata = [("mode".to_string(), json!(PLAN_MODE_SHADOW))]
+            .into_iter()
+            .collect();
+        let (data_layer, mut scaling_planner) = get_scaling_planner(
+            vec![PlanItemDefinition {
+                id: plan_item_id.clone(),
+                priority: 1,
+                scaling_components: vec![
+                    json!({"component_id": "test_component_id_1", "replicas": 3}),
+                    json!({
+                        "component_id": "test_component_id_2",
+                        "replicas": "(function() { while(true) {} })()"
+                    }),
+                ],
+                cool_down: Some(60),
+                ..Default::default()
+            }],
+            plan_metadata,
+        )
+        .await;
+        scaling_planner.definition.id = format!("test_{}", uuid::Uuid::new_v4().simple());
+        scaling_planner.set_js_limits(JsSandboxLimits {
+            time_limit_ms: 100,
+            memory_limit_mb: 0,
+        });
+        scaling_planner.run();
+
+        let action = data_layer
+            .send_plan_action(
+                scaling_planner.definition.id.clone(),
+                plan_item_id,
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        scaling_planner.stop();
+
+        // The action is not succeeded if the params hit the limit of the sandbox
+        let action = data_layer.get_action(&action.id).await.unwrap().unwrap();
+        assert_eq!(action.status, ActionStatus::Failed.to_string());
+        assert!(action.error.unwrap().contains("CPU time limit (100 ms)"));
+        // No scaling component of the plan item is applied and no cool down is started
+        assert_eq!(action.result_json.as_deref(), Some("[]"));
+        let component_cool_downs = scaling_planner.get_component_cool_downs();
+        let component_cool_downs = component_cool_downs.read().await;
+        assert!(component_cool_downs
+            .get_time_left("test_component_id_1", Utc::now())
+            .is_none());
     }
 
     #[tokio::test]
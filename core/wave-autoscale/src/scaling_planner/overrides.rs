@@ -0,0 +1,97 @@
+use anyhow::Result;
+use data_layer::types::{action::ActionOverrides, plan_item_definition::PlanItemDefinition};
+use serde_json::Value;
+
+/**
+ * Apply the parameter overrides of a manual run to a plan item
+ * - The params of each override replace the ones in the metadata of the scaling components with the same component_id
+ * - An override of a scaling component that is not in the plan item is an error so that a typo doesn't run the plan item as defined
+ */
+pub fn apply_overrides(
+    plan_item: &PlanItemDefinition,
+    overrides: &ActionOverrides,
+) -> Result<PlanItemDefinition> {
+    let mut plan_item = plan_item.clone();
+    for (component_id, params) in overrides.iter() {
+        if params.contains_key("component_id") {
+            return Err(anyhow::anyhow!(
+                "component_id of {} can't be overridden",
+                component_id
+            ));
+        }
+        let mut found = false;
+        for metadata in plan_item.scaling_components.iter_mut() {
+            if metadata["component_id"].as_str() != Some(component_id.as_str()) {
+                continue;
+            }
+            let Value::Object(metadata) = metadata else {
+                continue;
+            };
+            for (key, value) in params.iter() {
+                metadata.insert(key.clone(), value.clone());
+            }
+            found = true;
+        }
+        if !found {
+            return Err(anyhow::anyhow!(
+                "Scaling component {} is not in plan_item: {}",
+                component_id,
+                plan_item.id
+            ));
+        }
+    }
+    Ok(plan_item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn get_plan_item() -> PlanItemDefinition {
+        serde_json::from_value(json!({
+            "id": "scale_out",
+            "priority": 1,
+            "expression": "true",
+            "scaling_components": [
+                { "component_id": "k8s_deployment", "replicas": "$replicas + 1", "name": "api" },
+                { "component_id": "ec2_asg", "desired": 2 },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let plan_item = get_plan_item();
+        let overrides: ActionOverrides = HashMap::from([(
+            "k8s_deployment".to_string(),
+            HashMap::from([("replicas".to_string(), json!(40))]),
+        )]);
+        let result = apply_overrides(&plan_item, &overrides).unwrap();
+        assert_eq!(
+            result.scaling_components,
+            vec![
+                json!({ "component_id": "k8s_deployment", "replicas": 40, "name": "api" }),
+                json!({ "component_id": "ec2_asg", "desired": 2 }),
+            ]
+        );
+        // The definition is not changed
+        assert_eq!(
+            plan_item.scaling_components[0]["replicas"],
+            json!("$replicas + 1")
+        );
+
+        let overrides: ActionOverrides = HashMap::from([(
+            "unknown".to_string(),
+            HashMap::from([("replicas".to_string(), json!(40))]),
+        )]);
+        assert!(apply_overrides(&plan_item, &overrides).is_err());
+        let overrides: ActionOverrides = HashMap::from([(
+            "ec2_asg".to_string(),
+            HashMap::from([("component_id".to_string(), json!("k8s_deployment"))]),
+        )]);
+        assert!(apply_overrides(&plan_item, &overrides).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+use super::js_sandbox::{JsSandbox, JsSandboxLimits};
+use super::scaling_planner_manager::SharedScalingPlannerManager;
+use super::transaction::{apply_transaction, is_transactional};
+use super::{
+    create_plan_item_logs, get_applied_component_ids, release_rate_limit, reserve_rate_limit,
+    PlanItemResults,
+};
+use crate::scaling_component::{apply_to_shared, SharedScalingComponentManager};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use data_layer::{
+    data_layer::DataLayer,
+    types::{
+        pending_action::PendingActionStatus, plan_item_definition::PlanItemDefinition,
+        plan_log_definition::PlanLogStatus,
+    },
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::{error, info};
+
+// Parse the TTL of the pending actions (e.g. "30m", "1h")
+pub fn parse_pending_action_ttl(ttl: &str) -> Result<Duration> {
+    let duration = duration_str::parse(ttl)
+        .map_err(|_| anyhow::anyhow!("Failed to parse the pending action TTL: {}", ttl))?;
+    Ok(Duration::from_std(duration)?)
+}
+
+/**
+ * Approve a pending action and apply its resolved params to the scaling components
+ * - The pending action should be PENDING and not expired
+ * - Every scaling component of the pending action should exist
+ * - The approval shares max_actions with the runs of the plan. A rate limited action stays PENDING.
+ * - The budgets are used when the action is approved and given back by the scaling components that fail
+ * - The results are saved in the plan logs and sent to the webhooks of the plan like the interval runs (SUCCESS or FAIL)
+ * - A transactional plan item is applied in order with the rollback on failure and recorded as one plan log
+ * - The applied scaling components start the cool down of the plan item in its scaling planner
+ * - Returns the result of each scaling component
+ */
+pub async fn approve_pending_action(
+    data_layer: &DataLayer,
+    scaling_component_manager: &SharedScalingComponentManager,
+    scaling_planner_manager: &SharedScalingPlannerManager,
+    id: &str,
+    js_limits: JsSandboxLimits,
+) -> Result<Vec<Value>> {
+    // The expired actions can't be approved
+    data_layer.expire_pending_actions(Utc::now()).await?;
+    let Some(pending_action) = data_layer.get_pending_action(id).await? else {
+        return Err(anyhow::anyhow!("Pending action not found: {}", id));
+    };
+    let scaling_components: Vec<HashMap<String, Value>> =
+        serde_json::from_str(pending_action.scaling_components_json.as_str())?;
+    // The pending action stays PENDING if a scaling component was removed or is not loaded yet
+    {
+        let scaling_component_manager = scaling_component_manager.read().await;
+        for params in scaling_components.iter() {
+            let component_id = params
+                .get("component_id")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if scaling_component_manager
+                .get_scaling_component(component_id)
+                .is_none()
+            {
+                return Err(anyhow::anyhow!(
+                    "Unknown scaling component: {}",
+                    component_id
+                ));
+            }
+        }
+    }
+    let plan_item: Option<PlanItemDefinition> =
+        serde_json::from_str(pending_action.plan_item_json.as_str()).ok();
+
+    // The params were resolved when the plan item fired, but a scaling component may evaluate its own expressions
+    // The sandbox is created before the approval so that its failure leaves the pending action PENDING
+    let sandbox = JsSandbox::new(js_limits).await?;
+    let context = sandbox.new_context().await?;
+
+    // The plan of a removed definition has no budget of its own
+    let plan_rate_limit = scaling_planner_manager
+        .read()
+        .await
+        .get_plan_rate_limit(&pending_action.plan_id)?
+        .unwrap_or_default();
+    let component_ids: Vec<String> = scaling_components
+        .iter()
+        .filter_map(|params| params.get("component_id").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect();
+    let component_ids: Vec<&str> = component_ids.iter().map(String::as_str).collect();
+    let mut resolved_plan_item = plan_item.clone().unwrap_or_default();
+    resolved_plan_item.scaling_components = scaling_components
+        .iter()
+        .map(|params| json!(params))
+        .collect();
+    // The budgets are used by the approval, so concurrent approvals can't exceed max_actions
+    let reserved_at = Utc::now();
+    let rate_limited = reserve_rate_limit(
+        &resolved_plan_item,
+        &pending_action.plan_id,
+        &plan_rate_limit,
+        scaling_component_manager,
+        reserved_at,
+    )
+    .await;
+    if !rate_limited.is_empty() {
+        let messages: Vec<String> = rate_limited
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect();
+        return Err(anyhow::anyhow!(messages.join(" ")));
+    }
+    let approved = data_layer
+        .update_pending_action_status(
+            id,
+            PendingActionStatus::Pending,
+            PendingActionStatus::Approved,
+        )
+        .await;
+    if !matches!(approved, Ok(true)) {
+        release_rate_limit(
+            &component_ids,
+            &[],
+            &plan_rate_limit,
+            scaling_component_manager,
+            reserved_at,
+        )
+        .await;
+        approved?;
+        return Err(anyhow::anyhow!(
+            "The pending action is not pending: {} ({})",
+            id,
+            pending_action.status
+        ));
+    }
+    info!(
+        "[PendingAction] Approved {} of {} - {}",
+        id, pending_action.plan_id, pending_action.plan_item_id
+    );
+
+    let (plan_item_results, results) = if plan_item.as_ref().map(is_transactional).unwrap_or(false)
+    {
+        let transaction =
+            apply_transaction(scaling_components, scaling_component_manager, context).await;
+        let steps = transaction.steps.clone();
+        (PlanItemResults::Transactional(transaction), steps)
+    } else {
+        let mut scaling_results = Vec::new();
+        let mut results = Vec::new();
+        for params in scaling_components {
+            let Some(component_id) = params
+                .get("component_id")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+            else {
+                error!("[PendingAction] Failed to get component_id");
+                continue;
+            };
+            let result = apply_to_shared(
+                scaling_component_manager,
+                component_id.as_str(),
+                params.clone(),
+                context.clone(),
+            )
+            .await;
+            let (fail_message, status) = match result.as_ref() {
+                Ok(_) => (None, PlanLogStatus::Success),
+                Err(error) => (Some(error.to_string()), PlanLogStatus::Fail),
+            };
+            results.push(json!({
+                "component_id": component_id,
+                "params": params,
+                "status": status.to_string(),
+                "fail_message": fail_message,
+            }));
+            scaling_results.push(result);
+        }
+        (PlanItemResults::Independent(scaling_results), results)
+    };
+
+    // The failed scaling components give back the budgets. The applied ones start the cool down of the plan item.
+    let applied_params: Vec<HashMap<String, Value>> = plan_item_results
+        .results()
+        .iter()
+        .flatten()
+        .cloned()
+        .collect();
+    release_rate_limit(
+        &component_ids,
+        &get_applied_component_ids(plan_item_results.results()),
+        &plan_rate_limit,
+        scaling_component_manager,
+        reserved_at,
+    )
+    .await;
+    let plan_log_context = {
+        let scaling_planner_manager = scaling_planner_manager.read().await;
+        if let Some(plan_item) = plan_item.as_ref() {
+            scaling_planner_manager
+                .record_approved_action(
+                    &pending_action.plan_id,
+                    plan_item,
+                    &applied_params,
+                    Utc::now(),
+                )
+                .await;
+        }
+        scaling_planner_manager
+            .get_plan_log_context(&pending_action.plan_db_id, &pending_action.plan_id)
+    };
+    let plan_item = plan_item.unwrap_or_else(|| PlanItemDefinition {
+        id: pending_action.plan_item_id.clone(),
+        ..Default::default()
+    });
+    create_plan_item_logs(
+        &plan_log_context,
+        &plan_item,
+        None,
+        &plan_item_results,
+        false,
+    )
+    .await;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metric_updater::MetricUpdater, scaling_component::ScalingComponentManager,
+        scaling_planner::scaling_planner_manager::ScalingPlannerManager,
+    };
+    use data_layer::{
+        types::{object_kind::ObjectKind, plan_item_definition::PlanItemDefinition},
+        ScalingComponentDefinition, ScalingPlanDefinition,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_pending_action_ttl() {
+        assert_eq!(parse_pending_action_ttl("1h").unwrap(), Duration::hours(1));
+        assert_eq!(
+            parse_pending_action_ttl("30m").unwrap(),
+            Duration::minutes(30)
+        );
+        assert!(parse_pending_action_ttl("one hour").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_approve_pending_action() {
+        let data_layer = Arc::new(DataLayer::new("sqlite::memory:", 500_000, false).await);
+        data_layer.sync("").await;
+        let scaling_component_manager = ScalingComponentManager::new_shared();
+        scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: "scaling_component_id".to_string(),
+                component_kind: "wa-logger".to_string(),
+                metadata: HashMap::from([("max_actions".to_string(), json!("1 per 1h"))]),
+                ..Default::default()
+            })
+            .unwrap();
+        let scaling_planner_manager = ScalingPlannerManager::new_shared(
+            data_layer.clone(),
+            MetricUpdater::new_shared(data_layer.clone(), 1000),
+            scaling_component_manager.clone(),
+            None,
+            false,
+            JsSandboxLimits::default(),
+            Duration::hours(1),
+        );
+        let plan_item: PlanItemDefinition = serde_json::from_value(json!({
+            "id": "plan_item_id",
+            "expression": "true",
+            "scaling_components": [{ "component_id": "scaling_component_id" }],
+            "ui": null,
+            "cool_down": 60,
+            "require_approval": true,
+        }))
+        .unwrap();
+        scaling_planner_manager
+            .write()
+            .await
+            .add_definitions(vec![ScalingPlanDefinition {
+                id: "plan_id".to_string(),
+                db_id: "plan_db_id".to_string(),
+                kind: ObjectKind::ScalingPlan,
+                variables: HashMap::new(),
+                metadata: HashMap::new(),
+                plans: vec![plan_item.clone()],
+                enabled: true,
+                behavior: None,
+            }])
+            .unwrap();
+        let scaling_components = vec![HashMap::from([
+            ("component_id".to_string(), json!("scaling_component_id")),
+            ("replicas".to_string(), json!(3)),
+        ])];
+        let pending_action = data_layer
+            .add_pending_action(
+                "plan_db_id",
+                "plan_id",
+                &plan_item,
+                &scaling_components,
+                Utc::now() + Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        let unknown_component_action = data_layer
+            .add_pending_action(
+                "plan_db_id",
+                "plan_id",
+                &plan_item,
+                &[HashMap::from([(
+                    "component_id".to_string(),
+                    json!("unknown_component_id"),
+                )])],
+                Utc::now() + Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        let rate_limited_action = data_layer
+            .add_pending_action(
+                "plan_db_id",
+                "plan_id",
+                &plan_item,
+                &scaling_components,
+                Utc::now() + Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        let expired_action = data_layer
+            .add_pending_action(
+                "plan_db_id",
+                "plan_id",
+                &plan_item,
+                &scaling_components,
+                Utc::now() - Duration::seconds(1),
+            )
+            .await
+            .unwrap();
+
+        let from_date = Utc::now();
+        let results = approve_pending_action(
+            &data_layer,
+            &scaling_component_manager,
+            &scaling_planner_manager,
+            pending_action.id.as_str(),
+            JsSandboxLimits::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["status"], "SUCCESS");
+        assert_eq!(results[0]["params"]["replicas"], 3);
+        let plan_logs = data_layer
+            .get_plan_logs_by_date(Some("plan_id".to_string()), from_date, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(plan_logs.len(), 1);
+        assert_eq!(
+            plan_logs[0].status,
+            Some(PlanLogStatus::Success.to_string())
+        );
+        // The approval started the cool down of the plan item in its scaling planner
+        {
+            let scaling_planner_manager = scaling_planner_manager.read().await;
+            let component_cool_downs = scaling_planner_manager.get_scaling_planners()["plan_id"]
+                .get_component_cool_downs();
+            let component_cool_downs = component_cool_downs.read().await;
+            let action = component_cool_downs
+                .get_last_action("scaling_component_id")
+                .unwrap();
+            assert_eq!(action.plan_item_id, "plan_item_id");
+            assert_eq!(
+                (action.cool_down_until - action.applied_at).num_seconds(),
+                60
+            );
+        }
+
+        // Approved only once
+        assert!(approve_pending_action(
+            &data_layer,
+            &scaling_component_manager,
+            &scaling_planner_manager,
+            pending_action.id.as_str(),
+            JsSandboxLimits::default(),
+        )
+        .await
+        .is_err());
+        // The action of an unknown scaling component is not approved
+        assert!(approve_pending_action(
+            &data_layer,
+            &scaling_component_manager,
+            &scaling_planner_manager,
+            unknown_component_action.id.as_str(),
+            JsSandboxLimits::default(),
+        )
+        .await
+        .is_err());
+        let unknown_component_action = data_layer
+            .get_pending_action(unknown_component_action.id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            unknown_component_action.status,
+            PendingActionStatus::Pending.to_string()
+        );
+        // The approval used max_actions of the scaling component
+        let error = approve_pending_action(
+            &data_layer,
+            &scaling_component_manager,
+            &scaling_planner_manager,
+            rate_limited_action.id.as_str(),
+            JsSandboxLimits::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("the scaling component scaling_component_id reached max_actions (1 per 1h)"));
+        let rate_limited_action = data_layer
+            .get_pending_action(rate_limited_action.id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            rate_limited_action.status,
+            PendingActionStatus::Pending.to_string()
+        );
+        // The expired action can't be approved
+        assert!(approve_pending_action(
+            &data_layer,
+            &scaling_component_manager,
+            &scaling_planner_manager,
+            expired_action.id.as_str(),
+            JsSandboxLimits::default(),
+        )
+        .await
+        .is_err());
+        let expired_action = data_layer
+            .get_pending_action(expired_action.id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            expired_action.status,
+            PendingActionStatus::Expired.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_approve_pending_actions_concurrently() {
+        let data_layer = Arc::new(DataLayer::new("sqlite::memory:", 500_000, false).await);
+        data_layer.sync("").await;
+        let scaling_component_manager = ScalingComponentManager::new_shared();
+        scaling_component_manager
+            .write()
+            .await
+            .add_definition(ScalingComponentDefinition {
+                id: "scaling_component_id".to_string(),
+                component_kind: "wa-logger".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let scaling_planner_manager = ScalingPlannerManager::new_shared(
+            data_layer.clone(),
+            MetricUpdater::new_shared(data_layer.clone(), 1000),
+            scaling_component_manager.clone(),
+            None,
+            false,
+            JsSandboxLimits::default(),
+            Duration::hours(1),
+        );
+        let plan_item: PlanItemDefinition = serde_json::from_value(json!({
+            "id": "plan_item_id",
+            "expression": "true",
+            "scaling_components": [{ "component_id": "scaling_component_id" }],
+            "ui": null,
+            "require_approval": true,
+        }))
+        .unwrap();
+        scaling_planner_manager
+            .write()
+            .await
+            .add_definitions(vec![ScalingPlanDefinition {
+                id: "plan_id".to_string(),
+                db_id: "plan_db_id".to_string(),
+                kind: ObjectKind::ScalingPlan,
+                variables: HashMap::new(),
+                metadata: HashMap::from([("max_actions".to_string(), json!("1 per 1h"))]),
+                plans: vec![plan_item.clone()],
+                enabled: true,
+                behavior: None,
+            }])
+            .unwrap();
+        let scaling_components = vec![HashMap::from([(
+            "component_id".to_string(),
+            json!("scaling_component_id"),
+        )])];
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let pending_action = data_layer
+                .add_pending_action(
+                    "plan_db_id",
+                    "plan_id",
+                    &plan_item,
+                    &scaling_components,
+                    Utc::now() + Duration::hours(1),
+                )
+                .await
+                .unwrap();
+            ids.push(pending_action.id);
+        }
+
+        // Only one of the approvals gets the budget of the plan
+        let (first, second) = tokio::join!(
+            approve_pending_action(
+                &data_layer,
+                &scaling_component_manager,
+                &scaling_planner_manager,
+                ids[0].as_str(),
+                JsSandboxLimits::default(),
+            ),
+            approve_pending_action(
+                &data_layer,
+                &scaling_component_manager,
+                &scaling_planner_manager,
+                ids[1].as_str(),
+                JsSandboxLimits::default(),
+            )
+        );
+        assert!(first.is_ok() != second.is_ok());
+        let error = first.err().or(second.err()).unwrap();
+        assert!(error
+            .to_string()
+            .contains("the scaling plan plan_id reached max_actions (1 per 1h)"));
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+
+/**
+ * PlanItemState
+ * The state of a plan item that has the "for" duration
+ * - Pending: the expression is true but not yet for the duration. "transitioned" is true when it has just become pending.
+ * - Firing: the expression has been true for the duration
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanItemState {
+    Pending {
+        since: DateTime<Utc>,
+        transitioned: bool,
+    },
+    Firing {
+        since: DateTime<Utc>,
+    },
+}
+
+/**
+ * PlanItemStateTracker
+ * Tracks since when the expression of each plan item has been continuously true
+ */
+#[derive(Debug, Default)]
+pub struct PlanItemStateTracker {
+    // plan_item_id => the time when the expression became true
+    true_since: HashMap<String, DateTime<Utc>>,
+}
+
+impl PlanItemStateTracker {
+    pub fn new() -> Self {
+        PlanItemStateTracker::default()
+    }
+
+    // The expression of the plan item is true at now
+    pub fn observe_true(
+        &mut self,
+        plan_item_id: &str,
+        for_duration: Duration,
+        now: DateTime<Utc>,
+    ) -> PlanItemState {
+        let transitioned = !self.true_since.contains_key(plan_item_id);
+        let since = *self
+            .true_since
+            .entry(plan_item_id.to_string())
+            .or_insert(now);
+        if now - since >= for_duration {
+            PlanItemState::Firing { since }
+        } else {
+            PlanItemState::Pending {
+                since,
+                transitioned,
+            }
+        }
+    }
+
+    // The expression of the plan item is false or failed
+    pub fn reset(&mut self, plan_item_id: &str) {
+        self.true_since.remove(plan_item_id);
+    }
+
+    // Reset the plan items that were not evaluated in a tick (e.g. a higher priority plan item was executed)
+    // Their expressions may have been false meanwhile, so the "for" duration starts again at the next evaluation.
+    pub fn reset_unevaluated(&mut self, evaluated_plan_item_ids: &HashSet<&str>) {
+        self.true_since
+            .retain(|plan_item_id, _| evaluated_plan_item_ids.contains(plan_item_id.as_str()));
+    }
+
+    // For testing
+    #[allow(dead_code)]
+    pub fn get_true_since(&self, plan_item_id: &str) -> Option<DateTime<Utc>> {
+        self.true_since.get(plan_item_id).copied()
+    }
+}
+
+// Parse the "for" duration of a plan item (e.g. "30s", "2m", "1h")
+pub fn parse_for_duration(for_duration: &str) -> Result<Duration> {
+    let duration = duration_str::parse(for_duration)
+        .map_err(|_| anyhow::anyhow!("Failed to parse the for duration: {}", for_duration))?;
+    Ok(Duration::from_std(duration)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_for_duration() {
+        assert_eq!(parse_for_duration("2m").unwrap(), Duration::minutes(2));
+        assert_eq!(parse_for_duration("30s").unwrap(), Duration::seconds(30));
+        assert!(parse_for_duration("two minutes").is_err());
+    }
+
+    #[test]
+    fn test_plan_item_state() {
+        let mut tracker = PlanItemStateTracker::new();
+        let for_duration = Duration::minutes(2);
+        let now = Utc::now();
+
+        assert_eq!(
+            tracker.observe_true("item", for_duration, now),
+            PlanItemState::Pending {
+                since: now,
+                transitioned: true
+            }
+        );
+        assert_eq!(
+            tracker.observe_true("item", for_duration, now + Duration::seconds(60)),
+            PlanItemState::Pending {
+                since: now,
+                transitioned: false
+            }
+        );
+        assert_eq!(
+            tracker.observe_true("item", for_duration, now + Duration::seconds(120)),
+            PlanItemState::Firing { since: now }
+        );
+
+        // A false expression resets the state
+        tracker.reset("item");
+        assert!(tracker.get_true_since("item").is_none());
+        assert!(matches!(
+            tracker.observe_true("item", for_duration, now + Duration::seconds(180)),
+            PlanItemState::Pending {
+                transitioned: true,
+                ..
+            }
+        ));
+
+        // A plan item that was not evaluated in a tick is reset
+        tracker.observe_true("other", for_duration, now);
+        tracker.reset_unevaluated(&HashSet::from(["item"]));
+        assert!(tracker.get_true_since("item").is_some());
+        assert!(tracker.get_true_since("other").is_none());
+    }
+}
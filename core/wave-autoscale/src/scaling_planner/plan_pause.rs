@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use data_layer::{data_layer::DataLayer, types::plan_pause::PlanPauseDefinition};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{error::TryRecvError, Receiver};
+use tracing::error;
+
+// The pauses from the API servers of the other instances are not notified, so the cache is refreshed from the database regularly
+const PLAN_PAUSE_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/**
+ * PlanPauseCache
+ * The pause of a plan that is checked on every tick without querying the database
+ * - The pause is refreshed when the plan is paused or resumed in this instance and every PLAN_PAUSE_REFRESH_INTERVAL
+ * - The expiry of the pause (paused_until) is checked on every tick
+ */
+pub struct PlanPauseCache {
+    plan_id: String,
+    receiver: Receiver<String>,
+    plan_pause: Option<PlanPauseDefinition>,
+    refreshed_at: Option<Instant>,
+}
+
+impl PlanPauseCache {
+    // Subscribe before the first refresh so that no pause is missed
+    pub fn new(data_layer: &DataLayer, plan_id: &str) -> Self {
+        PlanPauseCache {
+            plan_id: plan_id.to_string(),
+            receiver: data_layer.subscribe_plan_pause(),
+            plan_pause: None,
+            refreshed_at: None,
+        }
+    }
+
+    // Whether the plan is paused now. The last pause is kept if the database fails.
+    pub async fn is_paused(&mut self, data_layer: &DataLayer, now: DateTime<Utc>) -> bool {
+        if self.needs_refresh() {
+            match data_layer.get_plan_pause(&self.plan_id).await {
+                Ok(plan_pause) => {
+                    self.plan_pause = plan_pause;
+                    self.refreshed_at = Some(Instant::now());
+                }
+                Err(error) => {
+                    error!(
+                        "[ScalingPlanner] Failed to get the pause of the plan - {}",
+                        error
+                    );
+                }
+            }
+        }
+        let Some(plan_pause) = self.plan_pause.as_ref() else {
+            return false;
+        };
+        match plan_pause
+            .paused_until
+            .as_deref()
+            .map(DateTime::parse_from_rfc3339)
+        {
+            None => true,
+            Some(Ok(paused_until)) => paused_until > now,
+            // An invalid paused_until is not expected because it is saved by the data layer
+            Some(Err(_)) => true,
+        }
+    }
+
+    fn needs_refresh(&mut self) -> bool {
+        let mut needs_refresh = self
+            .refreshed_at
+            .map(|refreshed_at| refreshed_at.elapsed() >= PLAN_PAUSE_REFRESH_INTERVAL)
+            .unwrap_or(true);
+        loop {
+            match self.receiver.try_recv() {
+                Ok(plan_id) => needs_refresh |= plan_id == self.plan_id,
+                // Some notifications were dropped. One of them may be for this plan.
+                Err(TryRecvError::Lagged(_)) => needs_refresh = true,
+                Err(_) => break,
+            }
+        }
+        needs_refresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_plan_pause_cache() {
+        let data_layer = DataLayer::new("sqlite::memory:", 500_000, false).await;
+        data_layer.sync("").await;
+        let mut plan_pause_cache = PlanPauseCache::new(&data_layer, "plan_1");
+        assert!(!plan_pause_cache.is_paused(&data_layer, Utc::now()).await);
+
+        // The pause of the plan is notified
+        let paused_until = Utc::now() + chrono::Duration::hours(1);
+        data_layer
+            .pause_plan("plan_1", Some(paused_until))
+            .await
+            .unwrap();
+        assert!(plan_pause_cache.is_paused(&data_layer, Utc::now()).await);
+        // The pause expires without the database
+        assert!(!plan_pause_cache.is_paused(&data_layer, paused_until).await);
+
+        // The pause of another plan does not refresh the cache
+        data_layer.pause_plan("plan_2", None).await.unwrap();
+        assert!(!plan_pause_cache.needs_refresh());
+
+        data_layer.resume_plan("plan_1").await.unwrap();
+        assert!(!plan_pause_cache.is_paused(&data_layer, Utc::now()).await);
+    }
+}
@@ -0,0 +1,220 @@
+use rquickjs::{async_with, CatchResultExt};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tracing::error;
+
+/**
+ * PlanVariables
+ * The evaluated variables of a scaling plan in a tick
+ * - values: "$key" => the typed value (number, string, bool, object, array or null)
+ * - fail_message: the last error of the variables if any variable failed
+ */
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PlanVariables {
+    pub values: BTreeMap<String, Value>,
+    pub fail_message: Option<String>,
+}
+
+// The keys of the other variables that the variable refers to (e.g. "$threshold * 2" => ["threshold"])
+// A reference to itself is not a dependency because "var $key" is hoisted (e.g. "typeof $key === 'undefined' ? 1 : $key")
+fn get_variable_references(
+    key: &str,
+    value: &Value,
+    plan_variables: &HashMap<String, Value>,
+) -> BTreeSet<String> {
+    let Some(value) = value.as_str() else {
+        return BTreeSet::new();
+    };
+    let re_variables = regex::Regex::new(r"\$([A-Za-z0-9_]+)").unwrap();
+    re_variables
+        .captures_iter(value)
+        .map(|captures| captures[1].to_string())
+        .filter(|reference| reference != key && plan_variables.contains_key(reference))
+        .collect()
+}
+
+/**
+Sort the variables so that a variable is evaluated after the variables that it refers to
+- The variables without dependencies between them are sorted by the key
+- Returns (the sorted keys, the keys of the variables in circular references and the variables that depend on them)
+ */
+pub fn sort_plan_variables(plan_variables: &HashMap<String, Value>) -> (Vec<String>, Vec<String>) {
+    // key => the keys that are not evaluated yet
+    let mut dependencies: BTreeMap<String, BTreeSet<String>> = plan_variables
+        .iter()
+        .map(|(key, value)| {
+            (
+                key.clone(),
+                get_variable_references(key, value, plan_variables),
+            )
+        })
+        .collect();
+    let mut sorted = Vec::new();
+    loop {
+        let Some(key) = dependencies
+            .iter()
+            .find(|(_, references)| references.is_empty())
+            .map(|(key, _)| key.clone())
+        else {
+            break;
+        };
+        dependencies.remove(&key);
+        for references in dependencies.values_mut() {
+            references.remove(&key);
+        }
+        sorted.push(key);
+    }
+    (sorted, dependencies.into_keys().collect())
+}
+
+/**
+Evaluate the variables of the scaling plan in the dependency order and set them to the context as "$key"
+- A string is a JavaScript expression that can use get() and the other variables (e.g. "get({ metric_id: 'cpu' }) * $ratio")
+- The other values (number, bool, object, array) are assigned as they are
+- The variables in circular references are not evaluated
+ */
+pub async fn set_plan_variables(
+    context: &rquickjs::AsyncContext,
+    plan_variables: &HashMap<String, Value>,
+) -> PlanVariables {
+    let mut result = PlanVariables::default();
+    let (sorted, circular) = sort_plan_variables(plan_variables);
+    if !circular.is_empty() {
+        let message = format!(
+            "Circular references in the variables: {}",
+            circular
+                .iter()
+                .map(|key| format!("${}", key))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        error!("[ScalingPlanner] {}", message);
+        result.fail_message = Some(message);
+    }
+
+    for key in sorted.iter() {
+        let Some(value) = plan_variables.get(key) else {
+            continue;
+        };
+        // If the value is a string, it should be assigned without quotes.
+        let value_for_set = match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+
+        // Evaluate the variable, set the value to the context with "$key" and get the value as JSON
+        let evaluation = async_with!(context => |ctx| {
+            let expression = format!("var ${} = {};\nJSON.stringify(${});", key, value_for_set, key);
+            ctx.eval::<Option<String>, _>(expression)
+                .catch(ctx)
+                .map_err(|error| error.to_string())
+        })
+        .await;
+        match evaluation {
+            Ok(json) => {
+                // undefined and functions have no JSON
+                let value = json
+                    .and_then(|json| serde_json::from_str::<Value>(&json).ok())
+                    .unwrap_or(Value::Null);
+                result.values.insert(format!("${}", key), value);
+            }
+            Err(error) => {
+                error!(
+                    "[ScalingPlanner] Failed to set the variable: {} - {}",
+                    key, error
+                );
+                result.fail_message = Some(format!("${}: {}", key, error));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn get_variables(variables: Value) -> HashMap<String, Value> {
+        serde_json::from_value(variables).unwrap()
+    }
+
+    #[test]
+    fn test_sort_plan_variables() {
+        let variables = get_variables(json!({
+            "c": "$b * 2",
+            "b": "$a + 1",
+            "a": 1,
+            "d": "$unknown + 1",
+        }));
+        let (sorted, circular) = sort_plan_variables(&variables);
+        assert_eq!(sorted, vec!["a", "b", "c", "d"]);
+        assert!(circular.is_empty());
+
+        let variables = get_variables(json!({
+            "a": "$b + 1",
+            "b": "$a + 1",
+            "c": "$b * 2",
+            "d": "typeof $d === 'undefined' ? 1 : $d",
+            "e": 1,
+        }));
+        let (sorted, circular) = sort_plan_variables(&variables);
+        assert_eq!(sorted, vec!["d", "e"]);
+        assert_eq!(circular, vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_plan_variables() {
+        let runtime = rquickjs::AsyncRuntime::new().unwrap();
+        let context = rquickjs::AsyncContext::full(&runtime).await.unwrap();
+        async_with!(context => |ctx| {
+            let _ = ctx.globals().set(
+                "get",
+                rquickjs::prelude::Func::new("get", |_args: rquickjs::Object<'_>| Ok::<f64, rquickjs::Error>(70.0)),
+            );
+        })
+        .await;
+
+        // The variables refer to get() and the other variables in any order
+        let variables = get_variables(json!({
+            "scaled": "$cpu / $max * 100",
+            "cpu": "get({ metric_id: 'cpu' })",
+            "max": 200,
+            "is_high": "$scaled > 30",
+            "label": "'cpu-' + $scaled",
+            "limits": { "min": 1, "max": 10 },
+        }));
+        let result = set_plan_variables(&context, &variables).await;
+        assert!(result.fail_message.is_none());
+        assert_eq!(
+            result.values,
+            BTreeMap::from([
+                ("$cpu".to_string(), json!(70)),
+                ("$is_high".to_string(), json!(true)),
+                ("$label".to_string(), json!("cpu-35")),
+                ("$limits".to_string(), json!({ "min": 1, "max": 10 })),
+                ("$max".to_string(), json!(200)),
+                ("$scaled".to_string(), json!(35)),
+            ])
+        );
+        let max_of_limits = async_with!(context => |ctx| {
+            ctx.eval::<f64, _>("$limits.max").unwrap()
+        })
+        .await;
+        assert_eq!(max_of_limits, 10.0);
+
+        // The failures are reported and the other variables are still evaluated
+        let variables = get_variables(json!({
+            "a": "$b + 1",
+            "b": "$a + 1",
+            "c": "(1",
+            "d": 1,
+        }));
+        let result = set_plan_variables(&context, &variables).await;
+        assert!(result.fail_message.unwrap().starts_with("$c:"));
+        assert_eq!(
+            result.values,
+            BTreeMap::from([("$d".to_string(), json!(1))])
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use chrono::{DateTime, Duration, Utc};
+use data_layer::types::max_actions::MaxActions;
+use std::{collections::VecDeque, sync::Arc};
+use tokio::sync::RwLock;
+
+/**
+ * ActionHistory
+ * The timestamps of the recent actions of a scaling plan or a scaling component to enforce MaxActions
+ */
+#[derive(Debug, Default, Clone)]
+pub struct ActionHistory {
+    timestamps: VecDeque<DateTime<Utc>>,
+}
+
+impl ActionHistory {
+    pub fn new() -> Self {
+        ActionHistory::default()
+    }
+
+    // Save an action. Only the last "count" actions are needed to enforce max_actions.
+    pub fn record(&mut self, max_actions: &MaxActions, now: DateTime<Utc>) {
+        self.timestamps.push_back(now);
+        while self.timestamps.len() > max_actions.count {
+            self.timestamps.pop_front();
+        }
+    }
+
+    // Remove a saved action that was not done (e.g. a reserved approval that failed)
+    pub fn remove(&mut self, timestamp: DateTime<Utc>) {
+        if let Some(index) = self
+            .timestamps
+            .iter()
+            .rposition(|saved| *saved == timestamp)
+        {
+            self.timestamps.remove(index);
+        }
+    }
+
+    // The time until the next action is allowed. None if an action is allowed now.
+    pub fn get_wait_time(&self, max_actions: &MaxActions, now: DateTime<Utc>) -> Option<Duration> {
+        let len = self.timestamps.len();
+        if len < max_actions.count {
+            return None;
+        }
+        // The oldest action in the budget frees a slot when it leaves the period
+        let wait_time = self.timestamps[len - max_actions.count] + max_actions.period - now;
+        if wait_time > Duration::zero() {
+            Some(wait_time)
+        } else {
+            None
+        }
+    }
+}
+
+/**
+ * PlanRateLimit
+ * metadata.max_actions of a scaling plan and its recent actions
+ * - The interval runs, the manual runs (run-plan) and the approvals of the pending actions share the budget of the plan
+ */
+#[derive(Debug, Default, Clone)]
+pub struct PlanRateLimit {
+    pub max_actions: Option<MaxActions>,
+    pub action_history: Arc<RwLock<ActionHistory>>,
+}
+
+impl PlanRateLimit {
+    // Save an action of the scaling plan if it has max_actions
+    pub async fn record(&self, now: DateTime<Utc>) {
+        if let Some(max_actions) = self.max_actions.as_ref() {
+            self.action_history.write().await.record(max_actions, now);
+        }
+    }
+
+    // Remove a saved action of the scaling plan that was not done
+    pub async fn remove(&self, timestamp: DateTime<Utc>) {
+        if self.max_actions.is_some() {
+            self.action_history.write().await.remove(timestamp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_history() {
+        let max_actions = MaxActions::parse("2 per 1m").unwrap();
+        let mut history = ActionHistory::new();
+        let now = Utc::now();
+        assert!(history.get_wait_time(&max_actions, now).is_none());
+
+        history.record(&max_actions, now);
+        history.record(&max_actions, now + Duration::seconds(20));
+        // The budget is used up until the first action leaves the period
+        let wait_time = history
+            .get_wait_time(&max_actions, now + Duration::seconds(30))
+            .unwrap();
+        assert_eq!(wait_time.num_seconds(), 30);
+        assert!(history
+            .get_wait_time(&max_actions, now + Duration::seconds(60))
+            .is_none());
+
+        // The next action is allowed after the second action leaves the period
+        history.record(&max_actions, now + Duration::seconds(60));
+        let wait_time = history
+            .get_wait_time(&max_actions, now + Duration::seconds(70))
+            .unwrap();
+        assert_eq!(wait_time.num_seconds(), 10);
+
+        // The removed action does not use the budget
+        history.remove(now + Duration::seconds(60));
+        assert!(history
+            .get_wait_time(&max_actions, now + Duration::seconds(70))
+            .is_none());
+    }
+}
@@ -2,11 +2,24 @@ use crate::{
     metric_updater::SharedMetricUpdater, scaling_component::SharedScalingComponentManager,
 };
 
-use super::ScalingPlanner;
+use super::{
+    js_sandbox::JsSandboxLimits,
+    rate_limit::{ActionHistory, PlanRateLimit},
+    PlanLogContext, ScalingPlanner,
+};
 use anyhow::Result;
-use data_layer::{data_layer::DataLayer, ScalingPlanDefinition};
-use std::{collections::HashMap, sync::Arc};
+use chrono::{DateTime, Utc};
+use data_layer::{
+    data_layer::DataLayer, types::plan_item_definition::PlanItemDefinition,
+    MaintenanceWindowDefinition, ScalingPlanDefinition,
+};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use tokio::sync::RwLock;
+use tracing::error;
 //
 // PlannerManager
 //
@@ -18,6 +31,12 @@ pub struct ScalingPlannerManager {
     metric_updater: SharedMetricUpdater,
     scaling_component_manager: SharedScalingComponentManager,
     webhooks: Option<Vec<utils::wave_config::Webhooks>>,
+    shadow_mode: bool,
+    js_limits: JsSandboxLimits,
+    pending_action_ttl: chrono::Duration,
+    maintenance_windows: Vec<MaintenanceWindowDefinition>,
+    // plan_id => the recent actions of the plan for max_actions. It is kept when the definitions are reloaded.
+    plan_action_histories: HashMap<String, Arc<RwLock<ActionHistory>>>,
 }
 
 impl ScalingPlannerManager {
@@ -26,6 +45,9 @@ impl ScalingPlannerManager {
         metric_updater: SharedMetricUpdater,
         scaling_component_manager: SharedScalingComponentManager,
         webhooks: Option<Vec<utils::wave_config::Webhooks>>,
+        shadow_mode: bool,
+        js_limits: JsSandboxLimits,
+        pending_action_ttl: chrono::Duration,
     ) -> Self {
         ScalingPlannerManager {
             scaling_planners: HashMap::new(),
@@ -33,6 +55,11 @@ impl ScalingPlannerManager {
             metric_updater,
             scaling_component_manager,
             webhooks,
+            shadow_mode,
+            js_limits,
+            pending_action_ttl,
+            maintenance_windows: Vec::new(),
+            plan_action_histories: HashMap::new(),
         }
     }
     pub fn new_shared(
@@ -40,30 +67,72 @@ impl ScalingPlannerManager {
         metric_updater: SharedMetricUpdater,
         scaling_component_manager: SharedScalingComponentManager,
         webhooks: Option<Vec<utils::wave_config::Webhooks>>,
+        shadow_mode: bool,
+        js_limits: JsSandboxLimits,
+        pending_action_ttl: chrono::Duration,
     ) -> SharedScalingPlannerManager {
         Arc::new(RwLock::new(ScalingPlannerManager::new(
             data_layer,
             metric_updater,
             scaling_component_manager,
             webhooks,
+            shadow_mode,
+            js_limits,
+            pending_action_ttl,
         )))
     }
 
     // Factory method to create a scaling component.
-    fn create_scaling_planner(&self, definition: ScalingPlanDefinition) -> Result<ScalingPlanner> {
-        Ok(ScalingPlanner::new(
+    fn create_scaling_planner(
+        &mut self,
+        definition: ScalingPlanDefinition,
+    ) -> Result<ScalingPlanner> {
+        let mut scaling_planner = ScalingPlanner::new(
             definition,
             self.metric_updater.clone(),
             self.scaling_component_manager.clone(),
             self.data_layer.clone(),
             self.webhooks.clone(),
-        ))
+            self.shadow_mode,
+        );
+        // Restore the state of the planner that had the same plan id (e.g. restart, definition change)
+        scaling_planner.set_persist_state(true);
+        scaling_planner.set_js_limits(self.js_limits);
+        scaling_planner.set_pending_action_ttl(self.pending_action_ttl);
+        scaling_planner.set_maintenance_windows(self.maintenance_windows.clone());
+        // The simulated actions in shadow mode do not use max_actions of the plan after it becomes active
+        if !scaling_planner.is_shadow_mode() {
+            let plan_action_history = self
+                .plan_action_histories
+                .entry(scaling_planner.get_id())
+                .or_default()
+                .clone();
+            scaling_planner.set_plan_action_history(plan_action_history);
+        }
+        Ok(scaling_planner)
+    }
+
+    // The maintenance windows of the scaling planners that are added after this
+    pub fn set_maintenance_windows(
+        &mut self,
+        maintenance_windows: Vec<MaintenanceWindowDefinition>,
+    ) {
+        self.maintenance_windows = maintenance_windows;
     }
 
     pub fn add_definitions(
         &mut self,
         scaling_plan_definitions: Vec<ScalingPlanDefinition>,
     ) -> Result<()> {
+        // The budget of a removed plan is not inherited by a new plan with the same id
+        let plan_ids: HashSet<String> = scaling_plan_definitions
+            .iter()
+            .map(|definition| definition.id.clone())
+            .chain(self.scaling_planners.keys().cloned())
+            .collect();
+        self.plan_action_histories
+            .retain(|plan_id, _| plan_ids.contains(plan_id));
+
         for scaling_plan_definition in scaling_plan_definitions {
             let scaling_component = self.create_scaling_planner(scaling_plan_definition)?;
             self.add_scaling_component(scaling_component);
@@ -80,6 +149,48 @@ impl ScalingPlannerManager {
         &self.scaling_planners
     }
 
+    pub fn get_plan_rate_limit(&self, plan_id: &str) -> Result<Option<PlanRateLimit>> {
+        self.scaling_planners
+            .get(plan_id)
+            .map(ScalingPlanner::get_plan_rate_limit)
+            .transpose()
+    }
+
+    // Start the cool downs of an approved pending action in the scaling planner of the plan
+    pub async fn record_approved_action(
+        &self,
+        plan_id: &str,
+        plan_item: &PlanItemDefinition,
+        applied_params: &[HashMap<String, Value>],
+        now: DateTime<Utc>,
+    ) {
+        if let Some(scaling_planner) = self.scaling_planners.get(plan_id) {
+            scaling_planner
+                .record_approved_action(plan_item, applied_params, now)
+                .await;
+        }
+    }
+
+    // The plan logs and the webhooks of an approved pending action are the same as the interval runs of the plan
+    pub(super) fn get_plan_log_context(&self, plan_db_id: &str, plan_id: &str) -> PlanLogContext {
+        if let Some(scaling_planner) = self.scaling_planners.get(plan_id) {
+            match scaling_planner.get_plan_log_context() {
+                Ok(plan_log_context) => return plan_log_context,
+                Err(error) => error!("[ScalingPlannerManager] {}", error),
+            }
+        }
+        // The plan of a removed definition only has the global webhooks
+        PlanLogContext {
+            data_layer: self.data_layer.clone(),
+            plan_db_id: plan_db_id.to_string(),
+            plan_id: plan_id.to_string(),
+            plan_webhooks: None,
+            webhooks: self.webhooks.clone(),
+            variables_json: None,
+            overrides_json: None,
+        }
+    }
+
     pub fn remove_all(&mut self) {
         self.scaling_planners.clear();
     }
@@ -95,3 +206,191 @@ impl ScalingPlannerManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{metric_updater::MetricUpdater, scaling_component::ScalingComponentManager};
+    use chrono::Utc;
+    use data_layer::types::object_kind::ObjectKind;
+    use serde_json::json;
+
+    fn get_scaling_plan_definition() -> ScalingPlanDefinition {
+        ScalingPlanDefinition {
+            id: "plan_id".to_string(),
+            db_id: "plan_db_id".to_string(),
+            kind: ObjectKind::ScalingPlan,
+            variables: HashMap::new(),
+            metadata: HashMap::from([("max_actions".to_string(), json!("1 per 1h"))]),
+            plans: vec![],
+            enabled: true,
+            behavior: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_action_history_is_kept() {
+        let data_layer = Arc::new(DataLayer::new("sqlite::memory:", 500_000, false).await);
+        data_layer.sync("").await;
+        let mut scaling_planner_manager = ScalingPlannerManager::new(
+            data_layer.clone(),
+            MetricUpdater::new_shared(data_layer.clone(), 1000),
+            ScalingComponentManager::new_shared(),
+            None,
+            false,
+            JsSandboxLimits::default(),
+            chrono::Duration::hours(1),
+        );
+        scaling_planner_manager
+            .add_definitions(vec![get_scaling_plan_definition()])
+            .unwrap();
+        let plan_rate_limit = scaling_planner_manager
+            .get_plan_rate_limit("plan_id")
+            .unwrap()
+            .unwrap();
+        plan_rate_limit.record(Utc::now()).await;
+
+        // The recreated scaling planner has the same budget (e.g. a definition change)
+        scaling_planner_manager.remove_all();
+        scaling_planner_manager
+            .add_definitions(vec![get_scaling_plan_definition()])
+            .unwrap();
+        let plan_rate_limit = scaling_planner_manager
+            .get_plan_rate_limit("plan_id")
+            .unwrap()
+            .unwrap();
+        let max_actions = plan_rate_limit.max_actions.unwrap();
+        assert!(plan_rate_limit
+            .action_history
+            .read()
+            .await
+            .get_wait_time(&max_actions, Utc::now())
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_plan_action_history_of_removed_plan() {
+        let data_layer = Arc::new(DataLayer::new("sqlite::memory:", 500_000, false).await);
+        data_layer.sync("").await;
+        let mut scaling_planner_manager = ScalingPlannerManager::new(
+            data_layer.clone(),
+            MetricUpdater::new_shared(data_layer.clone(), 1000),
+            ScalingComponentManager::new_shared(),
+            None,
+            false,
+            JsSandboxLimits::default(),
+            chrono::Duration::hours(1),
+        );
+        scaling_planner_manager
+            .add_definitions(vec![get_scaling_plan_definition()])
+            .unwrap();
+        let plan_rate_limit = scaling_planner_manager
+            .get_plan_rate_limit("plan_id")
+            .unwrap()
+            .unwrap();
+        plan_rate_limit.record(Utc::now()).await;
+
+        // The plan is removed
+        scaling_planner_manager.remove_all();
+        scaling_planner_manager.add_definitions(vec![]).unwrap();
+        assert!(scaling_planner_manager.plan_action_histories.is_empty());
+
+        // A new plan with the same id starts with the full budget
+        scaling_planner_manager.remove_all();
+        scaling_planner_manager
+            .add_definitions(vec![get_scaling_plan_definition()])
+            .unwrap();
+        let plan_rate_limit = scaling_planner_manager
+            .get_plan_rate_limit("plan_id")
+            .unwrap()
+            .unwrap();
+        let max_actions = plan_rate_limit.max_actions.unwrap();
+        assert!(plan_rate_limit
+            .action_history
+            .read()
+            .await
+            .get_wait_time(&max_actions, Utc::now())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_plan_action_history_in_shadow_mode() {
+        let data_layer = Arc::new(DataLayer::new("sqlite::memory:", 500_000, false).await);
+        data_layer.sync("").await;
+        let mut scaling_planner_manager = ScalingPlannerManager::new(
+            data_layer.clone(),
+            MetricUpdater::new_shared(data_layer.clone(), 1000),
+            ScalingComponentManager::new_shared(),
+            None,
+            false,
+            JsSandboxLimits::default(),
+            chrono::Duration::hours(1),
+        );
+        let mut definition = get_scaling_plan_definition();
+        definition
+            .metadata
+            .insert("mode".to_string(), json!("shadow"));
+        scaling_planner_manager
+            .add_definitions(vec![definition])
+            .unwrap();
+        let plan_rate_limit = scaling_planner_manager
+            .get_plan_rate_limit("plan_id")
+            .unwrap()
+            .unwrap();
+        plan_rate_limit.record(Utc::now()).await;
+
+        // The plan becomes active without the simulated actions
+        scaling_planner_manager.remove_all();
+        scaling_planner_manager
+            .add_definitions(vec![get_scaling_plan_definition()])
+            .unwrap();
+        let plan_rate_limit = scaling_planner_manager
+            .get_plan_rate_limit("plan_id")
+            .unwrap()
+            .unwrap();
+        let max_actions = plan_rate_limit.max_actions.unwrap();
+        assert!(plan_rate_limit
+            .action_history
+            .read()
+            .await
+            .get_wait_time(&max_actions, Utc::now())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_plan_log_context() {
+        let data_layer = Arc::new(DataLayer::new("sqlite::memory:", 500_000, false).await);
+        data_layer.sync("").await;
+        let mut scaling_planner_manager = ScalingPlannerManager::new(
+            data_layer.clone(),
+            MetricUpdater::new_shared(data_layer.clone(), 1000),
+            ScalingComponentManager::new_shared(),
+            None,
+            false,
+            JsSandboxLimits::default(),
+            chrono::Duration::hours(1),
+        );
+        let mut definition = get_scaling_plan_definition();
+        definition
+            .metadata
+            .insert("webhooks".to_string(), json!(["webhook_id"]));
+        scaling_planner_manager
+            .add_definitions(vec![definition])
+            .unwrap();
+
+        // The approvals use the webhooks of the plan like the interval runs
+        let plan_log_context =
+            scaling_planner_manager.get_plan_log_context("plan_db_id", "plan_id");
+        assert_eq!(plan_log_context.plan_db_id, "plan_db_id");
+        assert_eq!(
+            plan_log_context.plan_webhooks,
+            Some(vec!["webhook_id".to_string()])
+        );
+
+        // The plan of a removed definition has no webhooks of its own
+        let plan_log_context =
+            scaling_planner_manager.get_plan_log_context("removed_plan_db_id", "removed_plan_id");
+        assert_eq!(plan_log_context.plan_id, "removed_plan_id");
+        assert!(plan_log_context.plan_webhooks.is_none());
+    }
+}
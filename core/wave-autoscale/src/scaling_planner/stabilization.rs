@@ -0,0 +1,300 @@
+use chrono::{DateTime, Duration, Utc};
+use data_layer::types::scaling_behavior::ScalingBehavior;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/**
+ * Stabilization
+ * The result of stabilizing the params of a scaling component
+ * - Apply: the params to apply. The numeric params may be replaced with the stabilized values.
+ * - Suppressed: every change of the numeric params was suppressed by the stabilization windows
+ */
+#[derive(Debug, Clone)]
+pub enum Stabilization {
+    Apply(HashMap<String, Value>),
+    Suppressed(String),
+}
+
+// [(timestamp, param name => value)]
+type Recommendations = Vec<(DateTime<Utc>, HashMap<String, f64>)>;
+
+/**
+ * Stabilizer
+ * Keeps the recommendations (the resolved numeric params of the matched plan items) and the last applied params
+ * of each scaling component to apply the stabilization windows of the behavior.
+ */
+#[derive(Debug, Default)]
+pub struct Stabilizer {
+    // component_id => recommendations
+    recommendations: HashMap<String, Recommendations>,
+    // component_id => (param name => value)
+    last_applied: HashMap<String, HashMap<String, f64>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Up,
+    Down,
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Up => write!(f, "scale_up"),
+            Direction::Down => write!(f, "scale_down"),
+        }
+    }
+}
+
+fn get_numeric_params(params: &HashMap<String, Value>) -> HashMap<String, f64> {
+    params
+        .iter()
+        .filter(|(key, _)| key.as_str() != "component_id")
+        .filter_map(|(key, value)| value.as_f64().map(|value| (key.clone(), value)))
+        .collect()
+}
+
+fn get_window_seconds(behavior: &ScalingBehavior, direction: Direction) -> u64 {
+    let rules = match direction {
+        Direction::Up => behavior.scale_up.as_ref(),
+        Direction::Down => behavior.scale_down.as_ref(),
+    };
+    rules
+        .map(|rules| rules.stabilization_window_seconds)
+        .unwrap_or(0)
+}
+
+impl Stabilizer {
+    pub fn new() -> Self {
+        Stabilizer::default()
+    }
+
+    /**
+    Save the resolved params of a matched plan item as a recommendation for the scaling component
+    - It is recorded even if the scaling component is not applied (e.g. cooling down)
+      so that the stabilization windows have every recent recommendation
+     */
+    pub fn record_recommendation(
+        &mut self,
+        params: &HashMap<String, Value>,
+        behavior: &ScalingBehavior,
+        now: DateTime<Utc>,
+    ) {
+        let Some(component_id) = params.get("component_id").and_then(Value::as_str) else {
+            return;
+        };
+        // Keep the recommendations only in the longest window
+        let longest_window = get_window_seconds(behavior, Direction::Up)
+            .max(get_window_seconds(behavior, Direction::Down));
+        let recommendations = self
+            .recommendations
+            .entry(component_id.to_string())
+            .or_default();
+        recommendations
+            .retain(|(timestamp, _)| now - *timestamp <= Duration::seconds(longest_window as i64));
+        recommendations.push((now, get_numeric_params(params)));
+    }
+
+    /**
+    Stabilize the resolved params of a scaling component with the recorded recommendations
+    - For scaling up, the lowest recommendation in the scale_up window is used
+    - For scaling down, the highest recommendation in the scale_down window is used
+     */
+    pub fn stabilize(
+        &self,
+        params: &HashMap<String, Value>,
+        behavior: &ScalingBehavior,
+        now: DateTime<Utc>,
+    ) -> Stabilization {
+        let Some(component_id) = params.get("component_id").and_then(Value::as_str) else {
+            return Stabilization::Apply(params.clone());
+        };
+        let Some(last_applied) = self.last_applied.get(component_id) else {
+            return Stabilization::Apply(params.clone());
+        };
+        let numeric_params = get_numeric_params(params);
+        let no_recommendations = Vec::new();
+        let recommendations = self
+            .recommendations
+            .get(component_id)
+            .unwrap_or(&no_recommendations);
+
+        let mut stabilized_params = params.clone();
+        let mut moved = false;
+        let mut suppressed_by: Vec<String> = Vec::new();
+        for (key, value) in numeric_params.iter() {
+            let Some(last_value) = last_applied.get(key) else {
+                moved = true;
+                continue;
+            };
+            let direction = if value > last_value {
+                Direction::Up
+            } else if value < last_value {
+                Direction::Down
+            } else {
+                continue;
+            };
+            let window = get_window_seconds(behavior, direction);
+            let values_in_window = recommendations
+                .iter()
+                .filter(|(timestamp, _)| now - *timestamp <= Duration::seconds(window as i64))
+                .filter_map(|(_, recommendation)| recommendation.get(key).copied());
+            let stabilized_value = match direction {
+                Direction::Up => values_in_window.fold(*value, f64::min).max(*last_value),
+                Direction::Down => values_in_window.fold(*value, f64::max).min(*last_value),
+            };
+            if stabilized_value == *last_value {
+                let message = format!("{} stabilization window ({} seconds)", direction, window);
+                if !suppressed_by.contains(&message) {
+                    suppressed_by.push(message);
+                }
+            } else {
+                moved = true;
+            }
+            if stabilized_value != *value {
+                stabilized_params.insert(key.clone(), Value::from(stabilized_value));
+            }
+        }
+
+        if !moved && !suppressed_by.is_empty() {
+            return Stabilization::Suppressed(format!(
+                "Suppressed by the {}",
+                suppressed_by.join(", ")
+            ));
+        }
+        Stabilization::Apply(stabilized_params)
+    }
+
+    // Save the params that are applied to the scaling component
+    pub fn record_applied(&mut self, params: &HashMap<String, Value>) {
+        let Some(component_id) = params.get("component_id").and_then(Value::as_str) else {
+            return;
+        };
+        self.last_applied
+            .insert(component_id.to_string(), get_numeric_params(params));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_layer::types::scaling_behavior::ScalingRules;
+    use serde_json::json;
+
+    fn get_params(replicas: f64) -> HashMap<String, Value> {
+        HashMap::from([
+            ("component_id".to_string(), json!("component1")),
+            ("replicas".to_string(), json!(replicas)),
+        ])
+    }
+
+    fn recommend(
+        stabilizer: &mut Stabilizer,
+        replicas: f64,
+        behavior: &ScalingBehavior,
+        now: DateTime<Utc>,
+    ) -> Stabilization {
+        let params = get_params(replicas);
+        stabilizer.record_recommendation(&params, behavior, now);
+        stabilizer.stabilize(&params, behavior, now)
+    }
+
+    fn get_behavior(scale_up: u64, scale_down: u64) -> ScalingBehavior {
+        ScalingBehavior {
+            scale_up: Some(ScalingRules {
+                stabilization_window_seconds: scale_up,
+            }),
+            scale_down: Some(ScalingRules {
+                stabilization_window_seconds: scale_down,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_scale_down_stabilization() {
+        let behavior = get_behavior(0, 300);
+        let mut stabilizer = Stabilizer::new();
+        let now = Utc::now();
+
+        // The first recommendation is applied
+        let result = recommend(&mut stabilizer, 10.0, &behavior, now);
+        assert!(matches!(result, Stabilization::Apply(_)));
+        stabilizer.record_applied(&get_params(10.0));
+
+        // Scaling down is suppressed in the window
+        let result = recommend(&mut stabilizer, 3.0, &behavior, now + Duration::seconds(60));
+        let Stabilization::Suppressed(message) = result else {
+            panic!("Expected to be suppressed");
+        };
+        assert!(message.contains("scale_down stabilization window (300 seconds)"));
+
+        // The highest recommendation in the window is used
+        recommend(
+            &mut stabilizer,
+            5.0,
+            &behavior,
+            now + Duration::seconds(301),
+        );
+        let result = recommend(
+            &mut stabilizer,
+            3.0,
+            &behavior,
+            now + Duration::seconds(302),
+        );
+        let Stabilization::Apply(params) = result else {
+            panic!("Expected to be applied");
+        };
+        assert_eq!(params["replicas"], json!(5.0));
+    }
+
+    #[test]
+    fn test_scale_up_without_window() {
+        let behavior = get_behavior(0, 300);
+        let mut stabilizer = Stabilizer::new();
+        let now = Utc::now();
+
+        recommend(&mut stabilizer, 3.0, &behavior, now);
+        stabilizer.record_applied(&get_params(3.0));
+
+        // Scaling up is applied immediately
+        let result = recommend(&mut stabilizer, 10.0, &behavior, now + Duration::seconds(1));
+        let Stabilization::Apply(params) = result else {
+            panic!("Expected to be applied");
+        };
+        assert_eq!(params["replicas"], json!(10.0));
+    }
+
+    #[test]
+    fn test_scale_up_stabilization() {
+        let behavior = get_behavior(60, 0);
+        let mut stabilizer = Stabilizer::new();
+        let now = Utc::now();
+
+        recommend(&mut stabilizer, 3.0, &behavior, now);
+        stabilizer.record_applied(&get_params(3.0));
+
+        // The lowest recommendation in the window is the last applied value
+        let result = recommend(
+            &mut stabilizer,
+            10.0,
+            &behavior,
+            now + Duration::seconds(10),
+        );
+        let Stabilization::Suppressed(message) = result else {
+            panic!("Expected to be suppressed");
+        };
+        assert!(message.contains("scale_up stabilization window (60 seconds)"));
+
+        // After the window, the lowest recommendation is 10
+        let result = recommend(
+            &mut stabilizer,
+            10.0,
+            &behavior,
+            now + Duration::seconds(61),
+        );
+        let Stabilization::Apply(params) = result else {
+            panic!("Expected to be applied");
+        };
+        assert_eq!(params["replicas"], json!(10.0));
+    }
+}
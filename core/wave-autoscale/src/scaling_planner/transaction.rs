@@ -0,0 +1,353 @@
+use crate::scaling_component::SharedScalingComponentManager;
+use anyhow::Result;
+use data_layer::types::plan_item_definition::{ApplyMode, PlanItemDefinition};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::{error, info, warn};
+
+// Whether the scaling components of the plan item are applied in order with the rollback on failure
+pub fn is_transactional(plan_item: &PlanItemDefinition) -> bool {
+    plan_item.apply_mode == Some(ApplyMode::Transactional)
+}
+
+/**
+ * TransactionResult
+ * The result of applying the scaling components of a transactional plan item
+ * - results: the result of each scaling component. Only the scaling components that stay applied are Ok.
+ * - steps: what happened to each scaling component. The whole sequence is recorded as one plan log.
+ * - fail_message: why the execution stopped (None if every scaling component was applied)
+ */
+#[derive(Debug)]
+pub struct TransactionResult {
+    pub results: Vec<Result<HashMap<String, Value>>>,
+    pub steps: Vec<Value>,
+    pub fail_message: Option<String>,
+}
+
+impl TransactionResult {
+    // The plan log of the whole sequence
+    pub fn to_plan_log_metadata(&self) -> HashMap<String, Value> {
+        HashMap::from([("steps".to_string(), json!(self.steps))])
+    }
+}
+
+fn get_component_id(params: &HashMap<String, Value>) -> String {
+    params
+        .get("component_id")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/**
+Apply the resolved params of the scaling components in the declared order
+1. The current params of every scaling component are captured. If one of them can't be captured, nothing is applied.
+2. The scaling components are applied one by one. The execution stops at the first failure.
+3. The scaling components that were applied before the failure are reverted to the captured params in the reverse order.
+ */
+pub async fn apply_transaction(
+    scaling_components: Vec<HashMap<String, Value>>,
+    shared_scaling_component_manager: &SharedScalingComponentManager,
+    context: rquickjs::AsyncContext,
+) -> TransactionResult {
+    // 1. Capture the current params before anything is applied
+    // The lock of the manager is not held while applying (ScalingComponentApplier)
+    let mut appliers = Vec::new();
+    let mut previous_params = Vec::new();
+    for params in scaling_components.iter() {
+        let component_id = get_component_id(params);
+        let applier = shared_scaling_component_manager
+            .read()
+            .await
+            .get_applier(component_id.as_str());
+        let current_params = match applier {
+            Ok(applier) => applier
+                .get_current_params(params)
+                .await
+                .map(|current_params| (applier, current_params)),
+            Err(error) => Err(error),
+        };
+        match current_params {
+            Ok((applier, current_params)) => {
+                appliers.push(applier);
+                previous_params.push(current_params);
+            }
+            Err(error) => {
+                let fail_message = format!(
+                    "Failed to capture the current params of {}. Nothing was applied. {}",
+                    component_id, error
+                );
+                error!("[ScalingPlanner] {}", fail_message);
+                let steps = scaling_components
+                    .iter()
+                    .map(|params| {
+                        json!({
+                            "component_id": get_component_id(params),
+                            "params": params,
+                            "status": "not_applied",
+                        })
+                    })
+                    .collect();
+                return TransactionResult {
+                    results: scaling_components
+                        .iter()
+                        .map(|_| Err(anyhow::anyhow!(fail_message.clone())))
+                        .collect(),
+                    steps,
+                    fail_message: Some(fail_message),
+                };
+            }
+        }
+    }
+
+    // 2. Apply in order until the first failure
+    let mut applied = Vec::new();
+    let mut failure: Option<(usize, String)> = None;
+    for (index, params) in scaling_components.iter().enumerate() {
+        match appliers[index].apply(params.clone(), context.clone()).await {
+            Ok(result) => applied.push(result),
+            Err(error) => {
+                failure = Some((index, error.to_string()));
+                break;
+            }
+        }
+    }
+    let Some((failed_index, error)) = failure else {
+        let steps = applied
+            .iter()
+            .enumerate()
+            .map(|(index, result)| {
+                json!({
+                    "component_id": get_component_id(&scaling_components[index]),
+                    "params": result,
+                    "previous_params": previous_params[index],
+                    "status": "applied",
+                })
+            })
+            .collect();
+        return TransactionResult {
+            results: applied.into_iter().map(Ok).collect(),
+            steps,
+            fail_message: None,
+        };
+    };
+
+    // 3. Revert the applied scaling components in the reverse order
+    let failed_component_id = get_component_id(&scaling_components[failed_index]);
+    warn!(
+        "[ScalingPlanner] Failed to apply {}. Revert {} scaling components. {}",
+        failed_component_id,
+        applied.len(),
+        error
+    );
+    let mut steps = vec![Value::Null; scaling_components.len()];
+    let mut revert_failures = Vec::new();
+    for (index, result) in applied.iter().enumerate().rev() {
+        let component_id = get_component_id(&scaling_components[index]);
+        let revert_result = appliers[index]
+            .apply(previous_params[index].clone(), context.clone())
+            .await;
+        steps[index] = match revert_result {
+            Ok(_) => {
+                info!("[ScalingPlanner] Reverted {}", component_id);
+                json!({
+                    "component_id": component_id,
+                    "params": result,
+                    "previous_params": previous_params[index],
+                    "status": "reverted",
+                })
+            }
+            Err(revert_error) => {
+                error!(
+                    "[ScalingPlanner] Failed to revert {}. {}",
+                    component_id, revert_error
+                );
+                revert_failures.push(component_id.clone());
+                json!({
+                    "component_id": component_id,
+                    "params": result,
+                    "previous_params": previous_params[index],
+                    "status": "revert_failed",
+                    "error": revert_error.to_string(),
+                })
+            }
+        };
+    }
+    steps[failed_index] = json!({
+        "component_id": failed_component_id,
+        "params": scaling_components[failed_index],
+        "previous_params": previous_params[failed_index],
+        "status": "failed",
+        "error": error,
+    });
+    for (index, params) in scaling_components.iter().enumerate().skip(failed_index + 1) {
+        steps[index] = json!({
+            "component_id": get_component_id(params),
+            "params": params,
+            "status": "not_applied",
+        });
+    }
+
+    let mut fail_message = format!(
+        "Failed to apply {}. {} scaling components before it were reverted. {}",
+        failed_component_id,
+        applied.len() - revert_failures.len(),
+        error
+    );
+    if !revert_failures.is_empty() {
+        fail_message = format!(
+            "{} Failed to revert {}.",
+            fail_message,
+            revert_failures.join(", ")
+        );
+    }
+    TransactionResult {
+        results: scaling_components
+            .iter()
+            .map(|_| Err(anyhow::anyhow!(fail_message.clone())))
+            .collect(),
+        steps,
+        fail_message: Some(fail_message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scaling_component::{ScalingComponent, ScalingComponentManager};
+    use crate::scaling_planner::js_sandbox::{JsSandbox, JsSandboxLimits};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    // A scaling component that records the applied replicas and fails to apply the replicas over max_replicas
+    struct TestComponent {
+        id: String,
+        replicas: Arc<Mutex<i64>>,
+        max_replicas: i64,
+    }
+
+    #[async_trait]
+    impl ScalingComponent for TestComponent {
+        async fn apply(
+            &self,
+            params: HashMap<String, Value>,
+            _context: rquickjs::AsyncContext,
+        ) -> Result<HashMap<String, Value>> {
+            let replicas = params.get("replicas").and_then(Value::as_i64).unwrap_or(0);
+            if replicas > self.max_replicas {
+                return Err(anyhow::anyhow!("Too many replicas"));
+            }
+            *self.replicas.lock().unwrap() = replicas;
+            Ok(params)
+        }
+        fn get_scaling_component_kind(&self) -> &str {
+            "test"
+        }
+        fn get_id(&self) -> &str {
+            &self.id
+        }
+        async fn get_current_params(
+            &self,
+            _params: &HashMap<String, Value>,
+        ) -> Result<HashMap<String, Value>> {
+            Ok(HashMap::from([(
+                "replicas".to_string(),
+                json!(*self.replicas.lock().unwrap()),
+            )]))
+        }
+    }
+
+    fn add_test_component(
+        scaling_component_manager: &mut ScalingComponentManager,
+        id: &str,
+        max_replicas: i64,
+    ) -> Arc<Mutex<i64>> {
+        let replicas = Arc::new(Mutex::new(1));
+        scaling_component_manager.add_scaling_component(Box::new(TestComponent {
+            id: id.to_string(),
+            replicas: replicas.clone(),
+            max_replicas,
+        }));
+        replicas
+    }
+
+    fn get_params(component_id: &str, replicas: i64) -> HashMap<String, Value> {
+        HashMap::from([
+            ("component_id".to_string(), json!(component_id)),
+            ("replicas".to_string(), json!(replicas)),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_apply_transaction() {
+        let sandbox = JsSandbox::new(JsSandboxLimits::default()).await.unwrap();
+        let context = sandbox.new_context().await.unwrap();
+        let shared_scaling_component_manager = ScalingComponentManager::new_shared();
+        let (database, app, worker) = {
+            let mut scaling_component_manager = shared_scaling_component_manager.write().await;
+            (
+                add_test_component(&mut scaling_component_manager, "database", 10),
+                add_test_component(&mut scaling_component_manager, "app", 10),
+                add_test_component(&mut scaling_component_manager, "worker", 10),
+            )
+        };
+
+        // Every scaling component is applied
+        let result = apply_transaction(
+            vec![get_params("database", 3), get_params("app", 5)],
+            &shared_scaling_component_manager,
+            context.clone(),
+        )
+        .await;
+        assert!(result.fail_message.is_none());
+        assert!(result.results.iter().all(|result| result.is_ok()));
+        assert_eq!(result.steps[1]["previous_params"]["replicas"], 1);
+        assert_eq!((*database.lock().unwrap(), *app.lock().unwrap()), (3, 5));
+
+        // The database is reverted because the app fails. The worker is not applied.
+        let result = apply_transaction(
+            vec![
+                get_params("database", 6),
+                get_params("app", 20),
+                get_params("worker", 2),
+            ],
+            &shared_scaling_component_manager,
+            context.clone(),
+        )
+        .await;
+        assert!(result
+            .fail_message
+            .as_ref()
+            .unwrap()
+            .starts_with("Failed to apply app. 1 scaling components before it were reverted."));
+        assert!(result.results.iter().all(|result| result.is_err()));
+        let statuses = result
+            .steps
+            .iter()
+            .map(|step| step["status"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, vec!["reverted", "failed", "not_applied"]);
+        assert_eq!(
+            (
+                *database.lock().unwrap(),
+                *app.lock().unwrap(),
+                *worker.lock().unwrap()
+            ),
+            (3, 5, 1)
+        );
+
+        // Nothing is applied if the current params of a scaling component can't be captured
+        let result = apply_transaction(
+            vec![get_params("database", 4), get_params("unknown", 1)],
+            &shared_scaling_component_manager,
+            context.clone(),
+        )
+        .await;
+        assert!(result
+            .fail_message
+            .as_ref()
+            .unwrap()
+            .starts_with("Failed to capture the current params of unknown"));
+        assert_eq!(*database.lock().unwrap(), 3);
+    }
+}
@@ -1,3 +1,4 @@
+use data_layer::types::plan_log_definition::PlanLogStatus;
 use reqwest::header::HeaderMap;
 use reqwest::Client;
 use std::collections::HashMap;
@@ -11,6 +12,7 @@ pub struct WebhookRequestBody {
     pub plan_item_id: String,
     pub scaling_component_json_str: String,
     pub fail_message: Option<String>,
+    pub status: PlanLogStatus,
 }
 impl WebhookRequestBody {
     pub fn to_http(&self) -> Option<serde_json::Value> {
@@ -29,7 +31,7 @@ impl WebhookRequestBody {
             "plan_id": self.plan_id,
             "plan_item_id": self.plan_item_id,
             "scaling_component": scaling_component,
-            "status": if self.fail_message.is_some() { PlanStatus::Fail.to_string() } else { PlanStatus::Success.to_string() },
+            "status": self.status.to_string(),
             "fail_message": if self.fail_message.is_some() { self.fail_message.clone().unwrap() } else { "".to_string() },
         }))
     }
@@ -59,7 +61,16 @@ impl WebhookRequestBody {
                     "type": "section",
                     "text": {
                         "type": "mrkdwn",
-                        "text": if self.fail_message.is_some() { format!(":X: *FAIL*\nFail Message: *{}*", self.fail_message.clone().unwrap()) } else { ":white_check_mark: *SUCCESS*".to_string() }
+                        "text": match self.status {
+                            PlanLogStatus::Fail => format!(":X: *FAIL*\nFail Message: *{}*", self.fail_message.clone().unwrap_or_default()),
+                            PlanLogStatus::Shadow => ":eyes: *SHADOW*\nWould have applied the scaling components".to_string(),
+                            PlanLogStatus::Success => ":white_check_mark: *SUCCESS*".to_string(),
+                            PlanLogStatus::Suppressed => format!(":zzz: *SUPPRESSED*\n{}", self.fail_message.clone().unwrap_or_default()),
+                            PlanLogStatus::Pending => format!(":hourglass_flowing_sand: *PENDING*\n{}", self.fail_message.clone().unwrap_or_default()),
+                            PlanLogStatus::Skipped => format!(":fast_forward: *SKIPPED*\n{}", self.fail_message.clone().unwrap_or_default()),
+                            PlanLogStatus::RateLimited => format!(":no_entry: *RATE LIMITED*\n{}", self.fail_message.clone().unwrap_or_default()),
+                            PlanLogStatus::PendingApproval => format!(":raising_hand: *PENDING APPROVAL*\n{}", self.fail_message.clone().unwrap_or_default()),
+                        }
                     }
                 },
                 {
@@ -86,19 +97,6 @@ impl WebhookRequestBody {
     }
 }
 
-enum PlanStatus {
-    Success,
-    Fail,
-}
-impl std::fmt::Display for PlanStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            PlanStatus::Success => write!(f, "SUCCESS"),
-            PlanStatus::Fail => write!(f, "FAIL"),
-        }
-    }
-}
-
 pub fn send_webhooks(
     webhooks: Option<Vec<Webhooks>>,
     plan_webhooks: Option<Vec<String>>,
@@ -226,6 +224,7 @@ mod tests {
             scaling_component_json_str: r#"{"component_id":"k8s_node_dp","replicas":"1"}"#
                 .to_string(),
             fail_message: None,
+            status: PlanLogStatus::Success,
         };
         let send_webhook_http = send_webhook_http(webhooks, webhook_request_body).await;
         assert!(send_webhook_http.is_ok());
@@ -248,6 +247,7 @@ mod tests {
             plan_item_id: "test-plan-item-1".to_string(),
             scaling_component_json_str: r#""#.to_string(),
             fail_message: None,
+            status: PlanLogStatus::Success,
         };
         let send_webhook_slack_incoming_webhook =
             send_webhook_slack_incoming_webhook(webhooks, webhook_request_body).await;
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ActionDefinition { id: string, plan_id: string, plan_item_id: string, overrides_json: string | null, status: string, result_json: string | null, error: string | null, created_at: string, started_at: string | null, finished_at: string | null, updated_at: string, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface DefinitionValidationError { document_index: number, plan_item_id: string | null, message: string, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ObjectKind } from "./object-kind";
+
+export interface MaintenanceWindowDefinition { kind: ObjectKind, db_id: string, id: string, description: string | null, start_time: string | null, end_time: string | null, cron_expression: string | null, duration: string | null, timezone: string | null, plan_ids: Array<string>, component_ids: Array<string>, enabled: boolean, }
\ No newline at end of file
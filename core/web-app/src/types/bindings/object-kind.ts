@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type ObjectKind = "Metric" | "ScalingPlan" | "ScalingComponent";
\ No newline at end of file
+export type ObjectKind = "Metric" | "ScalingPlan" | "ScalingComponent" | "MaintenanceWindow";
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface PendingActionDefinition { id: string, plan_db_id: string, plan_id: string, plan_item_id: string, plan_item_json: string, scaling_components_json: string, status: string, created_at: string, expires_at: string, updated_at: string, }
\ No newline at end of file
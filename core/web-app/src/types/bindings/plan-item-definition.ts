@@ -1,3 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApplyMode } from "./apply-mode";
+import type { CatchUpPolicy } from "./catch-up-policy";
+import type { ScalingBehavior } from "./scaling-behavior";
 
-export interface PlanItemDefinition { id: string, description: string | null, expression: string | null, cron_expression: string | null, cool_down: bigint | null, priority: number, scaling_components: Array<any>, ui: any, }
\ No newline at end of file
+export interface PlanItemDefinition { id: string, description: string | null, expression: string | null, cron_expression: string | null, cool_down: bigint | null, priority: number, scaling_components: Array<any>, ui: any, behavior: ScalingBehavior | null, for: string | null, timezone: string | null, catch_up: CatchUpPolicy | null, require_approval: boolean | null, fallback: boolean | null, fallback_after: number | null, apply_mode: ApplyMode | null, }
\ No newline at end of file
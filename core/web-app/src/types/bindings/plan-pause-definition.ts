@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface PlanPauseDefinition { plan_id: string, paused_at: string, paused_until: string | null, updated_at: string, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ScalingRules } from "./scaling-rules";
+
+export interface ScalingBehavior { scale_up: ScalingRules | null, scale_down: ScalingRules | null, }
\ No newline at end of file
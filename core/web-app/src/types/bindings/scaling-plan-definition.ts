@@ -1,5 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ObjectKind } from "./object-kind";
 import type { PlanItemDefinition } from "./plan-item-definition";
+import type { ScalingBehavior } from "./scaling-behavior";
 
-export interface ScalingPlanDefinition { kind: ObjectKind, db_id: string, id: string, metadata: object, variables: object, plans: Array<PlanItemDefinition>, enabled: boolean, }
\ No newline at end of file
+export interface ScalingPlanDefinition { kind: ObjectKind, db_id: string, id: string, metadata: object, variables: object, plans: Array<PlanItemDefinition>, enabled: boolean, behavior: ScalingBehavior | null, }
\ No newline at end of file
@@ -1,4 +1,5 @@
 import { MetricDefinition } from '@/types/bindings/metric-definition';
+import { PlanItemDefinition } from '@/types/bindings/plan-item-definition';
 import { ScalingPlanDefinition } from '@/types/bindings/scaling-plan-definition';
 import { GetJSParamDefinition } from '@/types/get-js-param-definition';
 import * as acorn from 'acorn';
@@ -37,10 +38,13 @@ export function generateScalingPlanDefinition({
 export function serializeScalingPlanDefinition(
   scalingPlanDefinition: ScalingPlanDefinition
 ) {
-  const { kind, id, metadata, plans, enabled, variables } =
+  const { kind, id, metadata, plans, enabled, variables, behavior } =
     scalingPlanDefinition;
   const editedPlans = plans?.map((planItem) => {
-    const { ui, ...rest } = planItem;
+    // next_fire_times is added by the API for the cron expression
+    const { ui, next_fire_times, ...rest } = planItem as PlanItemDefinition & {
+      next_fire_times?: string[];
+    };
     return {
       ...rest,
     };
@@ -52,6 +56,7 @@ export function serializeScalingPlanDefinition(
     variables,
     plans: editedPlans,
     enabled,
+    ...(behavior ? { behavior } : {}),
   });
   return serialized;
 }